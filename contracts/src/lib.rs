@@ -0,0 +1,67 @@
+//! Contracts for rustp written as attributes, e.g.
+//!
+//! ```
+//! use rustp_contracts::{ensures, requires, rustp_assert};
+//!
+//! #[requires(n >= 0)]
+//! #[ensures(return_value == n)]
+//! fn count(n: i32) -> i32 {
+//!     let mut s: i32 = 0;
+//!     #[invariant(s == i)]
+//!     for i in 0..n {
+//!         s = s + 1;
+//!     }
+//!     rustp_assert!(s == n);
+//!     s
+//! }
+//! # assert_eq!(count(3), 3);
+//! ```
+//!
+//! Under normal compilation they don't do anything. Attributes on loops can't be macros on stable Rust,
+//! so `#[invariant(..)]` and `#[variant(..)]` are removed from the body by the function's attributes.
+//! A function with loop conditions and nothing to require or ensure can use `#[requires(true)]`.
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use quote::ToTokens;
+use syn::visit_mut::VisitMut;
+
+/// The precondition of the function
+#[proc_macro_attribute]
+pub fn requires(_condition: TokenStream, item: TokenStream) -> TokenStream {
+    without_loop_conditions(item)
+}
+
+/// The postcondition of the function, the result is `return_value`
+#[proc_macro_attribute]
+pub fn ensures(_condition: TokenStream, item: TokenStream) -> TokenStream {
+    without_loop_conditions(item)
+}
+
+/// An assert proven at its place, the `//%assert` of the comments
+#[proc_macro]
+pub fn rustp_assert(_condition: TokenStream) -> TokenStream {
+    TokenStream::new()
+}
+
+fn without_loop_conditions(item: TokenStream) -> TokenStream {
+    let mut f = syn::parse_macro_input!(item as syn::ItemFn);
+    LoopConditions.visit_item_fn_mut(&mut f);
+    f.into_token_stream().into()
+}
+
+struct LoopConditions;
+
+impl VisitMut for LoopConditions {
+    fn visit_expr_mut(&mut self, e: &mut syn::Expr) {
+        let attrs = match e {
+            syn::Expr::While(x) => &mut x.attrs,
+            syn::Expr::Loop(x) => &mut x.attrs,
+            syn::Expr::ForLoop(x) => &mut x.attrs,
+            _ => return syn::visit_mut::visit_expr_mut(self, e),
+        };
+        attrs.retain(|a| !a.path.is_ident("invariant") && !a.path.is_ident("variant"));
+        syn::visit_mut::visit_expr_mut(self, e);
+    }
+}
@@ -0,0 +1,38 @@
+use rustp_contracts::{ensures, requires, rustp_assert};
+
+#[requires(x > 0 && y > 0)]
+#[ensures(return_value.0 * y + return_value.1 == x)]
+fn div(x: i32, y: i32) -> (i32, i32) {
+    let mut q: i32 = 0;
+    let mut r: i32 = x;
+    #[invariant(q * y + r == x && r >= 0)]
+    #[variant(r)]
+    while r >= y {
+        q += 1;
+        r -= y;
+    }
+    rustp_assert!(r < y);
+    (q, r)
+}
+
+#[requires(true)]
+fn first_zero(x: &[i32]) -> usize {
+    let mut i: usize = 0;
+    #[invariant(i <= x.len())]
+    loop {
+        if i == x.len() || x[i] == 0 {
+            break;
+        }
+        #[invariant(true)]
+        for _j in 0..1 {}
+        i += 1;
+    }
+    i
+}
+
+#[test]
+fn no_op1() {
+    assert_eq!(div(7, 2), (3, 1));
+    assert_eq!(first_zero(&[1, 0, 2]), 1);
+    assert_eq!(first_zero(&[1]), 1);
+}
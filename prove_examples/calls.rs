@@ -0,0 +1,15 @@
+//%precondition x >= 0 && x < 2000
+//%postcondition return_value == x + 1 && return_value > 0
+fn inc(x: i32) -> i32 {
+    (x + 1)
+}
+
+//%precondition y >= 0 && y <= 1000
+//%postcondition return_value == y + 2
+fn inc_twice(y: i32) -> i32 {
+    let a: i32 = inc(y);
+    let b: i32 = inc(a);
+    b
+}
+
+fn main() {}
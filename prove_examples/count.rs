@@ -0,0 +1,14 @@
+//%precondition n >= 0
+//%postcondition return_value == n
+fn count(n: i32) -> i32 {
+    let mut s: i32 = 0;
+
+    //%invariant s == i
+    for i in 0..n {
+        s = s + 1;
+    }
+
+    s
+}
+
+fn main() {}
@@ -0,0 +1,17 @@
+//%precondition *x < 100
+//%postcondition *x == x'old + 1
+fn inc(x: &mut i32) {
+    *x = *x + 1;
+}
+
+//%precondition a < 50
+//%postcondition return_value == a + 2
+fn inc_twice(a: i32) -> i32 {
+    let mut b: i32 = a;
+    inc(&mut b);
+    let r: &mut i32 = &mut b;
+    *r = *r + 1;
+    b
+}
+
+fn main() {}
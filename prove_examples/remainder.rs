@@ -1,5 +1,5 @@
 //%precondition x >= 0 && y > 0
-//%postcondition quo * y + rem == x
+//%postcondition return_value.0 * y + return_value.1 == x
 fn remainder(x: i32, y: i32) -> (i32, i32) {
     // First program proved by Hoare
     let mut quo: i32 = 0;
@@ -1,5 +1,5 @@
 //%precondition true
-//%postcondition x == y'old && y == x'old
+//%postcondition return_value.0 == y'old && return_value.1 == x'old
 fn swap(mut x: i32, mut y: i32) -> (i32, i32) {
     let t: i32 = x;
     x = y;
@@ -0,0 +1,9 @@
+//%precondition i < x.len() && j < x.len()
+//%postcondition x[i] == x'old[j] && x[j] == x'old[i] && (forall k !(k >= 0 && k < x.len() && k != i && k != j) || x[k] == x'old[k])
+fn swap_elements(x: &mut [i32], i: usize, j: usize) {
+    let t: i32 = x[i];
+    x[i] = x[j];
+    x[j] = t;
+}
+
+fn main() {}
@@ -1,5 +1,7 @@
+use crate::diagnostic::Source;
 use std::collections::HashSet;
 use std::fmt;
+use std::sync::Arc;
 
 #[cfg(test)]
 mod tests;
@@ -30,9 +32,43 @@ macro_rules! set_nonmut {
     };
 }
 
+/// Place in the source file
+/// nom only knows how much of the input is left to parse, so that's what is stored,
+/// diagnostic module turns it into the line and column.
+/// Spans are compared like anything else, `same_code` compares the code no matter where it's placed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Span {
+    /// Length of the input left after this place, 0 if the place is unknown
+    pub left: usize,
+}
+
+impl Span {
+    pub fn new(input: &str) -> Self {
+        Span { left: input.len() }
+    }
+
+    pub fn is_known(&self) -> bool {
+        self.left != 0
+    }
+}
+
 #[derive(PartialEq, Clone, Debug)]
 pub struct Program {
     pub content: Vec<Function>,
+    /// The file the program was parsed from, the spans point into it
+    pub source: Option<Arc<Source>>,
+}
+
+impl Program {
+    /// Whether the programs are the same, no matter where their code is placed
+    pub fn same_code(&self, other: &Self) -> bool {
+        self.content.len() == other.content.len()
+            && self
+                .content
+                .iter()
+                .zip(other.content.iter())
+                .all(|(a, b)| a.same_code(b))
+    }
 }
 
 impl fmt::Display for Program {
@@ -63,6 +99,8 @@ pub enum Bool {
     LowerEqual(Expr, Expr),
     GreaterThan(Expr, Expr),
     LowerThan(Expr, Expr),
+    /// Added by the prover, the checks of the formula (e.g. overflows) come from the code at the span
+    Located(Box<Bool>, Span),
 }
 
 impl fmt::Display for Bool {
@@ -82,6 +120,7 @@ impl fmt::Display for Bool {
             Bool::LowerThan(a, b) => write!(f, "{} < {}", a, b),
             Bool::GreaterEqual(a, b) => write!(f, "{} >= {}", a, b),
             Bool::LowerEqual(a, b) => write!(f, "{} <= {}", a, b),
+            Bool::Located(a, _) => write!(f, "{}", a),
         }
     }
 }
@@ -89,12 +128,17 @@ impl fmt::Display for Bool {
 #[derive(PartialEq, Clone, Debug, Hash, Eq)]
 pub enum ProveControl {
     Assert(Bool),
+    /// Only internal, callee's precondition to be checked and postcondition to be assumed at the call site
+    FunctionCall(Bool, Bool),
 }
 
 impl fmt::Display for ProveControl {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             ProveControl::Assert(x) => write!(f, "(assert {})", x),
+            ProveControl::FunctionCall(pre, post) => {
+                write!(f, "(call requires {} ensures {})", pre, post)
+            }
         }
     }
 }
@@ -107,6 +151,7 @@ impl ProveControlFuncs for ProveControl {
     fn get_bool(self) -> Bool {
         match self {
             ProveControl::Assert(a) => a,
+            ProveControl::FunctionCall(pre, post) => Bool::And(Box::new(pre), Box::new(post)),
         }
     }
 }
@@ -114,7 +159,16 @@ impl ProveControlFuncs for ProveControl {
 #[derive(PartialEq, Clone, Debug, Hash, Eq)]
 pub enum Type {
     Bool,
+    I8,
+    I16,
     I32,
+    I64,
+    U8,
+    U16,
+    U32,
+    U64,
+    /// 64 bits wide
+    Usize,
     /// no nested tuples for now, although this can be easily lifted if needed
     Tuple(Vec<Type>),
     Reference(Box<Type>),
@@ -124,19 +178,74 @@ pub enum Type {
     /// Basically same as array, but its length is not known
     ArraySlice(Box<Type>),
     Unit,
+    /// Left out in the source (`let x = 5;`), the typing pass infers it
+    Infer,
 }
 
 impl fmt::Display for Type {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
+            Type::I8 => write!(f, "i8"),
+            Type::I16 => write!(f, "i16"),
             Type::I32 => write!(f, "i32"),
+            Type::I64 => write!(f, "i64"),
+            Type::U8 => write!(f, "u8"),
+            Type::U16 => write!(f, "u16"),
+            Type::U32 => write!(f, "u32"),
+            Type::U64 => write!(f, "u64"),
+            Type::Usize => write!(f, "usize"),
             Type::Bool => write!(f, "bool"),
-            Type::Tuple(a) => write!(f, "Tuple({:?})", a),
-            Type::Reference(a) => write!(f, "&({})", a),
-            Type::ReferenceMutable(a) => write!(f, "mut&({})", a),
-            Type::Array(a, l) => write!(f, "[{};{}]", a, l),
+            Type::Tuple(a) => {
+                let a: Vec<String> = a.iter().map(|x| x.to_string()).collect();
+                write!(f, "({})", a.join(", "))
+            }
+            Type::Reference(a) => write!(f, "&{}", a),
+            Type::ReferenceMutable(a) => write!(f, "&mut {}", a),
+            Type::Array(a, l) => write!(f, "[{}; {}]", a, l),
             Type::ArraySlice(a) => write!(f, "&[{}]", a),
             Type::Unit => write!(f, "()"),
+            Type::Infer => write!(f, "_"),
+        }
+    }
+}
+
+impl Type {
+    /// Width in bits and signedness of the integer types
+    pub fn int(&self) -> Option<(u32, bool)> {
+        match self {
+            Type::I8 => Some((8, true)),
+            Type::I16 => Some((16, true)),
+            Type::I32 => Some((32, true)),
+            Type::I64 => Some((64, true)),
+            Type::U8 => Some((8, false)),
+            Type::U16 => Some((16, false)),
+            Type::U32 => Some((32, false)),
+            Type::U64 => Some((64, false)),
+            Type::Usize => Some((64, false)),
+            _ => None,
+        }
+    }
+
+    pub fn is_int(&self) -> bool {
+        self.int().is_some()
+    }
+
+    /// Type of the elements of an array or a slice, also behind a reference
+    pub fn array_elem(&self) -> Option<&Type> {
+        match self {
+            Type::Array(t, _) | Type::ArraySlice(t) => Some(t),
+            Type::Reference(t) | Type::ReferenceMutable(t) => t.array_elem(),
+            _ => None,
+        }
+    }
+
+    /// The smallest and the largest value of the integer type
+    pub fn int_range(&self) -> Option<(i128, i128)> {
+        let (bits, signed) = self.int()?;
+        if signed {
+            Some((-(1 << (bits - 1)), (1 << (bits - 1)) - 1))
+        } else {
+            Some((0, (1 << bits) - 1))
         }
     }
 }
@@ -167,9 +276,9 @@ impl fmt::Display for Value {
             Value::Tuple(tup) => write!(f, "{:?}", tup),
             Value::Array(arr) => write!(f, "{:?}", arr),
             Value::Unit => write!(f, "()"),
-            Value::Dereference(x) => write!(f, "{:?}", x),
-            Value::Reference(x) => write!(f, "{:?}", x),
-            Value::ReferenceMutable(x) => write!(f, "{:?}", x),
+            Value::Dereference(x) => write!(f, "*{}", x),
+            Value::Reference(x) => write!(f, "&{}", x),
+            Value::ReferenceMutable(x) => write!(f, "&mut {}", x),
             Value::FunctionCall(name, input) => {
                 write!(f, "FunctionCall({}, {:?})", name, input)
             }
@@ -181,22 +290,94 @@ impl fmt::Display for Value {
 }
 
 #[derive(PartialEq, Clone, Debug, Hash, Eq)]
+/// The span is where the command starts in the source file, unknown for the ones added by the prover
 pub enum Command {
-    Binding(Binding),
-    Assignment(Assignment),
-    ProveControl(ProveControl),
-    Block(Block),
+    Binding(Binding, Span),
+    Assignment(Assignment, Span),
+    ProveControl(ProveControl, Span),
+    Block(Block, Span),
     Noop,
+    /// Leave the innermost loop
+    Break(Span),
+    /// Go to the next iteration of the innermost loop
+    Continue(Span),
+    /// Leave the function with the value
+    Return(Value, Span),
+}
+
+impl Command {
+    pub fn span(&self) -> Span {
+        match self {
+            Command::Binding(_, s)
+            | Command::Assignment(_, s)
+            | Command::ProveControl(_, s)
+            | Command::Block(_, s)
+            | Command::Break(s)
+            | Command::Continue(s)
+            | Command::Return(_, s) => *s,
+            Command::Noop => Span::default(),
+        }
+    }
+
+    /// The same command placed at the span
+    pub fn with_span(self, span: Span) -> Self {
+        match self {
+            Command::Binding(x, _) => Command::Binding(x, span),
+            Command::Assignment(x, _) => Command::Assignment(x, span),
+            Command::ProveControl(x, _) => Command::ProveControl(x, span),
+            Command::Block(x, _) => Command::Block(x, span),
+            Command::Noop => Command::Noop,
+            Command::Break(_) => Command::Break(span),
+            Command::Continue(_) => Command::Continue(span),
+            Command::Return(x, _) => Command::Return(x, span),
+        }
+    }
+
+    /// Whether the commands do the same, no matter where they are placed
+    pub fn same_code(&self, other: &Self) -> bool {
+        without_spans(vec![self.clone()]) == without_spans(vec![other.clone()])
+    }
+}
+
+/// The code with all the spans unknown, nested blocks included
+fn without_spans(code: Vec<Command>) -> Vec<Command> {
+    code.into_iter()
+        .map(|x| match x {
+            Command::Binding(Binding::Tuple(vec), _) => {
+                Command::Binding(Binding::Tuple(without_spans(vec)), Span::default())
+            }
+            Command::Block(Block::If(conds, comms, el), _) => Command::Block(
+                Block::If(
+                    conds,
+                    comms.into_iter().map(without_spans).collect(),
+                    without_spans(el),
+                ),
+                Span::default(),
+            ),
+            Command::Block(Block::ForRange(iter, first, last, comms, inv), _) => Command::Block(
+                Block::ForRange(iter, first, last, without_spans(comms), inv),
+                Span::default(),
+            ),
+            Command::Block(Block::While(cond, comms, inv, var), _) => Command::Block(
+                Block::While(cond, without_spans(comms), inv, var),
+                Span::default(),
+            ),
+            x => x.with_span(Span::default()),
+        })
+        .collect()
 }
 
 impl fmt::Display for Command {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Command::Binding(x) => write!(f, "{}", x),
-            Command::Assignment(x) => write!(f, "{}", x),
-            Command::ProveControl(x) => write!(f, "{}", x),
-            Command::Block(x) => write!(f, "{}", x),
+            Command::Binding(x, _) => write!(f, "{}", x),
+            Command::Assignment(x, _) => write!(f, "{}", x),
+            Command::ProveControl(x, _) => write!(f, "{}", x),
+            Command::Block(x, _) => write!(f, "{}", x),
             Command::Noop => write!(f, "noop"),
+            Command::Break(_) => write!(f, "break"),
+            Command::Continue(_) => write!(f, "continue"),
+            Command::Return(x, _) => write!(f, "return {}", x),
         }
     }
 }
@@ -247,6 +428,8 @@ pub enum Variable {
     ArrayElem(String, Box<Value>),
     /// tuple name, index
     TupleElem(String, Box<Value>),
+    /// array name, used as `x.len()`
+    Length(String),
 }
 
 impl fmt::Display for Variable {
@@ -254,15 +437,20 @@ impl fmt::Display for Variable {
         match self {
             Variable::Named(x) => write!(f, "{}", x),
             Variable::Empty => write!(f, "_"),
-            Variable::ArrayElem(a, i) => write!(f, "{}[{}]", a, i),
+            // Elements of an array of tuples are kept as arrays named like `x.0`
+            Variable::ArrayElem(a, i) => match a.split_once('.') {
+                Some((a, j)) => write!(f, "{}[{}].{}", a, i, j),
+                None => write!(f, "{}[{}]", a, i),
+            },
             Variable::TupleElem(a, i) => write!(f, "{}.{}", a, i),
+            Variable::Length(a) => write!(f, "{}.len()", a),
         }
     }
 }
 
 #[derive(PartialEq, Clone, Debug, Eq, Hash)]
 pub enum Expr {
-    Number(i32),
+    Number(i128),
     Value(Box<Value>),
     Op(Box<Expr>, Opcode, Box<Expr>),
 }
@@ -304,7 +492,6 @@ pub enum Block {
     /// vector of conditions for if/elif, vector of vectors of commands for if/elif, vector of commands for else
     If(Vec<Bool>, Vec<Vec<Command>>, Vec<Command>),
     /// iterator's name, first range elem, second range elem, commands, invariant
-    #[allow(dead_code)]
     ForRange(Variable, Value, Value, Vec<Command>, Bool),
     /// condition, commands, invariant, variant
     While(Bool, Vec<Command>, Bool, Expr),
@@ -368,6 +555,63 @@ pub struct Function {
     /// default value is just true
     pub postcondition: Bool,
     pub return_value: Value,
+    /// Place of the value at the end, unknown if there's none
+    pub return_span: Span,
+    pub span: Span,
+    /// Arithmetic the function is proven with, the default one is used if not given
+    pub arith: Option<Arith>,
+    /// Limits of Z3 for the function's obligations, the default ones are used for the ones not given
+    pub limits: Limits,
+}
+
+impl Function {
+    /// Whether the functions are the same, no matter where their code is placed
+    pub fn same_code(&self, other: &Self) -> bool {
+        let strip = |f: &Function| Function {
+            content: without_spans(f.content.clone()),
+            return_span: Span::default(),
+            span: Span::default(),
+            ..f.clone()
+        };
+        strip(self) == strip(other)
+    }
+}
+
+/// How the ints are represented when proving
+#[derive(PartialEq, Clone, Copy, Debug, Hash, Eq)]
+pub enum Arith {
+    /// Unbounded integers, with the variables limited to the ranges of their types
+    Int,
+    /// Bit-vectors of the width of the type, with the overflows
+    Bv,
+}
+
+/// How long Z3 may look for a proof of a single obligation before giving up
+#[derive(PartialEq, Clone, Copy, Debug, Default, Hash, Eq)]
+pub struct Limits {
+    /// In milliseconds
+    pub timeout: Option<u32>,
+    /// Z3's resource limit, unlike the timeout it gives the same results on any machine
+    pub rlimit: Option<u32>,
+}
+
+impl Limits {
+    /// The limits given here, the other ones taken from the defaults
+    pub fn or(self, defaults: Limits) -> Limits {
+        Limits {
+            timeout: self.timeout.or(defaults.timeout),
+            rlimit: self.rlimit.or(defaults.rlimit),
+        }
+    }
+}
+
+impl fmt::Display for Arith {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Arith::Int => write!(f, "int"),
+            Arith::Bv => write!(f, "bv"),
+        }
+    }
 }
 
 impl fmt::Display for Function {
@@ -399,9 +643,37 @@ impl PreconditionCreator for Function {
     fn update_precondition(self) -> Self {
         let mut t = self;
         let inputs = t.input.clone();
-        let mut vars = HashSet::new();
+        // In the order of the inputs, so the precondition is the same on every parse
+        let mut vars: Vec<Variable> = Vec::new();
+        let mut arrays = Vec::new();
         for i in inputs {
-            vars.extend(i.get_affected_variables());
+            match i {
+                // Arrays get a snapshot of the whole array, arrays of tuples one for each element
+                Binding::Declaration(Variable::Named(x), t, _) if t.array_elem().is_some() => {
+                    match t.array_elem() {
+                        Some(Type::Tuple(types)) => {
+                            arrays.extend((0..types.len()).map(|j| format!("{}.{}", x, j)))
+                        }
+                        _ => arrays.push(x),
+                    }
+                }
+                // Tuples are compared element by element
+                Binding::Declaration(Variable::Named(x), Type::Tuple(types), _) => {
+                    for j in 0..types.len() {
+                        vars.push(Variable::TupleElem(
+                            x.clone(),
+                            Box::new(Value::Expr(Expr::Number(j as i128))),
+                        ));
+                    }
+                }
+                _ => {
+                    for v in i.get_affected_variables() {
+                        if !vars.contains(&v) {
+                            vars.push(v);
+                        }
+                    }
+                }
+            }
         }
 
         // Actually add the 'old == original assertions
@@ -409,11 +681,26 @@ impl PreconditionCreator for Function {
         for i in vars {
             t.precondition = Bool::And(Box::new(t.precondition), Box::new(i.old_wrapper()));
         }
+        for x in arrays {
+            let old = Bool::ValueEqual(
+                Box::new(Value::Variable(Variable::Named(x.clone()))),
+                Box::new(Value::Variable(Variable::Named(old_name(&x)))),
+            );
+            t.precondition = Bool::And(Box::new(t.precondition), Box::new(old));
+        }
 
         t
     }
 }
 
+/// `x'old` for `x`, the elements of an array of tuples `x.0` get `x'old.0`
+pub fn old_name(x: &str) -> String {
+    match x.split_once('.') {
+        Some((name, j)) => format!("{}'old.{}", name, j),
+        None => x.to_string() + "'old",
+    }
+}
+
 /// Create a bool condition setting var == var'old
 trait OldWrapper {
     fn old_wrapper(self) -> Bool;
@@ -429,12 +716,27 @@ impl OldWrapper for Variable {
                 )))),
             ),
             Variable::Empty => Bool::True,
-            Variable::ArrayElem(_, _a) => {
-                unimplemented!();
-            }
-            Variable::TupleElem(_, _a) => {
-                unimplemented!();
-            }
+            Variable::ArrayElem(x, a) => Bool::Equal(
+                Expr::Value(Box::new(Value::Variable(Variable::ArrayElem(
+                    x.clone(),
+                    a.clone(),
+                )))),
+                Expr::Value(Box::new(Value::Variable(Variable::ArrayElem(
+                    old_name(&x),
+                    a,
+                )))),
+            ),
+            Variable::TupleElem(x, a) => Bool::Equal(
+                Expr::Value(Box::new(Value::Variable(Variable::TupleElem(
+                    x.clone(),
+                    a.clone(),
+                )))),
+                Expr::Value(Box::new(Value::Variable(Variable::TupleElem(
+                    x + "'old",
+                    a,
+                )))),
+            ), // The lengths never change
+            Variable::Length(_) => Bool::True,
         }
     }
 }
@@ -525,11 +827,12 @@ impl AffectedVarGetter for Block {
 impl AffectedVarGetter for Command {
     fn get_affected_variables(self) -> HashSet<Variable> {
         match self {
-            Command::Binding(a) => a.get_affected_variables(),
-            Command::Assignment(a) => a.get_affected_variables(),
-            Command::ProveControl(_) => HashSet::new(),
-            Command::Block(a) => a.get_affected_variables(),
+            Command::Binding(a, _) => a.get_affected_variables(),
+            Command::Assignment(a, _) => a.get_affected_variables(),
+            Command::ProveControl(_, _) => HashSet::new(),
+            Command::Block(a, _) => a.get_affected_variables(),
             Command::Noop => HashSet::new(),
+            Command::Break(_) | Command::Continue(_) | Command::Return(_, _) => HashSet::new(),
         }
     }
 }
@@ -665,7 +968,7 @@ impl VarGetter for Bool {
 
                 t
             }
-            Bool::Not(a) => a.get_variables(),
+            Bool::Not(a) | Bool::Located(a, _) => a.get_variables(),
             Bool::Value(a) => a.get_variables(),
             Bool::True => HashSet::new(),
             Bool::False => HashSet::new(),
@@ -712,11 +1015,13 @@ impl VarGetter for Bool {
 impl VarGetter for Command {
     fn get_variables(self) -> HashSet<Variable> {
         match self {
-            Command::Binding(a) => a.get_variables(),
-            Command::Assignment(a) => a.get_variables(),
-            Command::ProveControl(a) => a.get_variables(),
-            Command::Block(a) => a.get_variables(),
+            Command::Binding(a, _) => a.get_variables(),
+            Command::Assignment(a, _) => a.get_variables(),
+            Command::ProveControl(a, _) => a.get_variables(),
+            Command::Block(a, _) => a.get_variables(),
             Command::Noop => HashSet::new(),
+            Command::Break(_) | Command::Continue(_) => HashSet::new(),
+            Command::Return(a, _) => a.get_variables(),
         }
     }
 }
@@ -806,6 +1111,7 @@ impl VarGetter for Variable {
                 t.extend(a.get_variables());
                 t
             }
+            Variable::Length(_) => set!(self),
         }
     }
 }
@@ -833,6 +1139,7 @@ impl Swapper for Bool {
                 Box::new(b.swap(var, val)),
             ),
             Bool::Not(a) => Bool::Not(Box::new(a.swap(var.clone(), val.clone()))),
+            Bool::Located(a, span) => Bool::Located(Box::new(a.swap(var, val)), span),
             Bool::Value(a) => Bool::Value(Box::new(a.swap(var.clone(), val.clone()))),
             Bool::True => Bool::True,
             Bool::False => Bool::False,
@@ -886,6 +1193,7 @@ impl Swapper for Bool {
                 index.clone(),
                 val.clone(),
             ))),
+            Bool::Located(a, span) => Bool::Located(Box::new(a.index_swap(name, index, val)), span),
             Bool::True => Bool::True,
             Bool::False => Bool::False,
             Bool::ValueEqual(a, b) => Bool::ValueEqual(
@@ -968,9 +1276,55 @@ impl Swapper for Value {
                         }
                     }
                     Variable::ArrayElem(name, index) => {
-                        Value::Variable(Variable::ArrayElem(name, Box::new(index.swap(var, val))))
+                        let index = Box::new(index.swap(var.clone(), val.clone()));
+                        // Renaming the whole array renames its elements
+                        match (var, val) {
+                            (Variable::Named(x), Value::Variable(Variable::Named(y)))
+                                if x == name =>
+                            {
+                                Value::Variable(Variable::ArrayElem(y, index))
+                            }
+                            (Variable::Named(x), Value::Variable(Variable::Named(y)))
+                                if name.split_once('.').map(|(a, _)| a) == Some(&x) =>
+                            {
+                                let name = name.replacen(&x, &y, 1);
+                                Value::Variable(Variable::ArrayElem(name, index))
+                            }
+                            _ => Value::Variable(Variable::ArrayElem(name, index)),
+                        }
                     }
-                    Variable::TupleElem(_name, _index) => unimplemented!(),
+                    Variable::TupleElem(name, index) => {
+                        if a == var {
+                            return val;
+                        }
+                        // Replacing the whole tuple replaces its elements
+                        match var {
+                            Variable::Named(x) if x == name => match (val, *index) {
+                                (Value::Variable(Variable::Named(y)), index) => {
+                                    Value::Variable(Variable::TupleElem(y, Box::new(index)))
+                                }
+                                (Value::Tuple(vals), Value::Expr(Expr::Number(i))) => {
+                                    vals[i as usize].clone()
+                                }
+                                // An element of an array of tuples
+                                (
+                                    Value::Variable(Variable::ArrayElem(y, ind)),
+                                    Value::Expr(Expr::Number(i)),
+                                ) => Value::Variable(Variable::ArrayElem(
+                                    format!("{}.{}", y, i),
+                                    ind,
+                                )),
+                                (val, _) => panic!("Unsupported tuple value: {}", val),
+                            },
+                            _ => Value::Variable(a),
+                        }
+                    }
+                    Variable::Length(name) => match (var, val) {
+                        (Variable::Named(x), Value::Variable(Variable::Named(y))) if x == name => {
+                            Value::Variable(Variable::Length(y))
+                        }
+                        _ => Value::Variable(a),
+                    },
                     Variable::Empty => Value::Variable(a),
                 }
             }
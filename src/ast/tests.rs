@@ -102,17 +102,19 @@ fn get_variables_binding2() {
 fn get_variables_binding3() {
     assert_eq!(
         Binding::Tuple(vec![
-            Command::Binding(Binding::Assignment(
-                Variable::Named(String::from("x")),
-                Type::I32,
-                Value::Variable(Variable::Named(String::from("y"))),
-                false
-            )),
-            Command::Binding(Binding::Declaration(
-                Variable::Named(String::from("x")),
-                Type::Bool,
-                false
-            ))
+            Command::Binding(
+                Binding::Assignment(
+                    Variable::Named(String::from("x")),
+                    Type::I32,
+                    Value::Variable(Variable::Named(String::from("y"))),
+                    false
+                ),
+                Span::default()
+            ),
+            Command::Binding(
+                Binding::Declaration(Variable::Named(String::from("x")), Type::Bool, false),
+                Span::default()
+            )
         ])
         .get_variables(),
         set![
@@ -144,12 +146,15 @@ fn get_variables_block_if1() {
                 String::from("arr"),
                 Box::new(Value::Variable(Variable::Named(String::from("y"))))
             ))))],
-            vec![vec![Command::Binding(Binding::Assignment(
-                Variable::Named(String::from("x")),
-                Type::I32,
-                Value::Unit,
-                false
-            )),]],
+            vec![vec![Command::Binding(
+                Binding::Assignment(
+                    Variable::Named(String::from("x")),
+                    Type::I32,
+                    Value::Unit,
+                    false
+                ),
+                Span::default()
+            ),]],
             Vec::new()
         )
         .get_variables(),
@@ -171,12 +176,15 @@ fn get_variables_block_for1() {
             Variable::Named(String::from("i")),
             Value::Variable(Variable::Named(String::from("first")),),
             Value::Variable(Variable::Named(String::from("second")),),
-            vec![Command::Binding(Binding::Assignment(
-                Variable::Named(String::from("x")),
-                Type::I32,
-                Value::Unit,
-                false
-            )),],
+            vec![Command::Binding(
+                Binding::Assignment(
+                    Variable::Named(String::from("x")),
+                    Type::I32,
+                    Value::Unit,
+                    false
+                ),
+                Span::default()
+            ),],
             Bool::True
         )
         .get_variables(),
@@ -196,12 +204,15 @@ fn get_variables_block_while1() {
             Bool::Value(Box::new(Value::Variable(Variable::Named(String::from(
                 "check"
             ))))),
-            vec![Command::Binding(Binding::Assignment(
-                Variable::Named(String::from("x")),
-                Type::I32,
-                Value::Unit,
-                false
-            )),],
+            vec![Command::Binding(
+                Binding::Assignment(
+                    Variable::Named(String::from("x")),
+                    Type::I32,
+                    Value::Unit,
+                    false
+                ),
+                Span::default()
+            ),],
             Bool::True,
             Expr::Op(
                 Box::new(Expr::Value(Box::new(Value::Variable(Variable::Named(
@@ -244,3 +255,120 @@ fn swap2() {
         Value::Expr(Expr::Number(2))
     );
 }
+
+#[test]
+fn type_int1() {
+    assert_eq!(Type::U8.int(), Some((8, false)));
+    assert_eq!(Type::I64.int(), Some((64, true)));
+    assert_eq!(Type::Bool.int(), None);
+    assert_eq!(Type::I8.int_range(), Some((-128, 127)));
+    assert_eq!(Type::U16.int_range(), Some((0, 65535)));
+    assert_eq!(Type::U64.int_range(), Some((0, u64::MAX as i128)));
+}
+
+#[test]
+fn swap_tuple1() {
+    let elem = |name: &str, i| {
+        Value::Variable(Variable::TupleElem(
+            String::from(name),
+            Box::new(Value::Expr(Expr::Number(i))),
+        ))
+    };
+    let t = Variable::Named(String::from("t"));
+
+    assert_eq!(
+        elem("t", 1).swap(
+            t.clone(),
+            Value::Variable(Variable::Named(String::from("u")))
+        ),
+        elem("u", 1)
+    );
+    assert_eq!(
+        elem("t", 1).swap(
+            t.clone(),
+            Value::Tuple(vec![
+                Value::Expr(Expr::Number(2)),
+                Value::Expr(Expr::Number(3))
+            ])
+        ),
+        Value::Expr(Expr::Number(3))
+    );
+    assert_eq!(
+        elem("t", 0).swap(
+            Variable::TupleElem(String::from("t"), Box::new(Value::Expr(Expr::Number(0)))),
+            Value::Expr(Expr::Number(2))
+        ),
+        Value::Expr(Expr::Number(2))
+    );
+    assert_eq!(
+        elem("t", 0).swap(
+            Variable::Named(String::from("u")),
+            Value::Expr(Expr::Number(2))
+        ),
+        elem("t", 0)
+    );
+}
+
+#[test]
+fn swap_length1() {
+    let len = |name: &str| Value::Variable(Variable::Length(String::from(name)));
+
+    assert_eq!(
+        len("a").swap(
+            Variable::Named(String::from("a")),
+            Value::Variable(Variable::Named(String::from("b")))
+        ),
+        len("b")
+    );
+    assert_eq!(
+        len("a").swap(
+            Variable::Named(String::from("c")),
+            Value::Variable(Variable::Named(String::from("b")))
+        ),
+        len("a")
+    );
+}
+
+#[test]
+fn swap_array_tuple1() {
+    let i = Box::new(Value::Variable(Variable::Named(String::from("i"))));
+    let elem = |name: &str| Value::Variable(Variable::ArrayElem(String::from(name), i.clone()));
+
+    // Renaming the array renames the arrays of the tuple elements
+    assert_eq!(
+        elem("a.1").swap(
+            Variable::Named(String::from("a")),
+            Value::Variable(Variable::Named(String::from("b")))
+        ),
+        elem("b.1")
+    );
+    // A tuple read from the array
+    assert_eq!(
+        Value::Variable(Variable::TupleElem(
+            String::from("t"),
+            Box::new(Value::Expr(Expr::Number(0)))
+        ))
+        .swap(Variable::Named(String::from("t")), elem("a")),
+        elem("a.0")
+    );
+}
+
+#[test]
+fn same_code1() {
+    let at = |left| {
+        Command::Block(
+            Block::While(
+                Bool::True,
+                vec![Command::Break(Span { left })],
+                Bool::True,
+                Expr::Number(0),
+            ),
+            Span { left: left + 1 },
+        )
+    };
+
+    // The same code placed elsewhere
+    assert!(at(5) != at(7));
+    assert!(at(5).same_code(&at(7)));
+    assert!(!at(5).same_code(&Command::Break(Span { left: 5 })));
+}
@@ -0,0 +1,125 @@
+use crate::ast::Span;
+use std::cell::RefCell;
+use std::fmt;
+use std::sync::Arc;
+
+#[cfg(test)]
+mod tests;
+
+/// The file being checked, needed to turn the spans into lines and columns
+#[derive(Debug, PartialEq)]
+pub struct Source {
+    pub name: String,
+    pub content: String,
+}
+
+impl Source {
+    pub fn new(name: &str, content: &str) -> Arc<Self> {
+        Arc::new(Source {
+            name: name.to_string(),
+            content: content.to_string(),
+        })
+    }
+}
+
+/// Line and column of the span (both counted from 1) along with the whole line
+fn locate(content: &str, span: Span) -> Option<(usize, usize, &str)> {
+    if !span.is_known() || span.left > content.len() {
+        return None;
+    }
+
+    let offset = content.len() - span.left;
+    let before = &content[..offset];
+    let line_start = before.rfind('\n').map_or(0, |x| x + 1);
+    let line_end = content[offset..]
+        .find('\n')
+        .map_or(content.len(), |x| offset + x);
+
+    let line = before.matches('\n').count() + 1;
+    let column = before[line_start..].chars().count() + 1;
+    Some((line, column, &content[line_start..line_end]))
+}
+
+/// File, line and column the span points to
+pub fn location(source: Option<&Source>, span: Span) -> Option<(String, usize, usize)> {
+    let source = source?;
+    let (line, column, _) = locate(&source.content, span)?;
+    Some((source.name.clone(), line, column))
+}
+
+/// Format the message like rustc does, pointing to the place in the source
+pub fn format(message: &str, span: Span, source: Option<&Source>) -> String {
+    let mut result = format!("error: {}", message);
+
+    let source = match source {
+        Some(a) => a,
+        None => return result,
+    };
+
+    if let Some((line, column, text)) = locate(&source.content, span) {
+        let gutter = " ".repeat(line.to_string().len());
+        // Keep the tabs, so the marker is placed properly
+        let pad: String = text
+            .chars()
+            .take(column - 1)
+            .map(|x| if x == '\t' { '\t' } else { ' ' })
+            .collect();
+
+        result += &format!("\n{}--> {}:{}:{}", gutter, source.name, line, column);
+        result += &format!("\n{} |", gutter);
+        result += &format!("\n{} | {}", line, text);
+        result += &format!("\n{} | {}^", gutter, pad);
+    }
+
+    result
+}
+
+/// Error found in the program, pointing to the place in the source
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    pub message: String,
+    pub span: Span,
+    /// The file the span points to, the diagnostic is printed without the place if it's not known
+    pub source: Option<Arc<Source>>,
+}
+
+impl Diagnostic {
+    /// The same diagnostic, pointing to the place in the given file
+    pub fn in_source(self, source: &Arc<Source>) -> Self {
+        Diagnostic {
+            source: Some(source.clone()),
+            ..self
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            format(&self.message, self.span, self.source.as_deref())
+        )
+    }
+}
+
+thread_local! {
+    /// Errors reported so far, checks only return whether they passed
+    static ERRORS: RefCell<Vec<Diagnostic>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Report the error pointing to the place in the source
+pub fn error(message: &str, span: Span) {
+    ERRORS.with(|x| {
+        x.borrow_mut().push(Diagnostic {
+            message: message.to_string(),
+            span,
+            source: None,
+        })
+    });
+}
+
+/// Get all the errors reported so far and start over
+pub fn take() -> Vec<Diagnostic> {
+    ERRORS.with(|x| x.borrow_mut().drain(..).collect())
+}
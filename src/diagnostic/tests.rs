@@ -0,0 +1,51 @@
+use crate::diagnostic::*;
+
+static CONTENT: &str = "fn a() {\n    let x: i32 = 1;\n}\n";
+
+#[test]
+fn locate1() {
+    let span = Span::new(&CONTENT[13..]);
+    assert_eq!(locate(CONTENT, span), Some((2, 5, "    let x: i32 = 1;")));
+}
+
+#[test]
+fn locate2() {
+    let span = Span::new(CONTENT);
+    assert_eq!(locate(CONTENT, span), Some((1, 1, "fn a() {")));
+}
+
+#[test]
+fn locate_unknown1() {
+    assert_eq!(locate(CONTENT, Span::default()), None);
+    assert_eq!(locate("", Span::new(CONTENT)), None);
+}
+
+#[test]
+fn format1() {
+    let source = Source::new("a.rs", CONTENT);
+    let span = Span::new(&CONTENT[13..]);
+    assert_eq!(
+        format("variable redeclared: x", span, Some(&source)),
+        "error: variable redeclared: x\n --> a.rs:2:5\n  |\n2 |     let x: i32 = 1;\n  |     ^"
+    );
+    assert_eq!(
+        format("variable redeclared: x", Span::default(), Some(&source)),
+        "error: variable redeclared: x"
+    );
+    assert_eq!(
+        format("variable redeclared: x", span, None),
+        "error: variable redeclared: x"
+    );
+}
+
+#[test]
+fn error1() {
+    take();
+    error("variable redeclared: x", Span::default());
+    error("variable redeclared: y", Span::default());
+
+    let errors = take();
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[1].message, "variable redeclared: y");
+    assert!(take().is_empty());
+}
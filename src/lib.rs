@@ -0,0 +1,163 @@
+//! `rustp` formally verifies (proves) code written in a subset of Rust.
+//!
+//! The usual flow is [`parse`] -> [`validate`] -> [`verify`]:
+//!
+//! ```no_run
+//! let source = std::fs::read_to_string("example.rs").unwrap();
+//! let program = match rustp::parse("example.rs", &source) {
+//!     Ok(program) => program,
+//!     Err(e) => panic!("{}", e),
+//! };
+//!
+//! for error in rustp::validate(&program) {
+//!     println!("{}", error);
+//! }
+//!
+//! let report = rustp::verify(&program, &[]);
+//! println!("{}", report.proven());
+//! ```
+
+pub mod ast;
+pub mod diagnostic;
+mod parser;
+pub mod prover;
+mod typing;
+mod validator;
+
+use diagnostic::Source;
+use std::fmt;
+use std::sync::Arc;
+
+#[cfg(test)]
+mod tests;
+
+pub use ast::{Arith, Limits};
+pub use diagnostic::Diagnostic;
+pub use prover::{errors_json, FunctionReport, Goal, Obligation, Options, Report, Status};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParseError {
+    /// The input is not correct
+    Failed(Diagnostic),
+    /// Only the part of the input was parsed, points to where the parsing stopped
+    NotWhole(Diagnostic),
+    /// The input is correct Rust, but uses something out of the supported subset
+    Unsupported(Diagnostic),
+}
+
+impl ParseError {
+    /// The same error, pointing to the place in the given file
+    fn in_source(self, source: &Arc<Source>) -> Self {
+        match self {
+            ParseError::Failed(d) => ParseError::Failed(d.in_source(source)),
+            ParseError::NotWhole(d) => ParseError::NotWhole(d.in_source(source)),
+            ParseError::Unsupported(d) => ParseError::Unsupported(d.in_source(source)),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::Failed(d) => write!(f, "{}", d),
+            ParseError::NotWhole(d) => write!(f, "{}", d),
+            ParseError::Unsupported(d) => write!(f, "{}", d),
+        }
+    }
+}
+
+/// How the source code is turned into the ast
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Frontend {
+    /// Rust parsed with syn, anything out of the supported subset is reported as such
+    #[default]
+    Syn,
+    /// The hand-written parser of the supported subset
+    Nom,
+}
+
+/// Parse the source code, the name of the file is only used in the diagnostics
+pub fn parse(name: &str, source: &str) -> Result<ast::Program, ParseError> {
+    parse_with(name, source, Frontend::default())
+}
+
+/// Same as [`parse`], but with the given frontend
+pub fn parse_with(
+    name: &str,
+    source: &str,
+    frontend: Frontend,
+) -> Result<ast::Program, ParseError> {
+    let file = Source::new(name, source);
+    let program = match frontend {
+        Frontend::Syn => parser::program_syn(source),
+        Frontend::Nom => program_nom(source),
+    };
+    match program {
+        Ok(program) => Ok(ast::Program {
+            source: Some(file),
+            ..program
+        }),
+        Err(e) => Err(e.in_source(&file)),
+    }
+}
+
+fn program_nom(source: &str) -> Result<ast::Program, ParseError> {
+    match parser::program(source) {
+        Ok((rest, tree)) => {
+            if rest.is_empty() {
+                Ok(tree)
+            } else {
+                Err(ParseError::NotWhole(Diagnostic {
+                    message: String::from("not the whole input was parsed, failed here"),
+                    span: ast::Span::new(rest),
+                    source: None,
+                }))
+            }
+        }
+        Err(nom::Err::Error(a)) | Err(nom::Err::Failure(a)) => {
+            Err(ParseError::Failed(Diagnostic {
+                message: format!("failed to parse ({:?})", a.code),
+                span: ast::Span::new(a.input),
+                source: None,
+            }))
+        }
+        // This case shouldn't happen at all, we're working with whole files, so the whole context is known
+        Err(nom::Err::Incomplete(_)) => Err(ParseError::Failed(Diagnostic {
+            message: String::from("failed to parse, not enough data"),
+            span: ast::Span::default(),
+            source: None,
+        })),
+    }
+}
+
+/// Check that the program can be proven (e.g. no shadowing, no undefined variables, no mismatched types).
+/// Returns the errors found, the program is fine if there are none
+pub fn validate(program: &ast::Program) -> Vec<Diagnostic> {
+    diagnostic::take();
+    if validator::validate(program.clone()) && typing::check(program.clone()) {
+        return Vec::new();
+    }
+
+    let mut errors = diagnostic::take();
+    if errors.is_empty() {
+        errors.push(Diagnostic {
+            message: String::from("validation failed"),
+            span: ast::Span::default(),
+            source: None,
+        });
+    }
+    match &program.source {
+        Some(source) => errors.into_iter().map(|x| x.in_source(source)).collect(),
+        None => errors,
+    }
+}
+
+/// Prove the functions with the given names, or all of them if none are given
+pub fn verify(program: &ast::Program, functions: &[String]) -> Report {
+    prover::verify(program.clone(), functions.to_vec())
+}
+
+/// Same as [`verify`], but with the options other than the default ones
+pub fn verify_with(program: &ast::Program, functions: &[String], options: Options) -> Report {
+    prover::verify_with(program.clone(), functions.to_vec(), options)
+}
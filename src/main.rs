@@ -1,16 +1,12 @@
-mod ast;
-mod parser;
-mod prover;
-mod validator;
-
 use clap::{App, Arg};
 use env_logger::Builder;
+use rustp::{ast, ParseError};
 use std::fs;
 use std::process::{Command, Stdio};
 
 extern crate log;
 
-fn rustc_check(filename: &str) {
+fn rustc_check(filename: &str, format: &str) {
     let status = Command::new("rustc")
         .arg(filename)
         .arg("--out-dir")
@@ -22,65 +18,36 @@ fn rustc_check(filename: &str) {
         .expect("failed to run rustc check!");
 
     if !status.success() {
-        println!("rustc check failed!");
-        println!("make sure that you code is correct Rust");
+        if format == "json" {
+            let e = rustp::Diagnostic {
+                message: String::from(
+                    "rustc check failed, make sure that you code is correct Rust",
+                ),
+                span: ast::Span::default(),
+                source: None,
+            };
+            println!("{}", rustp::errors_json("rustc", &[e]));
+        } else {
+            println!("rustc check failed!");
+            println!("make sure that you code is correct Rust");
+        }
         std::process::exit(1);
     }
 }
 
-fn parse(filename: &str) -> ast::Program {
+fn parse(filename: &str, frontend: rustp::Frontend, format: &str) -> ast::Program {
     let content = fs::read_to_string(filename).expect("Something went wrong reading the file");
-    let t = parser::program(&content);
-    match t {
-        Ok((rest, tree)) => {
-            if rest == "" {
-                tree
-            } else {
-                println!("Not the whole input was parsed.");
-                println!("The part that left is:\n");
-                let mut t: Vec<&str> = rest.lines().rev().collect();
-
-                for _ in 0..20 {
-                    if !t.is_empty() {
-                        println!("{}", t.pop().unwrap());
-                    }
-                }
-                std::process::exit(3);
-            }
-        }
-        Err(nom::Err::Error(a)) => {
-            println!("Failed to parse!");
-            println!("Tag: {:?}", a.code);
-            let mut t: Vec<&str> = a.input.lines().rev().collect();
-
-            println!("Unmatched input:\n");
-            for _ in 0..20 {
-                if !t.is_empty() {
-                    println!("{}", t.pop().unwrap());
-                }
-            }
-            std::process::exit(2);
-        }
-        Err(nom::Err::Failure(a)) => {
-            println!("Failed to parse!");
-            println!("Tag: {:?}", a.code);
-            let mut t: Vec<&str> = a.input.lines().rev().collect();
-
-            println!("Unmatched input:\n");
-            for _ in 0..20 {
-                if !t.is_empty() {
-                    println!("{}", t.pop().unwrap());
-                }
-            }
-            std::process::exit(2);
-        }
-        Err(nom::Err::Incomplete(_)) => {
-            // This case shouldn't happen at all, we're working with files, so the whole context is known
-            println!("Failed to parse!");
-            println!("Not enough data!");
-            std::process::exit(2);
-        }
+    let (e, code) = match rustp::parse_with(filename, &content, frontend) {
+        Ok(tree) => return tree,
+        Err(ParseError::Failed(e)) | Err(ParseError::Unsupported(e)) => (e, 2),
+        Err(ParseError::NotWhole(e)) => (e, 3),
+    };
+    if format == "json" {
+        println!("{}", rustp::errors_json("parse", &[e]));
+    } else {
+        println!("{}", e);
     }
+    std::process::exit(code);
 }
 
 fn setup_logging(level: i32, z3_debug: bool) {
@@ -102,7 +69,16 @@ fn setup_logging(level: i32, z3_debug: bool) {
     log::info!("Debug level: {}", s);
 }
 
-fn args() -> (String, i32, bool, Vec<String>, bool) {
+fn args() -> (
+    String,
+    i32,
+    bool,
+    Vec<String>,
+    bool,
+    String,
+    rustp::Frontend,
+    rustp::Options,
+) {
     let matches = App::new("rustp")
         .version("1.0")
         .author("d0ku <darthtyranus666666@gmail.com>")
@@ -141,6 +117,82 @@ fn args() -> (String, i32, bool, Vec<String>, bool) {
                 .long("function")
                 .multiple(true),
         )
+        .arg(
+            Arg::new("format")
+                .about("the format of the proving results")
+                .takes_value(true)
+                .long("format")
+                .possible_values(&["text", "json"])
+                .default_value("text"),
+        )
+        .arg(
+            Arg::new("frontend")
+                .about("the parser to use: syn for any Rust syntax (with errors for the unsupported constructs) or the hand-written nom one")
+                .takes_value(true)
+                .long("frontend")
+                .possible_values(&["syn", "nom"])
+                .default_value("syn"),
+        )
+        .arg(
+            Arg::new("keep-going")
+                .long("keep-going")
+                .takes_value(false)
+                .about("Check all the proof obligations, instead of stopping at the first failure"),
+        )
+        .arg(
+            Arg::new("arith")
+                .about("the arithmetic to prove with: unbounded integers or bit-vectors of the types' widths (functions can choose their own with //%arith)")
+                .takes_value(true)
+                .long("arith")
+                .possible_values(&["int", "bv"]),
+        )
+        .arg(
+            Arg::new("jobs")
+                .about("how many proof obligations are checked at the same time, the results are the same for any number")
+                .takes_value(true)
+                .short('j')
+                .long("jobs")
+                .validator(|x| match x.parse::<usize>() {
+                    Ok(n) if n > 0 => Ok(()),
+                    _ => Err(String::from("it has to be a positive number")),
+                }),
+        )
+        .arg(
+            Arg::new("timeout")
+                .about("how many milliseconds Z3 may spend on a single obligation before it's reported as unknown (functions can set their own with //%timeout)")
+                .takes_value(true)
+                .long("timeout")
+                .validator(|x| x.parse::<u32>().map(|_| ()).map_err(|x| x.to_string())),
+        )
+        .arg(
+            Arg::new("rlimit")
+                .about("Z3's resource limit for a single obligation, same as the timeout but independent of the machine (functions can set their own with //%rlimit)")
+                .takes_value(true)
+                .long("rlimit")
+                .validator(|x| x.parse::<u32>().map(|_| ()).map_err(|x| x.to_string())),
+        )
+        .arg(
+            Arg::new("emit-smt")
+                .about("write every query given to Z3 to the directory, as an SMT-LIB2 file named after the function and what it checks")
+                .takes_value(true)
+                .long("emit-smt"),
+        )
+        .arg(
+            Arg::new("smt-solver")
+                .about("check the queries with the solver started by the command (e.g. \"z3 -in\" or \"cvc5 --incremental\"), talking SMT-LIB2 to it instead of using the Z3 library")
+                .takes_value(true)
+                .long("smt-solver")
+                .validator(|x| match x.trim() {
+                    "" => Err(String::from("it has to be a command")),
+                    _ => Ok(()),
+                }),
+        )
+        .arg(
+            Arg::new("strict-overflow")
+                .long("strict-overflow")
+                .takes_value(false)
+                .about("Fail the proof if there may be an overflow, instead of only warning about it"),
+        )
         .get_matches();
 
     let filename = matches.value_of("INPUT").unwrap().to_string();
@@ -164,33 +216,111 @@ fn args() -> (String, i32, bool, Vec<String>, bool) {
             functions.push(f.to_string());
         }
     }
-    (filename, verbosity, z3_debug, functions, no_rustc_check)
+    let format = matches.value_of("format").unwrap().to_string();
+    let frontend = match matches.value_of("frontend") {
+        Some("nom") => rustp::Frontend::Nom,
+        _ => rustp::Frontend::Syn,
+    };
+
+    let default = rustp::Options::default();
+    let options = rustp::Options {
+        keep_going: matches.is_present("keep-going"),
+        strict_overflow: matches.is_present("strict-overflow"),
+        arith: match matches.value_of("arith") {
+            Some("int") => rustp::Arith::Int,
+            Some("bv") => rustp::Arith::Bv,
+            _ => default.arith,
+        },
+        jobs: match matches.value_of("jobs") {
+            Some(x) => x.parse().unwrap(),
+            None => default.jobs,
+        },
+        limits: rustp::Limits {
+            timeout: matches.value_of("timeout").map(|x| x.parse().unwrap()),
+            rlimit: matches.value_of("rlimit").map(|x| x.parse().unwrap()),
+        },
+        emit_smt: matches.value_of("emit-smt").map(std::path::PathBuf::from),
+        smt_solver: matches
+            .value_of("smt-solver")
+            .map(|x| x.split_whitespace().map(String::from).collect()),
+    };
+
+    (
+        filename,
+        verbosity,
+        z3_debug,
+        functions,
+        no_rustc_check,
+        format,
+        frontend,
+        options,
+    )
 }
 
-fn validate(input: ast::Program) {
-    let t = validator::validate(input);
-    if !t {
-        println!("Failed validation!");
+fn validate(input: &ast::Program, format: &str) {
+    let errors = rustp::validate(input);
+    if !errors.is_empty() {
+        if format == "json" {
+            println!("{}", rustp::errors_json("validation", &errors));
+        } else {
+            for e in errors {
+                println!("{}", e);
+            }
+            println!("Failed validation!");
+        }
         std::process::exit(4);
     }
 }
 
-fn prove(input: ast::Program, list: Vec<String>) {
-    let proved = prover::prove(input, list);
-    if !proved {
-        println!("Failed to prove!");
-        std::process::exit(5);
+fn prove(input: &ast::Program, list: Vec<String>, format: &str, options: rustp::Options) {
+    if let Some(dir) = &options.emit_smt {
+        fs::create_dir_all(dir).expect("Something went wrong creating the --emit-smt directory");
+    }
+    let report = rustp::verify_with(input, &list, options);
+
+    if format == "json" {
+        println!("{}", report.to_json());
+    } else {
+        for func in report.functions.iter() {
+            for obligation in func.obligations.iter() {
+                if obligation.status != rustp::Status::Proven {
+                    println!("{}", obligation.diagnostic());
+                }
+            }
+        }
+    }
+
+    match report.status() {
+        rustp::Status::Failed => {
+            if format != "json" {
+                println!("Failed to prove!");
+            }
+            std::process::exit(5);
+        }
+        rustp::Status::Unknown => {
+            if format != "json" {
+                println!("Could not decide everything, nothing was disproven though!");
+            }
+            std::process::exit(6);
+        }
+        rustp::Status::Proven => {}
+    }
+    if format != "json" {
+        println!("Successfully proved the whole program!");
+        println!();
     }
-    println!("Successfully proved the whole program!");
 }
 
 fn main() {
-    #[cfg(debug_assertions)]
-    println!("Running a DEBUG version");
-
-    let (_filename, verbosity, z3_debug, functions, no_rustc_check) = args();
+    let (_filename, verbosity, z3_debug, functions, no_rustc_check, format, frontend, options) =
+        args();
     let filename = &_filename.as_str();
 
+    #[cfg(debug_assertions)]
+    if format != "json" {
+        println!("Running a DEBUG version");
+    }
+
     setup_logging(verbosity, z3_debug);
 
     log::info!("Checking file: {}", filename);
@@ -199,12 +329,11 @@ fn main() {
         log::info!("Rustc check explicitly disabled via --no-rustc-check");
     } else {
         log::info!("Checking if the code is valid Rust via rustc");
-        rustc_check(filename);
+        rustc_check(filename, &format);
     }
 
-    let tree = parse(filename);
+    let tree = parse(filename, frontend, &format);
 
-    validate(tree.clone());
-    prove(tree, functions);
-    println!();
+    validate(&tree, &format);
+    prove(&tree, functions, &format, options);
 }
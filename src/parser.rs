@@ -4,8 +4,14 @@ use nom::IResult;
 use crate::ast;
 mod astp;
 mod boolean;
+mod lower;
 mod math;
 
 pub fn program(input: &str) -> IResult<&str, ast::Program> {
     astp::program(input)
 }
+
+/// Parse the Rust syntax with syn and lower it, the conditions are read from the //% comments
+pub fn program_syn(input: &str) -> Result<ast::Program, crate::ParseError> {
+    lower::program(input)
+}
@@ -7,19 +7,30 @@ use nom::{
     branch::alt, bytes::complete::tag, bytes::complete::take_until, bytes::complete::take_while1,
     character::complete::char, character::complete::multispace0, character::complete::newline,
     character::complete::space0, character::complete::space1, combinator::not, combinator::opt,
-    combinator::recognize, multi::many0, multi::many1, sequence::tuple, IResult,
+    combinator::recognize, error::Error, error::ErrorKind, multi::many0, multi::many1,
+    sequence::tuple, IResult,
 };
 
+use std::convert::TryInto;
+
 #[cfg(test)]
 mod tests;
 
-static KEYWORDS: [&'static str; 11] = [
-    "let", "true", "false", "&&", "||", "!", "_", "for", "while", "if", "else",
+static KEYWORDS: [&'static str; 15] = [
+    "let", "true", "false", "&&", "||", "!", "_", "for", "while", "if", "else", "loop", "break",
+    "continue", "return",
 ];
 
 pub fn program(input: &str) -> IResult<&str, ast::Program> {
-    many1(function)(input)
-        .and_then(|(next_input, res)| Ok((next_input, ast::Program { content: res })))
+    many1(function)(input).and_then(|(next_input, res)| {
+        Ok((
+            next_input,
+            ast::Program {
+                content: res,
+                source: None,
+            },
+        ))
+    })
 }
 
 fn function_input(input: &str) -> IResult<&str, ast::Binding> {
@@ -65,6 +76,10 @@ fn function_inputs(input: &str) -> IResult<&str, Vec<ast::Binding>> {
 }
 
 fn precondition(input: &str) -> IResult<&str, ast::Bool> {
+    alt((precondition_comment, |x| condition_attribute("requires", x)))(input)
+}
+
+fn precondition_comment(input: &str) -> IResult<&str, ast::Bool> {
     tuple((
         prove_start,
         tag("precondition"),
@@ -79,6 +94,10 @@ fn precondition(input: &str) -> IResult<&str, ast::Bool> {
 }
 
 fn postcondition(input: &str) -> IResult<&str, ast::Bool> {
+    alt((postcondition_comment, |x| condition_attribute("ensures", x)))(input)
+}
+
+fn postcondition_comment(input: &str) -> IResult<&str, ast::Bool> {
     tuple((
         prove_start,
         tag("postcondition"),
@@ -97,9 +116,14 @@ fn function(input: &str) -> IResult<&str, ast::Function> {
         //many0(tuple((multispace0, comments, multispace0))),
         opt(precondition),
         multispace0,
-        opt(postcondition),
-        multispace0,
-        tag("fn"),
+        tuple((
+            opt(postcondition),
+            multispace0,
+            opt(arith),
+            multispace0,
+            many0(tuple((limit, multispace0))),
+        )),
+        tuple((span, tag("fn"))),
         space1,
         function_name,
         space0,
@@ -111,7 +135,7 @@ fn function(input: &str) -> IResult<&str, ast::Function> {
         space0,
         block,
         multispace0,
-        opt(r_value),
+        opt(tuple((span, r_value))),
         multispace0,
         tag("}"),
         multispace0,
@@ -121,9 +145,8 @@ fn function(input: &str) -> IResult<&str, ast::Function> {
             //   _,
             pre,
             _,
-            post,
-            _,
-            _,
+            (post, _, arith, _, limits),
+            (location, _),
             _,
             name,
             _,
@@ -156,9 +179,9 @@ fn function(input: &str) -> IResult<&str, ast::Function> {
             None => ast::Type::Unit,
         };
 
-        let ret_val = match ret {
+        let (ret_span, ret_val) = match ret {
             Some(a) => a,
-            None => ast::Value::Unit,
+            None => (ast::Span::default(), ast::Value::Unit),
         };
 
         Ok((
@@ -171,6 +194,21 @@ fn function(input: &str) -> IResult<&str, ast::Function> {
                 precondition: pre,
                 postcondition: post,
                 return_value: ret_val,
+                return_span: ret_span,
+                span: location,
+                arith,
+                limits: limits
+                    .into_iter()
+                    .fold(ast::Limits::default(), |l, ((kind, n), _)| match kind {
+                        "timeout" => ast::Limits {
+                            timeout: Some(n),
+                            ..l
+                        },
+                        _ => ast::Limits {
+                            rlimit: Some(n),
+                            ..l
+                        },
+                    }),
             }
             .update_precondition(),
         ))
@@ -181,6 +219,7 @@ fn block(input: &str) -> IResult<&str, Vec<ast::Command>> {
     many0(tuple((
         comments,
         multispace0,
+        span,
         command,
         multispace0,
         comments,
@@ -188,13 +227,18 @@ fn block(input: &str) -> IResult<&str, Vec<ast::Command>> {
     .and_then(|(next_input, res)| {
         let mut result = Vec::new();
         for i in res {
-            let (_, _, temp, _, _) = i;
-            result.push(temp)
+            let (_, _, location, temp, _, _) = i;
+            result.push(temp.with_span(location))
         }
         Ok((next_input, result))
     })
 }
 
+/// Doesn't consume anything, just marks the current place in the input
+fn span(input: &str) -> IResult<&str, ast::Span> {
+    Ok((input, ast::Span::new(input)))
+}
+
 fn comments(input: &str) -> IResult<&str, Vec<&str>> {
     many0(tuple((
         multispace0,
@@ -260,7 +304,11 @@ fn command(input: &str) -> IResult<&str, ast::Command> {
         assignment,
         if_else,
         while_parse,
-        //for_parse,
+        loop_parse,
+        for_parse,
+        break_parse,
+        continue_parse,
+        return_parse,
     ))(input)
 }
 
@@ -269,9 +317,30 @@ fn assignment(input: &str) -> IResult<&str, ast::Command> {
         assignment_tuple_unpack,
         assignment_tuple_single,
         assignment_single,
+        assignment_dereference,
+        assignment_call,
     ))(input)
 }
 
+/// `f(x);`, the result of the call is thrown away
+fn assignment_call(input: &str) -> IResult<&str, ast::Command> {
+    tuple((space0, function_call, space0, char(';')))(input).map(|(next_input, (_, f, _, _))| {
+        (
+            next_input,
+            ast::Command::Assignment(
+                ast::Assignment::Single(ast::Variable::Empty, f),
+                ast::Span::default(),
+            ),
+        )
+    })
+}
+
+/// `*x = y;`, the reference stands for the value it points to, so it's an assignment to `x`
+fn assignment_dereference(input: &str) -> IResult<&str, ast::Command> {
+    tuple((space0, char('*'), assignment_single))(input)
+        .map(|(next_input, (_, _, a))| (next_input, a))
+}
+
 fn assignment_single(input: &str) -> IResult<&str, ast::Command> {
     alt((
         tuple((
@@ -349,7 +418,7 @@ fn assignment_single(input: &str) -> IResult<&str, ast::Command> {
         let (_, v, _, _, _, val, _, _) = res;
         Ok((
             next_input,
-            ast::Command::Assignment(ast::Assignment::Single(v, val)),
+            ast::Command::Assignment(ast::Assignment::Single(v, val), ast::Span::default()),
         ))
     })
 }
@@ -371,7 +440,7 @@ fn assignment_tuple_unpack(input: &str) -> IResult<&str, ast::Command> {
 
             Ok((
                 next_input,
-                ast::Command::Assignment(ast::Assignment::Tuple(result)),
+                ast::Command::Assignment(ast::Assignment::Tuple(result), ast::Span::default()),
             ))
         } else {
             panic!("Incorrect case")
@@ -392,7 +461,7 @@ fn assignment_tuple_single(input: &str) -> IResult<&str, ast::Command> {
             let mut result = Vec::new();
             let mut indexes = Vec::new();
             for i in 0..v.len() {
-                indexes.push(i as i32);
+                indexes.push(i as i128);
             }
             for (var, i) in itertools::izip!(v, indexes) {
                 result.push(ast::Assignment::Single(
@@ -406,7 +475,7 @@ fn assignment_tuple_single(input: &str) -> IResult<&str, ast::Command> {
 
             Ok((
                 next_input,
-                ast::Command::Assignment(ast::Assignment::Tuple(result)),
+                ast::Command::Assignment(ast::Assignment::Tuple(result), ast::Span::default()),
             ))
         } else {
             panic!("Incorrect case")
@@ -414,7 +483,6 @@ fn assignment_tuple_single(input: &str) -> IResult<&str, ast::Command> {
     })
 }
 
-#[allow(dead_code)]
 fn for_parse(input: &str) -> IResult<&str, ast::Command> {
     tuple((
         loop_invariant,
@@ -441,7 +509,10 @@ fn for_parse(input: &str) -> IResult<&str, ast::Command> {
         |(next_input, (inv, _, _, _, iter, _, _, _, start, _, _, _, end, _, _, _, comms, _, _))| {
             Ok((
                 next_input,
-                ast::Command::Block(ast::Block::ForRange(iter, start, end, comms, inv)),
+                ast::Command::Block(
+                    ast::Block::ForRange(iter, start, end, comms, inv),
+                    ast::Span::default(),
+                ),
             ))
         },
     )
@@ -470,12 +541,68 @@ fn while_parse(input: &str) -> IResult<&str, ast::Command> {
             };
             Ok((
                 next_input,
-                ast::Command::Block(ast::Block::While(*c, comms, inv, var)),
+                ast::Command::Block(ast::Block::While(*c, comms, inv, var), ast::Span::default()),
             ))
         },
     )
 }
 
+/// `loop { .. }` is a `while true { .. }`, it can only be left with `break` or `return`
+fn loop_parse(input: &str) -> IResult<&str, ast::Command> {
+    tuple((
+        loop_invariant,
+        opt(loop_variant),
+        space0,
+        tag("loop"),
+        space0,
+        tag("{"),
+        multispace0,
+        block,
+        multispace0,
+        tag("}"),
+    ))(input)
+    .map(|(next_input, (inv, var, _, _, _, _, _, comms, _, _))| {
+        let var = var.unwrap_or(ast::Expr::Number(0));
+        (
+            next_input,
+            ast::Command::Block(
+                ast::Block::While(ast::Bool::True, comms, inv, var),
+                ast::Span::default(),
+            ),
+        )
+    })
+}
+
+fn break_parse(input: &str) -> IResult<&str, ast::Command> {
+    tuple((tag("break"), space0, char(';')))(input)
+        .map(|(next_input, _)| (next_input, ast::Command::Break(ast::Span::default())))
+}
+
+fn continue_parse(input: &str) -> IResult<&str, ast::Command> {
+    tuple((tag("continue"), space0, char(';')))(input)
+        .map(|(next_input, _)| (next_input, ast::Command::Continue(ast::Span::default())))
+}
+
+/// `return x;`, or just `return;` from a function returning nothing
+fn return_parse(input: &str) -> IResult<&str, ast::Command> {
+    alt((return_value, return_unit))(input)
+}
+
+fn return_value(input: &str) -> IResult<&str, ast::Command> {
+    tuple((tag("return"), space1, statement_value))(input).map(|(next_input, (_, _, val))| {
+        (next_input, ast::Command::Return(val, ast::Span::default()))
+    })
+}
+
+fn return_unit(input: &str) -> IResult<&str, ast::Command> {
+    tuple((tag("return"), space0, char(';')))(input).map(|(next_input, _)| {
+        (
+            next_input,
+            ast::Command::Return(ast::Value::Unit, ast::Span::default()),
+        )
+    })
+}
+
 fn if_else(input: &str) -> IResult<&str, ast::Command> {
     tuple((
         single_if,
@@ -498,7 +625,7 @@ fn if_else(input: &str) -> IResult<&str, ast::Command> {
         let mut comms = Vec::new();
 
         match first {
-            ast::Command::Block(b) => match b {
+            ast::Command::Block(b, _) => match b {
                 ast::Block::If(mut con, mut com, _) => {
                     conds.push(con.pop().unwrap());
                     comms.push(com.pop().unwrap());
@@ -511,7 +638,7 @@ fn if_else(input: &str) -> IResult<&str, ast::Command> {
         for elem in rest {
             let (_, _, _, i) = elem;
             match i {
-                ast::Command::Block(b) => match b {
+                ast::Command::Block(b, _) => match b {
                     ast::Block::If(mut con, mut com, _) => {
                         conds.push(con.pop().unwrap());
                         comms.push(com.pop().unwrap());
@@ -529,7 +656,7 @@ fn if_else(input: &str) -> IResult<&str, ast::Command> {
 
         Ok((
             next_input,
-            ast::Command::Block(ast::Block::If(conds, comms, el)),
+            ast::Command::Block(ast::Block::If(conds, comms, el), ast::Span::default()),
         ))
     })
 }
@@ -554,7 +681,7 @@ fn single_if(input: &str) -> IResult<&str, ast::Command> {
         comms.push(b);
 
         let t = ast::Block::If(conds, comms, Vec::new());
-        Ok((next_input, ast::Command::Block(t)))
+        Ok((next_input, ast::Command::Block(t, ast::Span::default())))
     })
 }
 
@@ -568,13 +695,19 @@ fn assert(input: &str) -> IResult<&str, ast::Command> {
             let (_, _, _, a, _) = res;
             (
                 next_input,
-                ast::Command::ProveControl(ast::ProveControl::Assert(*a)),
+                ast::Command::ProveControl(ast::ProveControl::Assert(*a), ast::Span::default()),
             )
         },
     )
 }
 
 fn loop_invariant(input: &str) -> IResult<&str, ast::Bool> {
+    alt((loop_invariant_comment, |x| {
+        condition_attribute("invariant", x)
+    }))(input)
+}
+
+fn loop_invariant_comment(input: &str) -> IResult<&str, ast::Bool> {
     tuple((
         space0,
         prove_start,
@@ -591,6 +724,39 @@ fn loop_invariant(input: &str) -> IResult<&str, ast::Bool> {
 }
 
 fn loop_variant(input: &str) -> IResult<&str, ast::Expr> {
+    alt((loop_variant_comment, loop_variant_attribute))(input)
+}
+
+/// `#[requires(x > 0)]`, the attribute written instead of a //% comment
+fn condition_attribute<'a>(name: &str, input: &'a str) -> IResult<&'a str, ast::Bool> {
+    tuple((
+        space0,
+        tag("#["),
+        tag(name),
+        char('('),
+        boolean::expr,
+        space0,
+        tag(")]"),
+        space0,
+        newline,
+    ))(input)
+    .map(|(next_input, (_, _, _, _, c, _, _, _, _))| (next_input, *c))
+}
+
+fn loop_variant_attribute(input: &str) -> IResult<&str, ast::Expr> {
+    tuple((
+        space0,
+        tag("#[variant("),
+        math::expr,
+        space0,
+        tag(")]"),
+        space0,
+        newline,
+    ))(input)
+    .map(|(next_input, (_, _, e, _, _, _, _))| (next_input, *e))
+}
+
+fn loop_variant_comment(input: &str) -> IResult<&str, ast::Expr> {
     tuple((
         space0,
         prove_start,
@@ -606,6 +772,45 @@ fn loop_variant(input: &str) -> IResult<&str, ast::Expr> {
     })
 }
 
+/// Arithmetic to prove the function with, e.g. //%arith bv
+fn arith(input: &str) -> IResult<&str, ast::Arith> {
+    tuple((
+        prove_start,
+        tag("arith"),
+        space1,
+        alt((tag("int"), tag("bv"))),
+        space0,
+        newline,
+    ))(input)
+    .map(|(next_input, res)| {
+        let (_, _, _, a, _, _) = res;
+        let a = match a {
+            "bv" => ast::Arith::Bv,
+            _ => ast::Arith::Int,
+        };
+        (next_input, a)
+    })
+}
+
+/// Limit of Z3 for the function, //%timeout 5000 (in milliseconds) or //%rlimit 100000
+fn limit(input: &str) -> IResult<&str, (&str, u32)> {
+    tuple((
+        prove_start,
+        alt((tag("timeout"), tag("rlimit"))),
+        space1,
+        take_while1(|a| char::is_digit(a, 10)),
+        space0,
+        newline,
+    ))(input)
+    .and_then(|(next_input, (_, kind, _, n, _, _))| match n.parse() {
+        Ok(n) => Ok((next_input, (kind, n))),
+        Err(_) => Err(nom::Err::Error(nom::error::Error::new(
+            n,
+            nom::error::ErrorKind::Digit,
+        ))),
+    })
+}
+
 fn prove_start(input: &str) -> IResult<&str, &str> {
     tag("//%")(input)
 }
@@ -659,7 +864,7 @@ fn reference_mut(input: &str) -> IResult<&str, ast::Value> {
     })
 }
 
-fn dereference(input: &str) -> IResult<&str, ast::Value> {
+pub fn dereference(input: &str) -> IResult<&str, ast::Value> {
     tuple((tag("*"), space0, variable_val))(input)
         .and_then(|(next_input, (_, _, r))| Ok((next_input, ast::Value::Dereference(Box::new(r)))))
 }
@@ -704,7 +909,13 @@ pub fn variable_val(input: &str) -> IResult<&str, ast::Value> {
 }
 
 fn variable(input: &str) -> IResult<&str, ast::Variable> {
-    alt((variable_tuple_elem, variable_array_elem, variable_single))(input)
+    alt((
+        variable_len,
+        variable_tuple_elem,
+        variable_array_tuple_elem,
+        variable_array_elem,
+        variable_single,
+    ))(input)
 }
 
 pub fn variable_single(input: &str) -> IResult<&str, ast::Variable> {
@@ -713,8 +924,8 @@ pub fn variable_single(input: &str) -> IResult<&str, ast::Variable> {
 }
 
 fn variable_array_elem(input: &str) -> IResult<&str, ast::Variable> {
-    tuple((variable_name, char('['), space0, r_value, space0, char(']')))(input).and_then(
-        |(next_input, (v, _, _, i, _, _))| {
+    tuple((variable_name, char('['), space0, array_index))(input).and_then(
+        |(next_input, (v, _, _, i))| {
             Ok((
                 next_input,
                 ast::Variable::ArrayElem(v.to_string(), Box::new(i)),
@@ -723,12 +934,47 @@ fn variable_array_elem(input: &str) -> IResult<&str, ast::Variable> {
     )
 }
 
+/// Element of an array of tuples, `x[i].0` is kept as the element of the array `x.0`
+fn variable_array_tuple_elem(input: &str) -> IResult<&str, ast::Variable> {
+    tuple((
+        variable_name,
+        char('['),
+        space0,
+        array_index,
+        char('.'),
+        math::number,
+    ))(input)
+    .map(|(next_input, (v, _, _, i, _, j))| {
+        (
+            next_input,
+            ast::Variable::ArrayElem(format!("{}.{}", v, j), Box::new(i)),
+        )
+    })
+}
+
+/// Index along with the closing bracket
+/// A lone variable stays a variable, `x.len() - 1` has to be tried as an expression too
+fn array_index(input: &str) -> IResult<&str, ast::Value> {
+    let closing = |input| tuple((space0, char(']')))(input);
+    alt((tuple((r_value, closing)), tuple((math::expr_val, closing))))(input)
+        .map(|(next_input, (i, _))| (next_input, i))
+}
+
+fn variable_len(input: &str) -> IResult<&str, ast::Variable> {
+    tuple((variable_name, tag(".len()")))(input)
+        .map(|(next_input, (v, _))| (next_input, ast::Variable::Length(v.to_string())))
+}
+
 fn variable_tuple_elem(input: &str) -> IResult<&str, ast::Variable> {
-    tuple((variable_name, char('.'), r_value))(input).and_then(|(next_input, (v, _, i))| {
-        Ok((
+    // Only literal indexes are allowed, so `t.0 + 1` is not parsed as `t.(0 + 1)`
+    tuple((variable_name, char('.'), math::number))(input).map(|(next_input, (v, _, i))| {
+        (
             next_input,
-            ast::Variable::TupleElem(v.to_string(), Box::new(i)),
-        ))
+            ast::Variable::TupleElem(
+                v.to_string(),
+                Box::new(ast::Value::Expr(ast::Expr::Number(i))),
+            ),
+        )
     })
 }
 
@@ -737,6 +983,7 @@ fn binding(input: &str) -> IResult<&str, ast::Command> {
         binding_assignment_tuple_multiple,
         binding_assignment_tuple_single,
         binding_assignment,
+        binding_assignment_inferred,
         binding_declaration_tuple,
         binding_declaration,
     ))(input)
@@ -812,7 +1059,7 @@ fn binding_assignment_tuple_single(input: &str) -> IResult<&str, ast::Command> {
             let mut result = Vec::new();
             let mut vals = Vec::new();
             for i in 0..vars.len() {
-                vals.push(i as i32);
+                vals.push(i as i128);
             }
 
             for (m, v, t, val) in itertools::izip!(muts, vars, types, vals) {
@@ -820,20 +1067,23 @@ fn binding_assignment_tuple_single(input: &str) -> IResult<&str, ast::Command> {
                     Some(_) => true,
                     None => false,
                 };
-                result.push(ast::Command::Binding(ast::Binding::Assignment(
-                    v,
-                    t,
-                    ast::Value::Variable(ast::Variable::TupleElem(
-                        name.clone(),
-                        Box::new(ast::Value::Expr(ast::Expr::Number(val))),
-                    )),
-                    mu,
-                )));
+                result.push(ast::Command::Binding(
+                    ast::Binding::Assignment(
+                        v,
+                        t,
+                        ast::Value::Variable(ast::Variable::TupleElem(
+                            name.clone(),
+                            Box::new(ast::Value::Expr(ast::Expr::Number(val))),
+                        )),
+                        mu,
+                    ),
+                    ast::Span::default(),
+                ));
             }
 
             Ok((
                 next_input,
-                ast::Command::Binding(ast::Binding::Tuple(result)),
+                ast::Command::Binding(ast::Binding::Tuple(result), ast::Span::default()),
             ))
         },
     )
@@ -912,14 +1162,15 @@ fn binding_assignment_tuple_multiple(input: &str) -> IResult<&str, ast::Command>
                     Some(_) => true,
                     None => false,
                 };
-                result.push(ast::Command::Binding(ast::Binding::Assignment(
-                    v, t, val, mu,
-                )));
+                result.push(ast::Command::Binding(
+                    ast::Binding::Assignment(v, t, val, mu),
+                    ast::Span::default(),
+                ));
             }
 
             Ok((
                 next_input,
-                ast::Command::Binding(ast::Binding::Tuple(result)),
+                ast::Command::Binding(ast::Binding::Tuple(result), ast::Span::default()),
             ))
         },
     )
@@ -940,28 +1191,32 @@ fn binding_assignment(input: &str) -> IResult<&str, ast::Command> {
             )),
             tuple((
                 tuple((char(':'), space0, tuple_type, space0)),
-                tuple((char('='), space0, tuple_values, space0, char(';'))),
+                tuple((
+                    char('='),
+                    space0,
+                    alt((tuple_values, function_call, variable_val)),
+                    space0,
+                    char(';'),
+                )),
             )),
             tuple((
                 tuple((char(':'), space0, type_def_bool, space0)),
                 tuple((char('='), space0, boolean::expr_val, space0, char(';'))),
             )),
             tuple((
-                tuple((char(':'), space0, type_def_i32, space0)),
+                tuple((char(':'), space0, type_def_int, space0)),
                 tuple((char('='), space0, math::expr_val, space0, char(';'))),
             )),
-            //tuple((
-            //    tuple((char(':'), space0, type_def_reference, space0)),
-            //    tuple((char('='), space0, reference, space0, char(';'))),
-            //)),
-            //tuple((
-            //    tuple((char(':'), space0, type_def_reference_mut, space0)),
-            //    tuple((char('='), space0, reference_mut, space0, char(';'))),
-            //)),
-            //tuple((
-            //    tuple((char(':'), space0, type_def_single, space0)),
-            //    tuple((char('='), space0, dereference, space0, char(';'))),
-            //)),
+            tuple((
+                tuple((char(':'), space0, type_def, space0)),
+                tuple((
+                    char('='),
+                    space0,
+                    alt((reference_mut, reference, variable_val)),
+                    space0,
+                    char(';'),
+                )),
+            )),
         )),
     ))(input)
     .and_then(|(next_input, x)| {
@@ -972,11 +1227,52 @@ fn binding_assignment(input: &str) -> IResult<&str, ast::Command> {
         };
         Ok((
             next_input,
-            ast::Command::Binding(ast::Binding::Assignment(v, t, exp, mu)),
+            ast::Command::Binding(
+                ast::Binding::Assignment(v, t, exp, mu),
+                ast::Span::default(),
+            ),
         ))
     })
 }
 
+/// Value ending the statement, without a type to tell whether it's an int or a bool
+fn statement_value(input: &str) -> IResult<&str, ast::Value> {
+    alt((
+        tuple((array_content, space0, char(';'))),
+        tuple((tuple_values, space0, char(';'))),
+        tuple((function_call, space0, char(';'))),
+        tuple((reference_mut, space0, char(';'))),
+        tuple((reference, space0, char(';'))),
+        tuple((math::expr_val, space0, char(';'))),
+        tuple((boolean::expr_val, space0, char(';'))),
+    ))(input)
+    .map(|(next_input, (val, _, _))| (next_input, val))
+}
+
+/// `let x = value;`, the type is filled in by the typing pass
+fn binding_assignment_inferred(input: &str) -> IResult<&str, ast::Command> {
+    tuple((
+        space0,
+        tag("let"),
+        space1,
+        opt(tuple((tag("mut"), space1))),
+        variable,
+        space0,
+        char('='),
+        space0,
+        statement_value,
+    ))(input)
+    .map(|(next_input, (_, _, _, m, v, _, _, _, exp))| {
+        (
+            next_input,
+            ast::Command::Binding(
+                ast::Binding::Assignment(v, ast::Type::Infer, exp, m.is_some()),
+                ast::Span::default(),
+            ),
+        )
+    })
+}
+
 fn binding_declaration(input: &str) -> IResult<&str, ast::Command> {
     tuple((
         space0,
@@ -985,19 +1281,23 @@ fn binding_declaration(input: &str) -> IResult<&str, ast::Command> {
         opt(tuple((tag("mut"), space1))),
         variable,
         space0,
-        tuple((char(':'), space0, type_def, space0)),
+        opt(tuple((char(':'), space0, type_def, space0))),
         space0,
         char(';'),
     ))(input)
     .and_then(|(next_input, x)| {
-        let (_, _, _, m, v, _, (_, _, t, _), _, _) = x;
+        let (_, _, _, m, v, _, t, _, _) = x;
+        let t = match t {
+            Some((_, _, t, _)) => t,
+            None => ast::Type::Infer,
+        };
         let mu = match m {
             Some(_) => true,
             None => false,
         };
         Ok((
             next_input,
-            ast::Command::Binding(ast::Binding::Declaration(v, t, mu)),
+            ast::Command::Binding(ast::Binding::Declaration(v, t, mu), ast::Span::default()),
         ))
     })
 }
@@ -1069,12 +1369,15 @@ fn binding_declaration_tuple(input: &str) -> IResult<&str, ast::Command> {
                     Some(_) => true,
                     None => false,
                 };
-                result.push(ast::Command::Binding(ast::Binding::Declaration(v, t, mu)));
+                result.push(ast::Command::Binding(
+                    ast::Binding::Declaration(v, t, mu),
+                    ast::Span::default(),
+                ));
             }
 
             Ok((
                 next_input,
-                ast::Command::Binding(ast::Binding::Tuple(result)),
+                ast::Command::Binding(ast::Binding::Tuple(result), ast::Span::default()),
             ))
         },
     )
@@ -1210,7 +1513,7 @@ fn array_type(input: &str) -> IResult<&str, ast::Type> {
     tuple((
         char('['),
         space0,
-        type_def_single,
+        array_elem_type,
         space0,
         char(';'),
         space0,
@@ -1218,9 +1521,12 @@ fn array_type(input: &str) -> IResult<&str, ast::Type> {
         space0,
         char(']'),
     ))(input)
-    .and_then(|(next_input, (_, _, t, _, _, _, i, _, _))| {
-        Ok((next_input, ast::Type::Array(Box::new(t), i)))
-    })
+    .and_then(
+        |(next_input, (_, _, t, _, _, _, i, _, _))| match i.try_into() {
+            Ok(i) => Ok((next_input, ast::Type::Array(Box::new(t), i))),
+            Err(_) => Err(nom::Err::Failure(Error::new(input, ErrorKind::TooLarge))),
+        },
+    )
 }
 
 fn array_slice_type(input: &str) -> IResult<&str, ast::Type> {
@@ -1231,7 +1537,7 @@ fn array_slice_type(input: &str) -> IResult<&str, ast::Type> {
         space0,
         char('['),
         space0,
-        type_def_single,
+        array_elem_type,
         space0,
         char(']'),
     ))(input)
@@ -1240,6 +1546,11 @@ fn array_slice_type(input: &str) -> IResult<&str, ast::Type> {
     })
 }
 
+/// Arrays of tuples are allowed, arrays of arrays are not
+fn array_elem_type(input: &str) -> IResult<&str, ast::Type> {
+    alt((tuple_type, type_def_single))(input)
+}
+
 fn type_def_reference(input: &str) -> IResult<&str, bool> {
     tag("&")(input).and_then(|(next_input, _)| Ok((next_input, false)))
 }
@@ -1270,23 +1581,37 @@ fn type_def(input: &str) -> IResult<&str, ast::Type> {
 }
 
 fn type_def_single(input: &str) -> IResult<&str, ast::Type> {
-    alt((type_def_bool, type_def_i32))(input)
+    alt((type_def_bool, type_def_int))(input)
 }
 
 fn type_def_bool(input: &str) -> IResult<&str, ast::Type> {
     tag("bool")(input).and_then(|(next_input, _)| Ok((next_input, ast::Type::Bool)))
 }
 
-fn type_def_i32(input: &str) -> IResult<&str, ast::Type> {
-    alt((_type_def_i32, type_def_usize))(input)
-}
-
-fn _type_def_i32(input: &str) -> IResult<&str, ast::Type> {
-    tag("i32")(input).and_then(|(next_input, _)| Ok((next_input, ast::Type::I32)))
-}
-
-fn type_def_usize(input: &str) -> IResult<&str, ast::Type> {
-    // This is pretty low effort way to support the usize type for iterating,
-    // should not really matter for proving that much, we treat everything as Int anyway
-    tag("usize")(input).and_then(|(next_input, _)| Ok((next_input, ast::Type::I32)))
+fn type_def_int(input: &str) -> IResult<&str, ast::Type> {
+    alt((
+        tag("i8"),
+        tag("i16"),
+        tag("i32"),
+        tag("i64"),
+        tag("u8"),
+        tag("u16"),
+        tag("u32"),
+        tag("u64"),
+        tag("usize"),
+    ))(input)
+    .map(|(next_input, t)| {
+        let t = match t {
+            "i8" => ast::Type::I8,
+            "i16" => ast::Type::I16,
+            "i32" => ast::Type::I32,
+            "i64" => ast::Type::I64,
+            "u8" => ast::Type::U8,
+            "u16" => ast::Type::U16,
+            "u32" => ast::Type::U32,
+            "u64" => ast::Type::U64,
+            _ => ast::Type::Usize,
+        };
+        (next_input, t)
+    })
 }
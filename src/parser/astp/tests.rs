@@ -84,12 +84,15 @@ fn function1() {
             == ""
     );
     let mut content = Vec::new();
-    content.push(ast::Command::Binding(ast::Binding::Assignment(
-        ast::Variable::Named("a".to_string()),
-        ast::Type::I32,
-        ast::Value::Expr(ast::Expr::Number(14)),
-        false,
-    )));
+    content.push(ast::Command::Binding(
+        ast::Binding::Assignment(
+            ast::Variable::Named("a".to_string()),
+            ast::Type::I32,
+            ast::Value::Expr(ast::Expr::Number(14)),
+            false,
+        ),
+        ast::Span::default(),
+    ));
     let a = function("fn a () {let a: i32 = 14;}").unwrap().1;
 
     let b = ast::Function {
@@ -100,9 +103,13 @@ fn function1() {
         precondition: ast::Bool::True,
         postcondition: ast::Bool::True,
         return_value: ast::Value::Unit,
+        return_span: ast::Span::default(),
+        span: ast::Span::default(),
+        arith: None,
+        limits: ast::Limits::default(),
     };
 
-    assert_eq!(a, b);
+    assert!(a.same_code(&b));
 }
 
 #[test]
@@ -112,12 +119,15 @@ fn function2() {
     assert!(function("fn a ( ) -> i32{ }").unwrap().0 == "");
     assert!(function("fn  a  (b: bool ) -> bool{ }").unwrap().0 == "");
     let mut content = Vec::new();
-    content.push(ast::Command::Binding(ast::Binding::Assignment(
-        ast::Variable::Named("a".to_string()),
-        ast::Type::I32,
-        ast::Value::Expr(ast::Expr::Number(14)),
-        false,
-    )));
+    content.push(ast::Command::Binding(
+        ast::Binding::Assignment(
+            ast::Variable::Named("a".to_string()),
+            ast::Type::I32,
+            ast::Value::Expr(ast::Expr::Number(14)),
+            false,
+        ),
+        ast::Span::default(),
+    ));
 
     let mut input = Vec::new();
     input.push(ast::Binding::Declaration(
@@ -142,20 +152,27 @@ fn function2() {
         precondition: a.precondition.clone(),
         postcondition: ast::Bool::True,
         return_value: ast::Value::Unit,
+        return_span: ast::Span::default(),
+        span: ast::Span::default(),
+        arith: None,
+        limits: ast::Limits::default(),
     };
 
-    assert_eq!(a, b);
+    assert!(a.same_code(&b));
 }
 
 #[test]
 fn function3() {
     let mut content = Vec::new();
-    content.push(ast::Command::Binding(ast::Binding::Assignment(
-        ast::Variable::Named("a".to_string()),
-        ast::Type::I32,
-        ast::Value::Expr(ast::Expr::Number(14)),
-        false,
-    )));
+    content.push(ast::Command::Binding(
+        ast::Binding::Assignment(
+            ast::Variable::Named("a".to_string()),
+            ast::Type::I32,
+            ast::Value::Expr(ast::Expr::Number(14)),
+            false,
+        ),
+        ast::Span::default(),
+    ));
 
     let mut input = Vec::new();
     input.push(ast::Binding::Declaration(
@@ -188,20 +205,27 @@ fn function3() {
             Box::new(ast::Bool::False),
         ),
         return_value: ast::Value::Unit,
+        return_span: ast::Span::default(),
+        span: ast::Span::default(),
+        arith: None,
+        limits: ast::Limits::default(),
     };
 
-    assert_eq!(a, b);
+    assert!(a.same_code(&b));
 }
 
 #[test]
 fn function4() {
     let mut content = Vec::new();
-    content.push(ast::Command::Binding(ast::Binding::Assignment(
-        ast::Variable::Named("a".to_string()),
-        ast::Type::I32,
-        ast::Value::Expr(ast::Expr::Number(14)),
-        false,
-    )));
+    content.push(ast::Command::Binding(
+        ast::Binding::Assignment(
+            ast::Variable::Named("a".to_string()),
+            ast::Type::I32,
+            ast::Value::Expr(ast::Expr::Number(14)),
+            false,
+        ),
+        ast::Span::default(),
+    ));
 
     let mut input = Vec::new();
     input.push(ast::Binding::Declaration(
@@ -234,9 +258,36 @@ fn function4() {
             Box::new(ast::Bool::False),
         ),
         return_value: ast::Value::Expr(ast::Expr::Number(13)),
+        return_span: ast::Span::default(),
+        span: ast::Span::default(),
+        arith: None,
+        limits: ast::Limits::default(),
     };
 
-    assert_eq!(a, b);
+    assert!(a.same_code(&b));
+}
+
+#[test]
+fn function_span1() {
+    let input = "//%precondition true\nfn a () {\n  let a: i32 = 14;}";
+    let a = function(input).unwrap().1;
+
+    assert_eq!(a.span.left, "fn a () {\n  let a: i32 = 14;}".len());
+    assert_eq!(a.content[0].span().left, "let a: i32 = 14;}".len());
+}
+
+#[test]
+fn function_arith1() {
+    let a = function("//%precondition true\nfn a () {\n}").unwrap().1;
+    assert_eq!(a.arith, None);
+
+    let a = function("//%postcondition true\n//%arith bv\nfn a () {\n}")
+        .unwrap()
+        .1;
+    assert_eq!(a.arith, Some(ast::Arith::Bv));
+
+    let a = function("//%arith int\nfn a () {\n}").unwrap().1;
+    assert_eq!(a.arith, Some(ast::Arith::Int));
 }
 
 #[test]
@@ -249,6 +300,15 @@ fn type_def1() {
     );
 }
 
+#[test]
+fn type_def2() {
+    assert!(type_def("u8").unwrap().1 == ast::Type::U8);
+    assert!(type_def("i16").unwrap().1 == ast::Type::I16);
+    assert!(type_def("u64").unwrap().1 == ast::Type::U64);
+    assert!(type_def("usize").unwrap().1 == ast::Type::Usize);
+    assert!(type_def("[u32; 3]").unwrap().1 == ast::Type::Array(Box::new(ast::Type::U32), 3));
+}
+
 #[test]
 fn block1() {
     assert!(block("let x: i32 = 1;").unwrap().0 == "");
@@ -272,6 +332,16 @@ fn command2() {
     assert!(command("let a: i32 = 14;").unwrap().0 == "");
 }
 
+#[test]
+fn command3() {
+    assert_eq!(
+        command("//%invariant true\nfor i in 0..b {let x: i32 = 1;}")
+            .unwrap()
+            .0,
+        ""
+    );
+}
+
 #[test]
 fn array_content1() {
     assert!(array_content("[1,]").is_err());
@@ -290,6 +360,20 @@ fn array_type1() {
     assert!(array_type("[i32;]").is_err());
     assert!(array_type("[i32;4]").unwrap().1 == ast::Type::Array(Box::new(ast::Type::I32), 4));
     assert!(array_type("[bool;4]").unwrap().1 == ast::Type::Array(Box::new(ast::Type::Bool), 4));
+    assert!(
+        array_type("[(i32, bool); 2]").unwrap().1
+            == ast::Type::Array(
+                Box::new(ast::Type::Tuple(vec![ast::Type::I32, ast::Type::Bool])),
+                2
+            )
+    );
+    assert!(
+        array_slice_type("&mut [(i32, bool)]").unwrap().1
+            == ast::Type::ArraySlice(Box::new(ast::Type::Tuple(vec![
+                ast::Type::I32,
+                ast::Type::Bool
+            ])))
+    );
 }
 
 #[test]
@@ -297,17 +381,23 @@ fn assignment_single1() {
     assert!(assignment_single("a = 12;").unwrap().0 == "");
     assert!(
         assignment_single("b = 12;").unwrap().1
-            == ast::Command::Assignment(ast::Assignment::Single(
-                ast::Variable::Named("b".to_string()),
-                ast::Value::Expr(ast::Expr::Number(12))
-            ))
+            == ast::Command::Assignment(
+                ast::Assignment::Single(
+                    ast::Variable::Named("b".to_string()),
+                    ast::Value::Expr(ast::Expr::Number(12))
+                ),
+                ast::Span::default()
+            )
     );
     assert!(
         assignment_single("b = true;").unwrap().1
-            == ast::Command::Assignment(ast::Assignment::Single(
-                ast::Variable::Named("b".to_string()),
-                ast::Value::Bool(ast::Bool::True)
-            ))
+            == ast::Command::Assignment(
+                ast::Assignment::Single(
+                    ast::Variable::Named("b".to_string()),
+                    ast::Value::Bool(ast::Bool::True)
+                ),
+                ast::Span::default()
+            )
     );
     assert!(assignment_single("a = (12, false, a);").unwrap().0 == "");
 }
@@ -317,13 +407,16 @@ fn assignment_single2() {
     assert!(assignment_single("a[1] = 12;").unwrap().0 == "");
     assert!(
         assignment_single("b[3] = 12;").unwrap().1
-            == ast::Command::Assignment(ast::Assignment::Single(
-                ast::Variable::ArrayElem(
-                    "b".to_string(),
-                    Box::new(ast::Value::Expr(ast::Expr::Number(3)))
+            == ast::Command::Assignment(
+                ast::Assignment::Single(
+                    ast::Variable::ArrayElem(
+                        "b".to_string(),
+                        Box::new(ast::Value::Expr(ast::Expr::Number(3)))
+                    ),
+                    ast::Value::Expr(ast::Expr::Number(12))
                 ),
-                ast::Value::Expr(ast::Expr::Number(12))
-            ))
+                ast::Span::default()
+            )
     );
 
     let mut temp = Vec::new();
@@ -332,11 +425,55 @@ fn assignment_single2() {
     temp.push(ast::Value::Expr(ast::Expr::Number(1)));
     assert!(
         assignment_single("b = [12, 3, 1];").unwrap().1
-            == ast::Command::Assignment(ast::Assignment::Single(
-                ast::Variable::Named("b".to_string(),),
-                ast::Value::Array(temp)
-            ))
+            == ast::Command::Assignment(
+                ast::Assignment::Single(
+                    ast::Variable::Named("b".to_string(),),
+                    ast::Value::Array(temp)
+                ),
+                ast::Span::default()
+            )
+    );
+}
+
+#[test]
+fn assignment_dereference1() {
+    assert_eq!(
+        assignment_dereference("*x = *x + 1;").unwrap().1,
+        ast::Command::Assignment(
+            ast::Assignment::Single(
+                ast::Variable::Named("x".to_string()),
+                ast::Value::Expr(ast::Expr::Op(
+                    Box::new(ast::Expr::Value(Box::new(ast::Value::Dereference(
+                        Box::new(ast::Value::Variable(ast::Variable::Named("x".to_string())))
+                    )))),
+                    ast::Opcode::Add,
+                    Box::new(ast::Expr::Number(1))
+                ))
+            ),
+            ast::Span::default()
+        )
+    );
+    assert!(assignment_dereference("x = 1;").is_err());
+}
+
+#[test]
+fn assignment_call1() {
+    assert_eq!(
+        assignment_call("inc(&mut a);").unwrap().1,
+        ast::Command::Assignment(
+            ast::Assignment::Single(
+                ast::Variable::Empty,
+                ast::Value::FunctionCall(
+                    "inc".to_string(),
+                    vec![ast::Value::ReferenceMutable(Box::new(
+                        ast::Value::Variable(ast::Variable::Named("a".to_string()))
+                    ))]
+                )
+            ),
+            ast::Span::default()
+        )
     );
+    assert_eq!(assignment("  f();").unwrap().0, "");
 }
 
 #[test]
@@ -367,7 +504,7 @@ fn assignment_tuple_unpack1() {
         assignment_tuple_unpack("(x,y, _) = (12, true, false);")
             .unwrap()
             .1
-            == ast::Command::Assignment(ast::Assignment::Tuple(temp))
+            == ast::Command::Assignment(ast::Assignment::Tuple(temp), ast::Span::default())
     );
 }
 
@@ -422,32 +559,41 @@ fn if_else3() {
         ast::Expr::Number(13),
     ));
 
-    comms_1.push(ast::Command::Binding(ast::Binding::Assignment(
-        ast::Variable::Named("b".to_string()),
-        ast::Type::I32,
-        ast::Value::Expr(ast::Expr::Number(3)),
-        false,
-    )));
-    comms_2.push(ast::Command::Binding(ast::Binding::Assignment(
-        ast::Variable::Named("b".to_string()),
-        ast::Type::I32,
-        ast::Value::Expr(ast::Expr::Number(4)),
-        false,
-    )));
+    comms_1.push(ast::Command::Binding(
+        ast::Binding::Assignment(
+            ast::Variable::Named("b".to_string()),
+            ast::Type::I32,
+            ast::Value::Expr(ast::Expr::Number(3)),
+            false,
+        ),
+        ast::Span::default(),
+    ));
+    comms_2.push(ast::Command::Binding(
+        ast::Binding::Assignment(
+            ast::Variable::Named("b".to_string()),
+            ast::Type::I32,
+            ast::Value::Expr(ast::Expr::Number(4)),
+            false,
+        ),
+        ast::Span::default(),
+    ));
 
     comms.push(comms_1);
     comms.push(comms_2);
 
-    el.push(ast::Command::Binding(ast::Binding::Assignment(
-        ast::Variable::Named("b".to_string()),
-        ast::Type::I32,
-        ast::Value::Expr(ast::Expr::Number(1)),
-        false,
-    )));
+    el.push(ast::Command::Binding(
+        ast::Binding::Assignment(
+            ast::Variable::Named("b".to_string()),
+            ast::Type::I32,
+            ast::Value::Expr(ast::Expr::Number(1)),
+            false,
+        ),
+        ast::Span::default(),
+    ));
 
-    let b = ast::Command::Block(ast::Block::If(conds, comms, el));
+    let b = ast::Command::Block(ast::Block::If(conds, comms, el), ast::Span::default());
 
-    assert!(a == b);
+    assert!(a.same_code(&b));
 }
 
 #[test]
@@ -487,6 +633,26 @@ fn loop_invariant1() {
     assert!(loop_invariant("//%invariant true\n").unwrap().0 == "");
 }
 
+#[test]
+fn condition_attribute1() {
+    assert!(precondition("#[requires(false)]\n").unwrap().1 == ast::Bool::False);
+    assert!(
+        postcondition("#[ensures((a || b) && true)]\n").unwrap().1
+            == postcondition("//%postcondition (a || b) && true\n")
+                .unwrap()
+                .1
+    );
+    assert!(loop_invariant("    #[invariant(i < 143)]\n")
+        .unwrap()
+        .0
+        .is_empty());
+    assert!(loop_variant("    #[variant(n - (i + 1))]\n")
+        .unwrap()
+        .0
+        .is_empty());
+    assert!(precondition("#[requires(false\n").is_err());
+}
+
 #[test]
 fn prove_start1() {
     assert!(prove_start("//%").unwrap().0 == "");
@@ -540,12 +706,13 @@ fn variable_4() {
 fn variable_5() {
     assert!(variable("a.").unwrap().0 == ".");
     assert!(variable("a.1").is_ok());
-    assert!(variable("abc.a").unwrap().0 == "");
+    assert!(variable("abc.1").unwrap().0 == "");
+    assert!(variable("abc.1 + 2").unwrap().0 == " + 2");
     assert!(
-        variable("abc.c").unwrap().1
+        variable("abc.2").unwrap().1
             == ast::Variable::TupleElem(
                 "abc".to_string(),
-                Box::new(ast::Value::Variable(ast::Variable::Named("c".to_string())))
+                Box::new(ast::Value::Expr(ast::Expr::Number(2)))
             )
     );
 }
@@ -555,6 +722,11 @@ fn variable_array_elem1() {
     assert!(variable_array_elem("a[]").is_err());
     assert!(variable_array_elem("a[1]").is_ok());
     assert!(variable_array_elem("abc[a]").unwrap().0 == "");
+    assert!(variable_array_elem("abc[a + 1]").unwrap().0.is_empty());
+    assert!(variable_array_elem("abc[abc.len() - 1]")
+        .unwrap()
+        .0
+        .is_empty());
     assert!(
         variable_array_elem("abc[c]").unwrap().1
             == ast::Variable::ArrayElem(
@@ -568,14 +740,40 @@ fn variable_array_elem1() {
 fn variable_tuple_elem1() {
     assert!(variable_tuple_elem("a.").is_err());
     assert!(variable_tuple_elem("a.1").is_ok());
-    assert!(variable_tuple_elem("abc.a").unwrap().0 == "");
+    assert!(variable_tuple_elem("abc.a").is_err());
+    assert!(variable_tuple_elem("abc.1").unwrap().0 == "");
+    assert!(variable_tuple_elem("abc.1 + 2").unwrap().0 == " + 2");
     assert!(
-        variable_tuple_elem("abc.c").unwrap().1
+        variable_tuple_elem("abc.2").unwrap().1
             == ast::Variable::TupleElem(
                 "abc".to_string(),
-                Box::new(ast::Value::Variable(ast::Variable::Named("c".to_string())))
+                Box::new(ast::Value::Expr(ast::Expr::Number(2)))
+            )
+    );
+}
+
+#[test]
+fn variable_array_tuple_elem1() {
+    assert!(variable_array_tuple_elem("a[1]").is_err());
+    assert!(variable_array_tuple_elem("a[1].").is_err());
+    assert!(variable_array_tuple_elem("abc[i].1 + 2").unwrap().0 == " + 2");
+    assert!(
+        variable("abc[i].1").unwrap().1
+            == ast::Variable::ArrayElem(
+                "abc.1".to_string(),
+                Box::new(ast::Value::Variable(ast::Variable::Named("i".to_string())))
             )
     );
+    assert_eq!(variable("abc[i].1").unwrap().1.to_string(), "abc[i].1");
+}
+
+#[test]
+fn variable_len1() {
+    assert!(variable_len("a.len").is_err());
+    assert!(variable_len("a.len()").is_ok());
+    assert!(variable_len("abc.len() - 1").unwrap().0 == " - 1");
+    assert!(variable_len("abc.len()").unwrap().1 == ast::Variable::Length("abc".to_string()));
+    assert!(variable("x.len()").unwrap().1 == ast::Variable::Length("x".to_string()));
 }
 
 #[test]
@@ -587,24 +785,30 @@ fn binding_assignment1() {
     assert!(binding_assignment("let z = 3;").is_err());
     assert_eq!(
         binding_assignment("let mut z: i32 = 3;").unwrap().1,
-        ast::Command::Binding(ast::Binding::Assignment(
-            ast::Variable::Named("z".to_string()),
-            ast::Type::I32,
-            ast::Value::Expr(ast::Expr::Number(3)),
-            true
-        ))
+        ast::Command::Binding(
+            ast::Binding::Assignment(
+                ast::Variable::Named("z".to_string()),
+                ast::Type::I32,
+                ast::Value::Expr(ast::Expr::Number(3)),
+                true
+            ),
+            ast::Span::default()
+        )
     );
     // This one will be validated later on?
     // Or can we just assume that the code we are getting is correct Rust?
     assert!(binding_assignment("let z: bool = 3;").is_err());
     assert_eq!(
         binding_assignment("let z: bool = true;").unwrap().1,
-        ast::Command::Binding(ast::Binding::Assignment(
-            ast::Variable::Named("z".to_string()),
-            ast::Type::Bool,
-            ast::Value::Bool(ast::Bool::True),
-            false
-        ))
+        ast::Command::Binding(
+            ast::Binding::Assignment(
+                ast::Variable::Named("z".to_string()),
+                ast::Type::Bool,
+                ast::Value::Bool(ast::Bool::True),
+                false
+            ),
+            ast::Span::default()
+        )
     );
     assert_eq!(binding_assignment("let c: i32 = a + 43;").unwrap().0, "");
     assert_eq!(
@@ -613,6 +817,39 @@ fn binding_assignment1() {
             .0,
         ""
     );
+    assert_eq!(
+        binding_assignment("let c: (i32, i32) = f(a, b);")
+            .unwrap()
+            .0,
+        ""
+    );
+    assert_eq!(
+        binding_assignment("let r: &mut i32 = &mut a;").unwrap().1,
+        ast::Command::Binding(
+            ast::Binding::Assignment(
+                ast::Variable::Named("r".to_string()),
+                ast::Type::ReferenceMutable(Box::new(ast::Type::I32)),
+                ast::Value::ReferenceMutable(Box::new(ast::Value::Variable(ast::Variable::Named(
+                    "a".to_string()
+                )))),
+                false
+            ),
+            ast::Span::default()
+        )
+    );
+    assert_eq!(binding_assignment("let r: &bool = x;").unwrap().0, "");
+    assert_eq!(
+        binding_assignment("let c: (i32, bool) = d;").unwrap().1,
+        ast::Command::Binding(
+            ast::Binding::Assignment(
+                ast::Variable::Named("c".to_string()),
+                ast::Type::Tuple(vec![ast::Type::I32, ast::Type::Bool]),
+                ast::Value::Variable(ast::Variable::Named("d".to_string())),
+                false
+            ),
+            ast::Span::default()
+        )
+    );
 }
 
 #[test]
@@ -624,12 +861,15 @@ fn binding_assigment2() {
 
     assert!(
         binding_assignment("let x: [i32; 3] = [1,2, 4];").unwrap().1
-            == ast::Command::Binding(ast::Binding::Assignment(
-                ast::Variable::Named("x".to_string()),
-                ast::Type::Array(Box::new(ast::Type::I32), 3),
-                ast::Value::Array(temp),
-                false
-            ))
+            == ast::Command::Binding(
+                ast::Binding::Assignment(
+                    ast::Variable::Named("x".to_string()),
+                    ast::Type::Array(Box::new(ast::Type::I32), 3),
+                    ast::Value::Array(temp),
+                    false
+                ),
+                ast::Span::default()
+            )
     );
 
     let mut temp = Vec::new();
@@ -641,12 +881,15 @@ fn binding_assigment2() {
         binding_assignment("let mut x: [i32; 3] = [1,2, 4];")
             .unwrap()
             .1
-            == ast::Command::Binding(ast::Binding::Assignment(
-                ast::Variable::Named("x".to_string()),
-                ast::Type::Array(Box::new(ast::Type::I32), 3),
-                ast::Value::Array(temp),
-                true
-            ))
+            == ast::Command::Binding(
+                ast::Binding::Assignment(
+                    ast::Variable::Named("x".to_string()),
+                    ast::Type::Array(Box::new(ast::Type::I32), 3),
+                    ast::Value::Array(temp),
+                    true
+                ),
+                ast::Span::default()
+            )
     );
 
     let mut temp = Vec::new();
@@ -658,12 +901,15 @@ fn binding_assigment2() {
         binding_assignment("let mut x: [i32; 3] = [1,2, 4];")
             .unwrap()
             .1
-            == ast::Command::Binding(ast::Binding::Assignment(
-                ast::Variable::Named("x".to_string()),
-                ast::Type::Array(Box::new(ast::Type::I32), 3),
-                ast::Value::Array(temp),
-                true
-            ))
+            == ast::Command::Binding(
+                ast::Binding::Assignment(
+                    ast::Variable::Named("x".to_string()),
+                    ast::Type::Array(Box::new(ast::Type::I32), 3),
+                    ast::Value::Array(temp),
+                    true
+                ),
+                ast::Span::default()
+            )
     );
 }
 
@@ -676,51 +922,63 @@ fn binding_assigment3() {
 
     assert!(
         binding_assignment("let x: [i32; 3] = [1,2, 4];").unwrap().1
-            == ast::Command::Binding(ast::Binding::Assignment(
-                ast::Variable::Named("x".to_string()),
-                ast::Type::Array(Box::new(ast::Type::I32), 3),
-                ast::Value::Array(temp),
-                false
-            ))
+            == ast::Command::Binding(
+                ast::Binding::Assignment(
+                    ast::Variable::Named("x".to_string()),
+                    ast::Type::Array(Box::new(ast::Type::I32), 3),
+                    ast::Value::Array(temp),
+                    false
+                ),
+                ast::Span::default()
+            )
     );
 }
 
 #[test]
 fn binding_assignment_tuple_single1() {
     let mut temp = Vec::new();
-    temp.push(ast::Command::Binding(ast::Binding::Assignment(
-        ast::Variable::Named("x".to_string()),
-        ast::Type::I32,
-        ast::Value::Variable(ast::Variable::TupleElem(
-            "b".to_string(),
-            Box::new(ast::Value::Expr(ast::Expr::Number(0))),
-        )),
-        true,
-    )));
-    temp.push(ast::Command::Binding(ast::Binding::Assignment(
-        ast::Variable::Named("y".to_string()),
-        ast::Type::Bool,
-        ast::Value::Variable(ast::Variable::TupleElem(
-            "b".to_string(),
-            Box::new(ast::Value::Expr(ast::Expr::Number(1))),
-        )),
-        false,
-    )));
-    temp.push(ast::Command::Binding(ast::Binding::Assignment(
-        ast::Variable::Empty,
-        ast::Type::Bool,
-        ast::Value::Variable(ast::Variable::TupleElem(
-            "b".to_string(),
-            Box::new(ast::Value::Expr(ast::Expr::Number(2))),
-        )),
-        false,
-    )));
+    temp.push(ast::Command::Binding(
+        ast::Binding::Assignment(
+            ast::Variable::Named("x".to_string()),
+            ast::Type::I32,
+            ast::Value::Variable(ast::Variable::TupleElem(
+                "b".to_string(),
+                Box::new(ast::Value::Expr(ast::Expr::Number(0))),
+            )),
+            true,
+        ),
+        ast::Span::default(),
+    ));
+    temp.push(ast::Command::Binding(
+        ast::Binding::Assignment(
+            ast::Variable::Named("y".to_string()),
+            ast::Type::Bool,
+            ast::Value::Variable(ast::Variable::TupleElem(
+                "b".to_string(),
+                Box::new(ast::Value::Expr(ast::Expr::Number(1))),
+            )),
+            false,
+        ),
+        ast::Span::default(),
+    ));
+    temp.push(ast::Command::Binding(
+        ast::Binding::Assignment(
+            ast::Variable::Empty,
+            ast::Type::Bool,
+            ast::Value::Variable(ast::Variable::TupleElem(
+                "b".to_string(),
+                Box::new(ast::Value::Expr(ast::Expr::Number(2))),
+            )),
+            false,
+        ),
+        ast::Span::default(),
+    ));
 
     assert!(
         binding_assignment_tuple_single("let (mut x,y, _): (i32, bool, bool) = b;")
             .unwrap()
             .1
-            == ast::Command::Binding(ast::Binding::Tuple(temp))
+            == ast::Command::Binding(ast::Binding::Tuple(temp), ast::Span::default())
     );
 }
 
@@ -751,7 +1009,7 @@ fn assignment_tuple_single1() {
 
     assert!(
         assignment_tuple_single("( x,y, _) = b;").unwrap().1
-            == ast::Command::Assignment(ast::Assignment::Tuple(temp))
+            == ast::Command::Assignment(ast::Assignment::Tuple(temp), ast::Span::default())
     );
 }
 
@@ -776,24 +1034,33 @@ fn binding_assignment_tuple_multiple1() {
             == ""
     );
     let mut temp = Vec::new();
-    temp.push(ast::Command::Binding(ast::Binding::Assignment(
-        ast::Variable::Named("x".to_string()),
-        ast::Type::I32,
-        ast::Value::Expr(ast::Expr::Number(12)),
-        true,
-    )));
-    temp.push(ast::Command::Binding(ast::Binding::Assignment(
-        ast::Variable::Named("y".to_string()),
-        ast::Type::Bool,
-        ast::Value::Bool(ast::Bool::True),
-        false,
-    )));
-    temp.push(ast::Command::Binding(ast::Binding::Assignment(
-        ast::Variable::Empty,
-        ast::Type::Bool,
-        ast::Value::Bool(ast::Bool::False),
-        false,
-    )));
+    temp.push(ast::Command::Binding(
+        ast::Binding::Assignment(
+            ast::Variable::Named("x".to_string()),
+            ast::Type::I32,
+            ast::Value::Expr(ast::Expr::Number(12)),
+            true,
+        ),
+        ast::Span::default(),
+    ));
+    temp.push(ast::Command::Binding(
+        ast::Binding::Assignment(
+            ast::Variable::Named("y".to_string()),
+            ast::Type::Bool,
+            ast::Value::Bool(ast::Bool::True),
+            false,
+        ),
+        ast::Span::default(),
+    ));
+    temp.push(ast::Command::Binding(
+        ast::Binding::Assignment(
+            ast::Variable::Empty,
+            ast::Type::Bool,
+            ast::Value::Bool(ast::Bool::False),
+            false,
+        ),
+        ast::Span::default(),
+    ));
 
     assert_eq!(
         binding_assignment_tuple_multiple(
@@ -801,35 +1068,44 @@ fn binding_assignment_tuple_multiple1() {
         )
         .unwrap()
         .1,
-        ast::Command::Binding(ast::Binding::Tuple(temp))
+        ast::Command::Binding(ast::Binding::Tuple(temp), ast::Span::default())
     );
 
     let mut temp = Vec::new();
-    temp.push(ast::Command::Binding(ast::Binding::Assignment(
-        ast::Variable::Named("x".to_string()),
-        ast::Type::I32,
-        ast::Value::Expr(ast::Expr::Number(12)),
-        true,
-    )));
-    temp.push(ast::Command::Binding(ast::Binding::Assignment(
-        ast::Variable::Named("y".to_string()),
-        ast::Type::Bool,
-        ast::Value::Bool(ast::Bool::True),
-        false,
-    )));
-    temp.push(ast::Command::Binding(ast::Binding::Assignment(
-        ast::Variable::Empty,
-        ast::Type::Bool,
-        ast::Value::Bool(ast::Bool::False),
-        false,
-    )));
+    temp.push(ast::Command::Binding(
+        ast::Binding::Assignment(
+            ast::Variable::Named("x".to_string()),
+            ast::Type::I32,
+            ast::Value::Expr(ast::Expr::Number(12)),
+            true,
+        ),
+        ast::Span::default(),
+    ));
+    temp.push(ast::Command::Binding(
+        ast::Binding::Assignment(
+            ast::Variable::Named("y".to_string()),
+            ast::Type::Bool,
+            ast::Value::Bool(ast::Bool::True),
+            false,
+        ),
+        ast::Span::default(),
+    ));
+    temp.push(ast::Command::Binding(
+        ast::Binding::Assignment(
+            ast::Variable::Empty,
+            ast::Type::Bool,
+            ast::Value::Bool(ast::Bool::False),
+            false,
+        ),
+        ast::Span::default(),
+    ));
 
     assert!(
         binding_assignment_tuple_multiple(
             "let (mut x,y, _): (i32, bool, bool) = (12, true, false);"
         )
         .unwrap()
-        .1 == ast::Command::Binding(ast::Binding::Tuple(temp))
+        .1 == ast::Command::Binding(ast::Binding::Tuple(temp), ast::Span::default())
     );
 }
 
@@ -840,67 +1116,145 @@ fn binding_declaration1() {
     assert!(binding_declaration("let y: bool;").unwrap().0 == "");
     assert!(
         binding_declaration("let z:i32;").unwrap().1
-            == ast::Command::Binding(ast::Binding::Declaration(
-                ast::Variable::Named("z".to_string()),
-                ast::Type::I32,
-                false
-            ))
+            == ast::Command::Binding(
+                ast::Binding::Declaration(
+                    ast::Variable::Named("z".to_string()),
+                    ast::Type::I32,
+                    false
+                ),
+                ast::Span::default()
+            )
     );
     assert!(
         binding_declaration("let mut z:bool;").unwrap().1
-            == ast::Command::Binding(ast::Binding::Declaration(
-                ast::Variable::Named("z".to_string()),
-                ast::Type::Bool,
-                true
-            ))
+            == ast::Command::Binding(
+                ast::Binding::Declaration(
+                    ast::Variable::Named("z".to_string()),
+                    ast::Type::Bool,
+                    true
+                ),
+                ast::Span::default()
+            )
     );
     assert!(
         binding_declaration("let x: i32;").unwrap().1
-            == ast::Command::Binding(ast::Binding::Declaration(
+            == ast::Command::Binding(
+                ast::Binding::Declaration(
+                    ast::Variable::Named("x".to_string()),
+                    ast::Type::I32,
+                    false
+                ),
+                ast::Span::default()
+            )
+    );
+    assert!(binding_declaration("let c: (i32, bool);").unwrap().0 == "");
+}
+
+#[test]
+fn binding_declaration2() {
+    assert_eq!(
+        binding_declaration("let mut x;").unwrap().1,
+        ast::Command::Binding(
+            ast::Binding::Declaration(
                 ast::Variable::Named("x".to_string()),
-                ast::Type::I32,
+                ast::Type::Infer,
+                true
+            ),
+            ast::Span::default()
+        )
+    );
+}
+
+#[test]
+fn binding_assignment_inferred1() {
+    assert_eq!(
+        binding_assignment_inferred("let x = 3 + y;").unwrap().1,
+        ast::Command::Binding(
+            ast::Binding::Assignment(
+                ast::Variable::Named("x".to_string()),
+                ast::Type::Infer,
+                ast::Value::Expr(ast::Expr::Op(
+                    Box::new(ast::Expr::Number(3)),
+                    ast::Opcode::Add,
+                    Box::new(ast::Expr::Value(Box::new(ast::Value::Variable(
+                        ast::Variable::Named("y".to_string())
+                    ))))
+                )),
                 false
-            ))
+            ),
+            ast::Span::default()
+        )
     );
-    assert!(binding_declaration("let c: (i32, bool);").unwrap().0 == "");
+    assert_eq!(
+        binding_assignment_inferred("let mut b = x < y;").unwrap().1,
+        ast::Command::Binding(
+            ast::Binding::Assignment(
+                ast::Variable::Named("b".to_string()),
+                ast::Type::Infer,
+                ast::Value::Bool(ast::Bool::LowerThan(
+                    ast::Expr::Value(Box::new(ast::Value::Variable(ast::Variable::Named(
+                        "x".to_string()
+                    )))),
+                    ast::Expr::Value(Box::new(ast::Value::Variable(ast::Variable::Named(
+                        "y".to_string()
+                    ))))
+                )),
+                true
+            ),
+            ast::Span::default()
+        )
+    );
+    assert!(binding_assignment_inferred("let t = (1, true);")
+        .unwrap()
+        .0
+        .is_empty());
+    assert!(binding_assignment_inferred("let a = [1, 2, 3];")
+        .unwrap()
+        .0
+        .is_empty());
+    assert!(binding_assignment_inferred("let r = &mut a;")
+        .unwrap()
+        .0
+        .is_empty());
+    assert!(binding_assignment_inferred("let z = f(a, 2);")
+        .unwrap()
+        .0
+        .is_empty());
+    assert!(binding_assignment_inferred("let z = ;").is_err());
 }
 
 #[test]
 fn binding_declaration_tuple1() {
     let mut temp = Vec::new();
-    temp.push(ast::Command::Binding(ast::Binding::Declaration(
-        ast::Variable::Named("a".to_string()),
-        ast::Type::I32,
-        false,
-    )));
-    temp.push(ast::Command::Binding(ast::Binding::Declaration(
-        ast::Variable::Named("b".to_string()),
-        ast::Type::I32,
-        true,
-    )));
+    temp.push(ast::Command::Binding(
+        ast::Binding::Declaration(ast::Variable::Named("a".to_string()), ast::Type::I32, false),
+        ast::Span::default(),
+    ));
+    temp.push(ast::Command::Binding(
+        ast::Binding::Declaration(ast::Variable::Named("b".to_string()), ast::Type::I32, true),
+        ast::Span::default(),
+    ));
     assert_eq!(
         binding_declaration_tuple("let (a, mut b): (i32, i32);")
             .unwrap()
             .1,
-        ast::Command::Binding(ast::Binding::Tuple(temp))
+        ast::Command::Binding(ast::Binding::Tuple(temp), ast::Span::default())
     );
 
     let mut temp = Vec::new();
-    temp.push(ast::Command::Binding(ast::Binding::Declaration(
-        ast::Variable::Named("a".to_string()),
-        ast::Type::I32,
-        false,
-    )));
-    temp.push(ast::Command::Binding(ast::Binding::Declaration(
-        ast::Variable::Named("b".to_string()),
-        ast::Type::Bool,
-        true,
-    )));
+    temp.push(ast::Command::Binding(
+        ast::Binding::Declaration(ast::Variable::Named("a".to_string()), ast::Type::I32, false),
+        ast::Span::default(),
+    ));
+    temp.push(ast::Command::Binding(
+        ast::Binding::Declaration(ast::Variable::Named("b".to_string()), ast::Type::Bool, true),
+        ast::Span::default(),
+    ));
     assert_eq!(
         binding_declaration_tuple("let (a, mut b): (i32, bool);")
             .unwrap()
             .1,
-        ast::Command::Binding(ast::Binding::Tuple(temp))
+        ast::Command::Binding(ast::Binding::Tuple(temp), ast::Span::default())
     );
 }
 
@@ -968,21 +1322,24 @@ fn tuple_unpack_left1() {
 fn while_parse1() {
     assert!(
         while_parse("//%invariant true\nwhile i {}").unwrap().1
-            == ast::Command::Block(ast::Block::While(
-                ast::Bool::Value(Box::new(ast::Value::Variable(ast::Variable::Named(
-                    String::from("i")
-                )))),
-                Vec::new(),
-                ast::Bool::True,
-                ast::Expr::Number(0)
-            ))
+            == ast::Command::Block(
+                ast::Block::While(
+                    ast::Bool::Value(Box::new(ast::Value::Variable(ast::Variable::Named(
+                        String::from("i")
+                    )))),
+                    Vec::new(),
+                    ast::Bool::True,
+                    ast::Expr::Number(0)
+                ),
+                ast::Span::default()
+            )
     );
 
-    assert!(
-        while_parse("//%invariant true\n//%variant x-2\nwhile i {}")
-            .unwrap()
-            .1
-            == ast::Command::Block(ast::Block::While(
+    assert!(while_parse("//%invariant true\n//%variant x-2\nwhile i {}")
+        .unwrap()
+        .1
+        .same_code(&ast::Command::Block(
+            ast::Block::While(
                 ast::Bool::Value(Box::new(ast::Value::Variable(ast::Variable::Named(
                     String::from("i")
                 )))),
@@ -995,43 +1352,48 @@ fn while_parse1() {
                     ast::Opcode::Sub,
                     Box::new(ast::Expr::Number(2))
                 )
-            ))
-    );
+            ),
+            ast::Span::default()
+        )));
 
     assert!(
         while_parse("//%invariant true\n //%variant x\nwhile i {}")
             .unwrap()
             .1
-            == ast::Command::Block(ast::Block::While(
-                ast::Bool::Value(Box::new(ast::Value::Variable(ast::Variable::Named(
-                    String::from("i")
-                )))),
-                Vec::new(),
-                ast::Bool::True,
-                ast::Expr::Value(Box::new(ast::Value::Variable(ast::Variable::Named(
-                    String::from("x")
-                ))))
-            ))
+            == ast::Command::Block(
+                ast::Block::While(
+                    ast::Bool::Value(Box::new(ast::Value::Variable(ast::Variable::Named(
+                        String::from("i")
+                    )))),
+                    Vec::new(),
+                    ast::Bool::True,
+                    ast::Expr::Value(Box::new(ast::Value::Variable(ast::Variable::Named(
+                        String::from("x")
+                    ))))
+                ),
+                ast::Span::default()
+            )
     );
 
     let mut temp = Vec::new();
-    temp.push(ast::Command::Binding(ast::Binding::Assignment(
-        ast::Variable::Named("x".to_string()),
-        ast::Type::I32,
-        ast::Value::Expr(ast::Expr::Number(1)),
-        false,
-    )));
+    temp.push(ast::Command::Binding(
+        ast::Binding::Assignment(
+            ast::Variable::Named("x".to_string()),
+            ast::Type::I32,
+            ast::Value::Expr(ast::Expr::Number(1)),
+            false,
+        ),
+        ast::Span::default(),
+    ));
 
-    assert_eq!(
+    assert!(
         while_parse("//%invariant true\n  while true {let x: i32 = 1;}")
             .unwrap()
-            .1,
-        ast::Command::Block(ast::Block::While(
-            ast::Bool::True,
-            temp,
-            ast::Bool::True,
-            ast::Expr::Number(0)
-        ))
+            .1
+            .same_code(&ast::Command::Block(
+                ast::Block::While(ast::Bool::True, temp, ast::Bool::True, ast::Expr::Number(0)),
+                ast::Span::default()
+            ))
     );
 }
 
@@ -1039,76 +1401,161 @@ fn while_parse1() {
 fn for_parse1() {
     assert!(
         for_parse("//%invariant true\nfor i in 0..2 {}").unwrap().1
-            == ast::Command::Block(ast::Block::ForRange(
-                ast::Variable::Named("i".to_string()),
-                ast::Value::Expr(ast::Expr::Number(0)),
-                ast::Value::Expr(ast::Expr::Number(2)),
-                Vec::new(),
-                ast::Bool::True
-            ))
+            == ast::Command::Block(
+                ast::Block::ForRange(
+                    ast::Variable::Named("i".to_string()),
+                    ast::Value::Expr(ast::Expr::Number(0)),
+                    ast::Value::Expr(ast::Expr::Number(2)),
+                    Vec::new(),
+                    ast::Bool::True
+                ),
+                ast::Span::default()
+            )
     );
 
     assert!(
         for_parse("//%invariant true\n for i in 0..b {}").unwrap().1
-            == ast::Command::Block(ast::Block::ForRange(
-                ast::Variable::Named("i".to_string()),
-                ast::Value::Expr(ast::Expr::Number(0)),
-                ast::Value::Variable(ast::Variable::Named("b".to_string())),
-                Vec::new(),
-                ast::Bool::True
-            ))
+            == ast::Command::Block(
+                ast::Block::ForRange(
+                    ast::Variable::Named("i".to_string()),
+                    ast::Value::Expr(ast::Expr::Number(0)),
+                    ast::Value::Variable(ast::Variable::Named("b".to_string())),
+                    Vec::new(),
+                    ast::Bool::True
+                ),
+                ast::Span::default()
+            )
     );
 
     let mut temp = Vec::new();
-    temp.push(ast::Command::Binding(ast::Binding::Assignment(
-        ast::Variable::Named("x".to_string()),
-        ast::Type::I32,
-        ast::Value::Expr(ast::Expr::Number(1)),
-        false,
-    )));
+    temp.push(ast::Command::Binding(
+        ast::Binding::Assignment(
+            ast::Variable::Named("x".to_string()),
+            ast::Type::I32,
+            ast::Value::Expr(ast::Expr::Number(1)),
+            false,
+        ),
+        ast::Span::default(),
+    ));
 
-    assert_eq!(
+    assert!(
         for_parse("//%invariant true\nfor i in 0..b {let x: i32 = 1;}")
             .unwrap()
-            .1,
-        ast::Command::Block(ast::Block::ForRange(
-            ast::Variable::Named("i".to_string()),
-            ast::Value::Expr(ast::Expr::Number(0)),
-            ast::Value::Variable(ast::Variable::Named("b".to_string())),
-            temp,
-            ast::Bool::True
-        ))
+            .1
+            .same_code(&ast::Command::Block(
+                ast::Block::ForRange(
+                    ast::Variable::Named("i".to_string()),
+                    ast::Value::Expr(ast::Expr::Number(0)),
+                    ast::Value::Variable(ast::Variable::Named("b".to_string())),
+                    temp,
+                    ast::Bool::True
+                ),
+                ast::Span::default()
+            ))
     );
 
     let mut temp = Vec::new();
-    temp.push(ast::Command::Binding(ast::Binding::Assignment(
-        ast::Variable::Named("x".to_string()),
-        ast::Type::I32,
-        ast::Value::Expr(ast::Expr::Number(1)),
-        false,
-    )));
+    temp.push(ast::Command::Binding(
+        ast::Binding::Assignment(
+            ast::Variable::Named("x".to_string()),
+            ast::Type::I32,
+            ast::Value::Expr(ast::Expr::Number(1)),
+            false,
+        ),
+        ast::Span::default(),
+    ));
 
-    temp.push(ast::Command::Binding(ast::Binding::Assignment(
-        ast::Variable::Named("y".to_string()),
-        ast::Type::Bool,
-        ast::Value::Bool(ast::Bool::True),
-        false,
-    )));
+    temp.push(ast::Command::Binding(
+        ast::Binding::Assignment(
+            ast::Variable::Named("y".to_string()),
+            ast::Type::Bool,
+            ast::Value::Bool(ast::Bool::True),
+            false,
+        ),
+        ast::Span::default(),
+    ));
 
     assert!(
         for_parse("//%invariant true\n  for i in 0..b {let x: i32 = 1; let y: bool = true;}")
             .unwrap()
             .1
-            == ast::Command::Block(ast::Block::ForRange(
-                ast::Variable::Named("i".to_string()),
-                ast::Value::Expr(ast::Expr::Number(0)),
-                ast::Value::Variable(ast::Variable::Named("b".to_string())),
-                temp,
-                ast::Bool::True
+            .same_code(&ast::Command::Block(
+                ast::Block::ForRange(
+                    ast::Variable::Named("i".to_string()),
+                    ast::Value::Expr(ast::Expr::Number(0)),
+                    ast::Value::Variable(ast::Variable::Named("b".to_string())),
+                    temp,
+                    ast::Bool::True
+                ),
+                ast::Span::default()
             ))
     );
 }
 
+#[test]
+fn loop_parse1() {
+    assert!(loop_parse("//%invariant true\n//%variant x\nloop {break;}")
+        .unwrap()
+        .1
+        .same_code(&ast::Command::Block(
+            ast::Block::While(
+                ast::Bool::True,
+                vec![ast::Command::Break(ast::Span::default())],
+                ast::Bool::True,
+                ast::Expr::Value(Box::new(ast::Value::Variable(ast::Variable::Named(
+                    String::from("x")
+                ))))
+            ),
+            ast::Span::default()
+        )));
+    assert!(loop_parse("loop {}").is_err());
+}
+
+#[test]
+fn break_parse1() {
+    assert_eq!(
+        break_parse("break;").unwrap().1,
+        ast::Command::Break(ast::Span::default())
+    );
+    assert_eq!(
+        continue_parse("continue ;").unwrap().1,
+        ast::Command::Continue(ast::Span::default())
+    );
+    assert!(break_parse("break").is_err());
+}
+
+#[test]
+fn return_parse1() {
+    assert_eq!(
+        return_parse("return x + 1;").unwrap().1,
+        ast::Command::Return(
+            ast::Value::Expr(ast::Expr::Op(
+                Box::new(ast::Expr::Value(Box::new(ast::Value::Variable(
+                    ast::Variable::Named(String::from("x"))
+                )))),
+                ast::Opcode::Add,
+                Box::new(ast::Expr::Number(1))
+            )),
+            ast::Span::default()
+        )
+    );
+    assert_eq!(
+        return_parse("return (1, true);").unwrap().1,
+        ast::Command::Return(
+            ast::Value::Tuple(vec![
+                ast::Value::Expr(ast::Expr::Number(1)),
+                ast::Value::Bool(ast::Bool::True)
+            ]),
+            ast::Span::default()
+        )
+    );
+    assert_eq!(
+        return_parse("return;").unwrap().1,
+        ast::Command::Return(ast::Value::Unit, ast::Span::default())
+    );
+    assert!(return_parse("returnx;").is_err());
+}
+
 #[test]
 fn reference1() {
     assert_eq!(
@@ -1180,3 +1627,17 @@ fn dereference1() {
         ))))
     );
 }
+
+#[test]
+fn function_limits1() {
+    let a = function("//%precondition true\nfn a () {\n}").unwrap().1;
+    assert_eq!(a.limits, ast::Limits::default());
+
+    let a = function("//%arith bv\n//%timeout 5000\n//%rlimit 10\nfn a () {\n}")
+        .unwrap()
+        .1;
+    assert_eq!(a.limits.timeout, Some(5000));
+    assert_eq!(a.limits.rlimit, Some(10));
+
+    assert!(function("//%timeout many\nfn a () {\n}").is_err());
+}
@@ -84,7 +84,7 @@ fn factor(input: &str) -> IResult<&str, Box<ast::Bool>> {
 }
 
 fn expr_r_value(input: &str) -> IResult<&str, Box<ast::Bool>> {
-    alt((astp::function_call, astp::variable_val))(input)
+    alt((astp::function_call, astp::dereference, astp::variable_val))(input)
         .and_then(|(next_input, res)| Ok((next_input, Box::new(ast::Bool::Value(Box::new(res))))))
 }
 
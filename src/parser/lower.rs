@@ -0,0 +1,1020 @@
+//! Lowering of the Rust syntax parsed by syn into the ast.
+//! syn drops the comments, so the //% ones are read from the source and matched with the code by their place.
+
+use crate::ast;
+use crate::ast::PreconditionCreator;
+use crate::diagnostic::Diagnostic;
+use crate::parser::boolean;
+use crate::parser::math;
+use crate::ParseError;
+
+use proc_macro2::LineColumn;
+use std::convert::TryInto;
+use syn::spanned::Spanned;
+
+#[cfg(test)]
+mod tests;
+
+pub fn program(source: &str) -> Result<ast::Program, ParseError> {
+    let mut lower = Lower::new(source);
+    let file = match syn::parse_file(source) {
+        Ok(file) => file,
+        Err(e) => {
+            return Err(ParseError::Failed(Diagnostic {
+                message: format!("failed to parse ({})", e),
+                span: lower.span(e.span().start()),
+                source: None,
+            }))
+        }
+    };
+
+    let mut content = Vec::new();
+    for item in file.items {
+        match item {
+            syn::Item::Fn(f) => content.push(lower.function(f)?),
+            // e.g. `use rustp_contracts::{ensures, requires};`
+            syn::Item::Use(_) => {}
+            x => return Err(lower.unsupported(&x, "item other than a function")),
+        }
+    }
+    if let Some(a) = lower.annotations.get(lower.next) {
+        return Err(ParseError::Failed(Diagnostic {
+            message: String::from("condition is not followed by any code"),
+            span: a.span,
+            source: None,
+        }));
+    }
+
+    Ok(ast::Program {
+        content,
+        source: None,
+    })
+}
+
+/// A //% comment, e.g. the `invariant i < n` of `//%invariant i < n`
+struct Annotation {
+    offset: usize,
+    span: ast::Span,
+    kind: String,
+    text: String,
+}
+
+/// The conditions written right before a function or a statement
+#[derive(Default)]
+struct Conditions {
+    precondition: Option<ast::Bool>,
+    postcondition: Option<ast::Bool>,
+    arith: Option<ast::Arith>,
+    limits: ast::Limits,
+    invariant: Option<ast::Bool>,
+    variant: Option<ast::Expr>,
+    asserts: Vec<(ast::Span, ast::Bool)>,
+    /// Where the first of the precondition, postcondition, arith and limits is
+    function: Option<ast::Span>,
+    /// The statement is only there for debugging, it's not proven
+    debug: bool,
+}
+
+struct Lower<'a> {
+    source: &'a str,
+    /// Where each line starts
+    lines: Vec<usize>,
+    annotations: Vec<Annotation>,
+    /// The first annotation not matched with the code yet
+    next: usize,
+}
+
+impl<'a> Lower<'a> {
+    fn new(source: &'a str) -> Self {
+        let mut lines = vec![0];
+        let mut annotations = Vec::new();
+        let mut offset = 0;
+        for line in source.split_inclusive('\n') {
+            let trimmed = line.trim_start();
+            if let Some(a) = trimmed.strip_prefix("//%") {
+                let start = offset + line.len() - trimmed.len();
+                let a = a.trim_end();
+                let (kind, text) = a.split_once(char::is_whitespace).unwrap_or((a, ""));
+                annotations.push(Annotation {
+                    offset: start,
+                    span: ast::Span::new(&source[start..]),
+                    kind: kind.to_string(),
+                    text: text.trim().to_string(),
+                });
+            }
+            offset += line.len();
+            lines.push(offset);
+        }
+
+        Lower {
+            source,
+            lines,
+            annotations,
+            next: 0,
+        }
+    }
+
+    fn offset(&self, at: LineColumn) -> usize {
+        let start = self.lines[at.line - 1];
+        let column: usize = self.source[start..]
+            .chars()
+            .take(at.column)
+            .map(|x| x.len_utf8())
+            .sum();
+        start + column
+    }
+
+    fn span(&self, at: LineColumn) -> ast::Span {
+        ast::Span::new(&self.source[self.offset(at)..])
+    }
+
+    fn unsupported<T: Spanned>(&self, node: &T, what: &str) -> ParseError {
+        ParseError::Unsupported(Diagnostic {
+            message: format!("unsupported construct: {}", what),
+            span: self.span(node.span().start()),
+            source: None,
+        })
+    }
+
+    fn failed(span: ast::Span, message: &str) -> ParseError {
+        ParseError::Failed(Diagnostic {
+            message: String::from(message),
+            span,
+            source: None,
+        })
+    }
+
+    /// Take all the conditions written before the given place
+    fn conditions(&mut self, before: LineColumn) -> Result<Conditions, ParseError> {
+        let offset = self.offset(before);
+        let mut result = Conditions::default();
+        while let Some(a) = self.annotations.get(self.next) {
+            if a.offset >= offset {
+                break;
+            }
+            self.next += 1;
+
+            let text = a.text.as_str();
+            if [
+                "precondition",
+                "postcondition",
+                "arith",
+                "timeout",
+                "rlimit",
+            ]
+            .contains(&a.kind.as_str())
+            {
+                result.function = result.function.or(Some(a.span));
+            }
+            match a.kind.as_str() {
+                "precondition" => result.precondition = Some(Self::condition(a.span, text)?),
+                "postcondition" => result.postcondition = Some(Self::condition(a.span, text)?),
+                "invariant" => result.invariant = Some(Self::condition(a.span, text)?),
+                "assert" => result
+                    .asserts
+                    .push((a.span, Self::condition(a.span, text)?)),
+                "variant" => match math::expr(text) {
+                    Ok((rest, e)) if rest.trim().is_empty() => result.variant = Some(*e),
+                    _ => return Err(Self::failed(a.span, "failed to parse the variant")),
+                },
+                "arith" => {
+                    result.arith = match text {
+                        "int" => Some(ast::Arith::Int),
+                        "bv" => Some(ast::Arith::Bv),
+                        _ => return Err(Self::failed(a.span, "unknown arithmetic")),
+                    }
+                }
+                "timeout" => result.limits.timeout = Some(Self::limit(a.span, text)?),
+                "rlimit" => result.limits.rlimit = Some(Self::limit(a.span, text)?),
+                "debug" => result.debug = true,
+                _ => return Err(Self::failed(a.span, "unknown condition")),
+            }
+        }
+        Ok(result)
+    }
+
+    /// Add the conditions written as attributes, e.g. `#[requires(x > 0)]`, the other attributes are left alone
+    fn attributes(
+        &self,
+        attrs: &[syn::Attribute],
+        allowed: &[&str],
+        cond: &mut Conditions,
+    ) -> Result<(), ParseError> {
+        for a in attrs {
+            let name = match a.path.segments.last() {
+                Some(x) => x.ident.to_string(),
+                None => continue,
+            };
+            if !["requires", "ensures", "invariant", "variant"].contains(&name.as_str()) {
+                continue;
+            }
+            let span = self.span(a.span().start());
+            if !allowed.contains(&name.as_str()) {
+                return Err(Self::failed(
+                    span,
+                    &format!("#[{}] can't be written here", name),
+                ));
+            }
+
+            // The conditions aren't Rust, so they are parsed from the source
+            let text = match a.tokens.clone().into_iter().next() {
+                Some(proc_macro2::TokenTree::Group(g))
+                    if g.delimiter() == proc_macro2::Delimiter::Parenthesis =>
+                {
+                    self.inside(g.span())
+                }
+                _ => {
+                    return Err(Self::failed(
+                        span,
+                        &format!("#[{}] without a condition", name),
+                    ))
+                }
+            };
+            let and = |a: Option<ast::Bool>, b| match a {
+                Some(a) => ast::Bool::And(Box::new(a), Box::new(b)),
+                None => b,
+            };
+            match name.as_str() {
+                "requires" => {
+                    let c = Self::condition(span, &text)?;
+                    cond.precondition = Some(and(cond.precondition.take(), c));
+                }
+                "ensures" => {
+                    let c = Self::condition(span, &text)?;
+                    cond.postcondition = Some(and(cond.postcondition.take(), c));
+                }
+                "invariant" => {
+                    let c = Self::condition(span, &text)?;
+                    cond.invariant = Some(and(cond.invariant.take(), c));
+                }
+                _ => match math::expr(&text) {
+                    Ok((rest, e)) if rest.trim().is_empty() && cond.variant.is_none() => {
+                        cond.variant = Some(*e)
+                    }
+                    _ => return Err(Self::failed(span, "failed to parse the variant")),
+                },
+            }
+        }
+        Ok(())
+    }
+
+    /// The source between the brackets of the group, on a single line
+    fn inside(&self, group: proc_macro2::Span) -> String {
+        let text = &self.source[self.offset(group.start()) + 1..self.offset(group.end()) - 1];
+        text.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+
+    /// `rustp_assert!(..)`, the assert written as a macro so the code still builds
+    fn assert_macro(&self, m: &syn::Macro) -> Result<ast::Command, ParseError> {
+        let group = match &m.delimiter {
+            syn::MacroDelimiter::Paren(x) => x.span,
+            syn::MacroDelimiter::Brace(x) => x.span,
+            syn::MacroDelimiter::Bracket(x) => x.span,
+        };
+        let span = self.span(m.path.span().start());
+        Ok(ast::Command::ProveControl(
+            ast::ProveControl::Assert(Self::condition(span, &self.inside(group))?),
+            span,
+        ))
+    }
+
+    fn limit(span: ast::Span, text: &str) -> Result<u32, ParseError> {
+        text.parse()
+            .map_err(|_| Self::failed(span, "the limit has to be a number"))
+    }
+
+    fn condition(span: ast::Span, text: &str) -> Result<ast::Bool, ParseError> {
+        match boolean::expr(text) {
+            Ok((rest, b)) if rest.trim().is_empty() => Ok(*b),
+            _ => Err(Self::failed(span, "failed to parse the condition")),
+        }
+    }
+
+    fn function(&mut self, f: syn::ItemFn) -> Result<ast::Function, ParseError> {
+        let start = f.sig.fn_token.span.start();
+        let mut cond = self.conditions(f.span().start())?;
+        if cond.invariant.is_some() || cond.variant.is_some() || !cond.asserts.is_empty() {
+            return Err(self.unsupported(&f.sig, "loop condition or assert before a function"));
+        }
+        self.attributes(&f.attrs, &["requires", "ensures"], &mut cond)?;
+
+        if !f.sig.generics.params.is_empty() {
+            return Err(self.unsupported(&f.sig.generics, "generic function"));
+        }
+        let mut input = Vec::new();
+        for i in f.sig.inputs.iter() {
+            match i {
+                syn::FnArg::Typed(syn::PatType { pat, ty, .. }) => {
+                    let (var, mutable) = self.pattern(pat)?;
+                    input.push(ast::Binding::Declaration(var, self.ty(ty)?, mutable));
+                }
+                syn::FnArg::Receiver(x) => return Err(self.unsupported(x, "method")),
+            }
+        }
+        let output = match &f.sig.output {
+            syn::ReturnType::Default => ast::Type::Unit,
+            syn::ReturnType::Type(_, t) => self.ty(t)?,
+        };
+
+        let mut stmts = f.block.stmts;
+        // The value at the end is what's returned, the control flow at the end is just a command
+        let tail = match stmts.last() {
+            Some(syn::Stmt::Expr(e)) if !is_command(e) => Some(e.clone()),
+            _ => None,
+        };
+        let (content, return_value, return_span) = match tail {
+            Some(e) => {
+                stmts.pop();
+                (
+                    self.block(&stmts, e.span().start())?,
+                    self.r_value(&e)?,
+                    self.span(e.span().start()),
+                )
+            }
+            None => (
+                self.block(&stmts, f.block.brace_token.span.end())?,
+                ast::Value::Unit,
+                ast::Span::default(),
+            ),
+        };
+
+        Ok(ast::Function {
+            name: f.sig.ident.to_string(),
+            content,
+            input,
+            output,
+            precondition: cond.precondition.unwrap_or(ast::Bool::True),
+            postcondition: cond.postcondition.unwrap_or(ast::Bool::True),
+            return_value,
+            return_span,
+            span: self.span(start),
+            arith: cond.arith,
+            limits: cond.limits,
+        }
+        .update_precondition())
+    }
+
+    /// Commands of the block, the asserts are placed where they are written, up to the `end`
+    fn block(
+        &mut self,
+        stmts: &[syn::Stmt],
+        end: LineColumn,
+    ) -> Result<Vec<ast::Command>, ParseError> {
+        let mut result = Vec::new();
+        for s in stmts {
+            let start = s.span().start();
+            let cond = self.conditions(start)?;
+            self.asserts(&mut result, &cond)?;
+            if cond.debug {
+                continue;
+            }
+
+            let command = match s {
+                syn::Stmt::Local(l) => {
+                    self.attributes(&l.attrs, &[], &mut Conditions::default())?;
+                    self.no_loop_conditions(s, &cond)?;
+                    self.binding(l)?
+                }
+                syn::Stmt::Semi(syn::Expr::Macro(syn::ExprMacro { mac, .. }), _)
+                | syn::Stmt::Expr(syn::Expr::Macro(syn::ExprMacro { mac, .. }))
+                | syn::Stmt::Item(syn::Item::Macro(syn::ItemMacro { mac, .. }))
+                    if is_assert(mac) =>
+                {
+                    self.no_loop_conditions(s, &cond)?;
+                    self.assert_macro(mac)?
+                }
+                syn::Stmt::Semi(e, _) | syn::Stmt::Expr(e) => self.command(e, cond)?,
+                syn::Stmt::Item(x) => return Err(self.unsupported(x, "nested item")),
+            };
+            result.push(command.with_span(self.span(start)));
+        }
+
+        let cond = self.conditions(end)?;
+        self.asserts(&mut result, &cond)?;
+        Ok(result)
+    }
+
+    fn asserts(&self, code: &mut Vec<ast::Command>, cond: &Conditions) -> Result<(), ParseError> {
+        if let Some(span) = cond.function {
+            return Err(Self::failed(
+                span,
+                "function conditions can only be written before the function",
+            ));
+        }
+        for (span, a) in cond.asserts.iter() {
+            code.push(ast::Command::ProveControl(
+                ast::ProveControl::Assert(a.clone()),
+                *span,
+            ));
+        }
+        Ok(())
+    }
+
+    fn no_loop_conditions<T: Spanned>(
+        &self,
+        node: &T,
+        cond: &Conditions,
+    ) -> Result<(), ParseError> {
+        if cond.invariant.is_some() || cond.variant.is_some() {
+            return Err(self.unsupported(
+                node,
+                "invariant or variant before something else than a loop",
+            ));
+        }
+        Ok(())
+    }
+
+    fn command(&mut self, e: &syn::Expr, mut cond: Conditions) -> Result<ast::Command, ParseError> {
+        let allowed: &[&str] = match e {
+            syn::Expr::While(_) | syn::Expr::Loop(_) | syn::Expr::ForLoop(_) => {
+                &["invariant", "variant"]
+            }
+            _ => &[],
+        };
+        self.attributes(attributes(e), allowed, &mut cond)?;
+        match e {
+            syn::Expr::While(w) => {
+                if w.label.is_some() {
+                    return Err(self.unsupported(e, "loop label"));
+                }
+                let inv = self.invariant(e, cond.invariant)?;
+                let var = cond.variant.unwrap_or(ast::Expr::Number(0));
+                let c = self.bool(&w.cond)?;
+                let comms = self.block(&w.body.stmts, w.body.brace_token.span.end())?;
+                Ok(ast::Command::Block(
+                    ast::Block::While(c, comms, inv, var),
+                    ast::Span::default(),
+                ))
+            }
+            syn::Expr::Loop(l) => {
+                if l.label.is_some() {
+                    return Err(self.unsupported(e, "loop label"));
+                }
+                let inv = self.invariant(e, cond.invariant)?;
+                let var = cond.variant.unwrap_or(ast::Expr::Number(0));
+                let comms = self.block(&l.body.stmts, l.body.brace_token.span.end())?;
+                Ok(ast::Command::Block(
+                    ast::Block::While(ast::Bool::True, comms, inv, var),
+                    ast::Span::default(),
+                ))
+            }
+            syn::Expr::ForLoop(f) => {
+                if f.label.is_some() {
+                    return Err(self.unsupported(e, "loop label"));
+                }
+                if cond.variant.is_some() {
+                    return Err(
+                        self.unsupported(e, "variant of a for loop (it's the rest of the range)")
+                    );
+                }
+                let inv = self.invariant(e, cond.invariant)?;
+                let iter = match self.pattern(&f.pat)? {
+                    (v @ ast::Variable::Named(_), false) => v,
+                    _ => return Err(self.unsupported(&f.pat, "for loop pattern")),
+                };
+                let (first, last) = match &*f.expr {
+                    syn::Expr::Range(syn::ExprRange {
+                        from: Some(first),
+                        limits: syn::RangeLimits::HalfOpen(_),
+                        to: Some(last),
+                        ..
+                    }) => (self.r_value(first)?, self.r_value(last)?),
+                    x => return Err(self.unsupported(x, "for loop over something else than a..b")),
+                };
+                let comms = self.block(&f.body.stmts, f.body.brace_token.span.end())?;
+                Ok(ast::Command::Block(
+                    ast::Block::ForRange(iter, first, last, comms, inv),
+                    ast::Span::default(),
+                ))
+            }
+            _ => {
+                self.no_loop_conditions(e, &cond)?;
+                self.simple_command(e)
+            }
+        }
+    }
+
+    fn invariant(&self, e: &syn::Expr, inv: Option<ast::Bool>) -> Result<ast::Bool, ParseError> {
+        match inv {
+            Some(inv) => Ok(inv),
+            None => Err(Self::failed(
+                self.span(e.span().start()),
+                "loop without an invariant",
+            )),
+        }
+    }
+
+    fn simple_command(&mut self, e: &syn::Expr) -> Result<ast::Command, ParseError> {
+        match e {
+            syn::Expr::Assign(a) => self.assignment(&a.left, &a.right),
+            syn::Expr::AssignOp(a) => {
+                let var = self.assigned(&a.left)?;
+                let op = match self.opcode(&a.op) {
+                    Some(op) => op,
+                    None => return Err(self.unsupported(e, "compound assignment operator")),
+                };
+                let val = ast::Expr::Op(
+                    Box::new(ast::Expr::Value(Box::new(ast::Value::Variable(
+                        var.clone(),
+                    )))),
+                    op,
+                    Box::new(self.expr(&a.right)?),
+                );
+                Ok(ast::Command::Assignment(
+                    ast::Assignment::Single(var, ast::Value::Expr(val)),
+                    ast::Span::default(),
+                ))
+            }
+            syn::Expr::Call(_) => Ok(ast::Command::Assignment(
+                ast::Assignment::Single(ast::Variable::Empty, self.value(e)?),
+                ast::Span::default(),
+            )),
+            syn::Expr::If(i) => self.if_else(i),
+            syn::Expr::Break(b) => {
+                if b.label.is_some() || b.expr.is_some() {
+                    return Err(self.unsupported(e, "break with a label or a value"));
+                }
+                Ok(ast::Command::Break(ast::Span::default()))
+            }
+            syn::Expr::Continue(c) => {
+                if c.label.is_some() {
+                    return Err(self.unsupported(e, "loop label"));
+                }
+                Ok(ast::Command::Continue(ast::Span::default()))
+            }
+            syn::Expr::Return(r) => match &r.expr {
+                Some(x) => Ok(ast::Command::Return(
+                    self.statement_value(x)?,
+                    ast::Span::default(),
+                )),
+                None => Ok(ast::Command::Return(ast::Value::Unit, ast::Span::default())),
+            },
+            x => Err(self.unsupported(x, &format!("{} used as a statement", kind(x)))),
+        }
+    }
+
+    fn if_else(&mut self, i: &syn::ExprIf) -> Result<ast::Command, ParseError> {
+        let mut conds = Vec::new();
+        let mut comms = Vec::new();
+        let mut el = Vec::new();
+
+        let mut current = i;
+        loop {
+            conds.push(self.bool(&current.cond)?);
+            let b = &current.then_branch;
+            comms.push(self.block(&b.stmts, b.brace_token.span.end())?);
+            match current.else_branch.as_ref().map(|(_, x)| &**x) {
+                Some(syn::Expr::If(next)) => current = next,
+                Some(syn::Expr::Block(b)) => {
+                    el = self.block(&b.block.stmts, b.block.brace_token.span.end())?;
+                    break;
+                }
+                Some(x) => return Err(self.unsupported(x, "else branch")),
+                None => break,
+            }
+        }
+
+        Ok(ast::Command::Block(
+            ast::Block::If(conds, comms, el),
+            ast::Span::default(),
+        ))
+    }
+
+    fn assignment(
+        &mut self,
+        left: &syn::Expr,
+        right: &syn::Expr,
+    ) -> Result<ast::Command, ParseError> {
+        if let syn::Expr::Tuple(t) = left {
+            let vars = t
+                .elems
+                .iter()
+                .map(|x| self.assigned(x))
+                .collect::<Result<Vec<_>, _>>()?;
+            let vals = self.tuple_values(right, vars.len())?;
+            let result = vars
+                .into_iter()
+                .zip(vals)
+                .map(|(var, val)| ast::Assignment::Single(var, val))
+                .collect();
+            return Ok(ast::Command::Assignment(
+                ast::Assignment::Tuple(result),
+                ast::Span::default(),
+            ));
+        }
+
+        let var = self.assigned(left)?;
+        // The variable's type isn't known here, a lone variable is a bool until the typing pass says otherwise
+        let val = match self.statement_value(right)? {
+            ast::Value::Expr(ast::Expr::Value(v)) => ast::Value::Bool(ast::Bool::Value(v)),
+            x => x,
+        };
+        Ok(ast::Command::Assignment(
+            ast::Assignment::Single(var, val),
+            ast::Span::default(),
+        ))
+    }
+
+    /// Left side of an assignment, `*x = y` is an assignment to `x`
+    fn assigned(&mut self, e: &syn::Expr) -> Result<ast::Variable, ParseError> {
+        match e {
+            syn::Expr::Unary(syn::ExprUnary {
+                op: syn::UnOp::Deref(_),
+                expr,
+                ..
+            }) => self.assigned(expr),
+            syn::Expr::Verbatim(x) if x.to_string() == "_" => Ok(ast::Variable::Empty),
+            _ => match self.value(e)? {
+                ast::Value::Variable(v @ ast::Variable::Length(_)) => {
+                    Err(self.unsupported(e, &format!("assignment to {}", v)))
+                }
+                ast::Value::Variable(v) => Ok(v),
+                _ => Err(self.unsupported(e, "assignment to something else than a variable")),
+            },
+        }
+    }
+
+    /// Values of a tuple, `t` stands for `(t.0, t.1, ...)`
+    fn tuple_values(&mut self, e: &syn::Expr, len: usize) -> Result<Vec<ast::Value>, ParseError> {
+        match self.value(e)? {
+            ast::Value::Tuple(vals) if vals.len() == len => Ok(vals),
+            ast::Value::Variable(ast::Variable::Named(name)) => Ok((0..len)
+                .map(|i| {
+                    ast::Value::Variable(ast::Variable::TupleElem(
+                        name.clone(),
+                        Box::new(ast::Value::Expr(ast::Expr::Number(i as i128))),
+                    ))
+                })
+                .collect()),
+            _ => Err(self.unsupported(e, "tuple value")),
+        }
+    }
+
+    fn binding(&mut self, l: &syn::Local) -> Result<ast::Command, ParseError> {
+        let (pat, t) = match &l.pat {
+            syn::Pat::Type(p) => (&*p.pat, Some(self.ty(&p.ty)?)),
+            p => (p, None),
+        };
+        let init = l.init.as_ref().map(|(_, x)| &**x);
+
+        if let syn::Pat::Tuple(p) = pat {
+            let types = match t {
+                Some(ast::Type::Tuple(types)) if types.len() == p.elems.len() => types,
+                None => vec![ast::Type::Infer; p.elems.len()],
+                _ => return Err(self.unsupported(&l.pat, "tuple pattern of this type")),
+            };
+            let vals = match init {
+                Some(x) => Some(self.tuple_values(x, types.len())?),
+                None => None,
+            };
+
+            let mut result = Vec::new();
+            for (i, (pat, t)) in p.elems.iter().zip(types).enumerate() {
+                let (var, mutable) = self.pattern(pat)?;
+                result.push(ast::Command::Binding(
+                    match &vals {
+                        Some(vals) => ast::Binding::Assignment(var, t, vals[i].clone(), mutable),
+                        None => ast::Binding::Declaration(var, t, mutable),
+                    },
+                    ast::Span::default(),
+                ));
+            }
+            return Ok(ast::Command::Binding(
+                ast::Binding::Tuple(result),
+                ast::Span::default(),
+            ));
+        }
+
+        let (var, mutable) = self.pattern(pat)?;
+        let t = t.unwrap_or(ast::Type::Infer);
+        Ok(ast::Command::Binding(
+            match init {
+                Some(x) => {
+                    // The type tells how the value is read, same as in the nom parser
+                    let val = match &t {
+                        ast::Type::Infer => self.statement_value(x)?,
+                        ast::Type::Bool => ast::Value::Bool(self.bool(x)?),
+                        t if t.is_int() => ast::Value::Expr(self.expr(x)?),
+                        _ => self.r_value(x)?,
+                    };
+                    ast::Binding::Assignment(var, t, val, mutable)
+                }
+                None => ast::Binding::Declaration(var, t, mutable),
+            },
+            ast::Span::default(),
+        ))
+    }
+
+    /// Variable bound by the pattern and whether it's mutable
+    fn pattern(&self, p: &syn::Pat) -> Result<(ast::Variable, bool), ParseError> {
+        match p {
+            syn::Pat::Ident(syn::PatIdent {
+                by_ref: None,
+                subpat: None,
+                mutability,
+                ident,
+                ..
+            }) => Ok((
+                ast::Variable::Named(ident.to_string()),
+                mutability.is_some(),
+            )),
+            syn::Pat::Wild(_) => Ok((ast::Variable::Empty, false)),
+            x => Err(self.unsupported(x, "pattern other than a name")),
+        }
+    }
+
+    fn ty(&self, t: &syn::Type) -> Result<ast::Type, ParseError> {
+        match t {
+            syn::Type::Path(p) if p.qself.is_none() => {
+                let name = match p.path.get_ident() {
+                    Some(x) => x.to_string(),
+                    None => return Err(self.unsupported(t, "type")),
+                };
+                match name.as_str() {
+                    "bool" => Ok(ast::Type::Bool),
+                    "i8" => Ok(ast::Type::I8),
+                    "i16" => Ok(ast::Type::I16),
+                    "i32" => Ok(ast::Type::I32),
+                    "i64" => Ok(ast::Type::I64),
+                    "u8" => Ok(ast::Type::U8),
+                    "u16" => Ok(ast::Type::U16),
+                    "u32" => Ok(ast::Type::U32),
+                    "u64" => Ok(ast::Type::U64),
+                    "usize" => Ok(ast::Type::Usize),
+                    _ => Err(self.unsupported(t, &format!("type {}", name))),
+                }
+            }
+            syn::Type::Tuple(x) if x.elems.is_empty() => Ok(ast::Type::Unit),
+            syn::Type::Tuple(x) => Ok(ast::Type::Tuple(
+                x.elems
+                    .iter()
+                    .map(|x| self.ty(x))
+                    .collect::<Result<_, _>>()?,
+            )),
+            syn::Type::Reference(r) => match &*r.elem {
+                syn::Type::Slice(s) => Ok(ast::Type::ArraySlice(Box::new(self.ty(&s.elem)?))),
+                x if r.mutability.is_some() => {
+                    Ok(ast::Type::ReferenceMutable(Box::new(self.ty(x)?)))
+                }
+                x => Ok(ast::Type::Reference(Box::new(self.ty(x)?))),
+            },
+            syn::Type::Array(a) => match self.value(&a.len)? {
+                ast::Value::Expr(ast::Expr::Number(n)) => match n.try_into() {
+                    Ok(n) => Ok(ast::Type::Array(Box::new(self.ty(&a.elem)?), n)),
+                    Err(_) => Err(self.unsupported(&a.len, "array length out of the i32 range")),
+                },
+                _ => Err(self.unsupported(&a.len, "array length other than a number")),
+            },
+            syn::Type::Paren(p) => self.ty(&p.elem),
+            x => Err(self.unsupported(x, "type")),
+        }
+    }
+
+    /// Value ending a statement, a lone variable is kept as an expression like `let x = y + 1;` would be
+    fn statement_value(&self, e: &syn::Expr) -> Result<ast::Value, ParseError> {
+        Ok(match self.value(e)? {
+            v @ ast::Value::Variable(_) | v @ ast::Value::Dereference(_) => {
+                ast::Value::Expr(ast::Expr::Value(Box::new(v)))
+            }
+            v => v,
+        })
+    }
+
+    /// Value used as an argument, an element of a tuple etc.
+    fn r_value(&self, e: &syn::Expr) -> Result<ast::Value, ParseError> {
+        self.value(e)
+    }
+
+    fn expr(&self, e: &syn::Expr) -> Result<ast::Expr, ParseError> {
+        Ok(match self.value(e)? {
+            ast::Value::Expr(e) => e,
+            v => ast::Expr::Value(Box::new(v)),
+        })
+    }
+
+    fn bool(&self, e: &syn::Expr) -> Result<ast::Bool, ParseError> {
+        Ok(match self.value(e)? {
+            ast::Value::Bool(b) => b,
+            ast::Value::Expr(ast::Expr::Value(v)) => ast::Bool::Value(v),
+            v => ast::Bool::Value(Box::new(v)),
+        })
+    }
+
+    fn opcode(&self, op: &syn::BinOp) -> Option<ast::Opcode> {
+        match op {
+            syn::BinOp::Add(_) | syn::BinOp::AddEq(_) => Some(ast::Opcode::Add),
+            syn::BinOp::Sub(_) | syn::BinOp::SubEq(_) => Some(ast::Opcode::Sub),
+            syn::BinOp::Mul(_) | syn::BinOp::MulEq(_) => Some(ast::Opcode::Mul),
+            syn::BinOp::Div(_) | syn::BinOp::DivEq(_) => Some(ast::Opcode::Div),
+            syn::BinOp::Rem(_) | syn::BinOp::RemEq(_) => Some(ast::Opcode::Rem),
+            _ => None,
+        }
+    }
+
+    fn value(&self, e: &syn::Expr) -> Result<ast::Value, ParseError> {
+        match e {
+            syn::Expr::Lit(l) => match &l.lit {
+                syn::Lit::Int(i) => match i.base10_parse::<i128>() {
+                    Ok(i) => Ok(ast::Value::Expr(ast::Expr::Number(i))),
+                    Err(_) => Err(self.unsupported(e, "number out of the i128 range")),
+                },
+                syn::Lit::Bool(b) if b.value => Ok(ast::Value::Bool(ast::Bool::True)),
+                syn::Lit::Bool(_) => Ok(ast::Value::Bool(ast::Bool::False)),
+                x => Err(self.unsupported(x, "literal")),
+            },
+            syn::Expr::Path(p) => match p.path.get_ident() {
+                Some(x) if p.qself.is_none() => {
+                    Ok(ast::Value::Variable(ast::Variable::Named(x.to_string())))
+                }
+                _ => Err(self.unsupported(e, "path")),
+            },
+            syn::Expr::Paren(p) => self.value(&p.expr),
+            syn::Expr::Group(g) => self.value(&g.expr),
+            syn::Expr::Binary(b) => {
+                if let Some(op) = self.opcode(&b.op) {
+                    return Ok(ast::Value::Expr(ast::Expr::Op(
+                        Box::new(self.expr(&b.left)?),
+                        op,
+                        Box::new(self.expr(&b.right)?),
+                    )));
+                }
+                let result = match b.op {
+                    syn::BinOp::And(_) => ast::Bool::And(
+                        Box::new(self.bool(&b.left)?),
+                        Box::new(self.bool(&b.right)?),
+                    ),
+                    syn::BinOp::Or(_) => ast::Bool::Or(
+                        Box::new(self.bool(&b.left)?),
+                        Box::new(self.bool(&b.right)?),
+                    ),
+                    syn::BinOp::Eq(_) => {
+                        ast::Bool::Equal(self.expr(&b.left)?, self.expr(&b.right)?)
+                    }
+                    syn::BinOp::Ne(_) => ast::Bool::Not(Box::new(ast::Bool::Equal(
+                        self.expr(&b.left)?,
+                        self.expr(&b.right)?,
+                    ))),
+                    syn::BinOp::Lt(_) => {
+                        ast::Bool::LowerThan(self.expr(&b.left)?, self.expr(&b.right)?)
+                    }
+                    syn::BinOp::Le(_) => {
+                        ast::Bool::LowerEqual(self.expr(&b.left)?, self.expr(&b.right)?)
+                    }
+                    syn::BinOp::Gt(_) => {
+                        ast::Bool::GreaterThan(self.expr(&b.left)?, self.expr(&b.right)?)
+                    }
+                    syn::BinOp::Ge(_) => {
+                        ast::Bool::GreaterEqual(self.expr(&b.left)?, self.expr(&b.right)?)
+                    }
+                    _ => return Err(self.unsupported(e, "operator")),
+                };
+                Ok(ast::Value::Bool(result))
+            }
+            syn::Expr::Unary(u) => match u.op {
+                syn::UnOp::Not(_) => Ok(ast::Value::Bool(ast::Bool::Not(Box::new(
+                    self.bool(&u.expr)?,
+                )))),
+                syn::UnOp::Neg(_) => Ok(ast::Value::Expr(match self.expr(&u.expr)? {
+                    ast::Expr::Number(n) => ast::Expr::Number(-n),
+                    x => ast::Expr::Op(
+                        Box::new(ast::Expr::Number(0)),
+                        ast::Opcode::Sub,
+                        Box::new(x),
+                    ),
+                })),
+                syn::UnOp::Deref(_) => Ok(ast::Value::Dereference(Box::new(self.value(&u.expr)?))),
+            },
+            syn::Expr::Reference(r) if r.mutability.is_some() => {
+                Ok(ast::Value::ReferenceMutable(Box::new(self.value(&r.expr)?)))
+            }
+            syn::Expr::Reference(r) => Ok(ast::Value::Reference(Box::new(self.value(&r.expr)?))),
+            syn::Expr::Tuple(t) if t.elems.is_empty() => Ok(ast::Value::Unit),
+            syn::Expr::Tuple(t) => Ok(ast::Value::Tuple(
+                t.elems
+                    .iter()
+                    .map(|x| self.r_value(x))
+                    .collect::<Result<_, _>>()?,
+            )),
+            syn::Expr::Array(a) => Ok(ast::Value::Array(
+                a.elems
+                    .iter()
+                    .map(|x| self.r_value(x))
+                    .collect::<Result<_, _>>()?,
+            )),
+            syn::Expr::Call(c) => {
+                let name = match &*c.func {
+                    syn::Expr::Path(p) if p.qself.is_none() && p.path.get_ident().is_some() => {
+                        p.path.get_ident().unwrap().to_string()
+                    }
+                    x => return Err(self.unsupported(x, "call of something else than a function")),
+                };
+                let args = c
+                    .args
+                    .iter()
+                    .map(|x| self.r_value(x))
+                    .collect::<Result<_, _>>()?;
+                Ok(ast::Value::FunctionCall(name, args))
+            }
+            syn::Expr::Index(i) => {
+                let name = self.name(&i.expr)?;
+                Ok(ast::Value::Variable(ast::Variable::ArrayElem(
+                    name,
+                    Box::new(self.r_value(&i.index)?),
+                )))
+            }
+            syn::Expr::Field(f) => {
+                let index = match &f.member {
+                    syn::Member::Unnamed(x) => x.index,
+                    x => return Err(self.unsupported(x, "named field")),
+                };
+                match &*f.base {
+                    // Elements of an array of tuples are kept as arrays named like `x.0`
+                    syn::Expr::Index(i) => Ok(ast::Value::Variable(ast::Variable::ArrayElem(
+                        format!("{}.{}", self.name(&i.expr)?, index),
+                        Box::new(self.r_value(&i.index)?),
+                    ))),
+                    x => Ok(ast::Value::Variable(ast::Variable::TupleElem(
+                        self.name(x)?,
+                        Box::new(ast::Value::Expr(ast::Expr::Number(index as i128))),
+                    ))),
+                }
+            }
+            syn::Expr::MethodCall(m) if m.method == "len" && m.args.is_empty() => Ok(
+                ast::Value::Variable(ast::Variable::Length(self.name(&m.receiver)?)),
+            ),
+            x => Err(self.unsupported(x, kind(x))),
+        }
+    }
+
+    /// Name of the array or the tuple
+    fn name(&self, e: &syn::Expr) -> Result<String, ParseError> {
+        match e {
+            syn::Expr::Path(p) if p.qself.is_none() => match p.path.get_ident() {
+                Some(x) => Ok(x.to_string()),
+                None => Err(self.unsupported(e, "path")),
+            },
+            syn::Expr::Paren(p) => self.name(&p.expr),
+            x => Err(self.unsupported(x, "indexing something else than a variable")),
+        }
+    }
+}
+
+fn is_assert(m: &syn::Macro) -> bool {
+    matches!(m.path.segments.last(), Some(x) if x.ident == "rustp_assert")
+}
+
+/// The attributes of the expressions that can be statements
+fn attributes(e: &syn::Expr) -> &[syn::Attribute] {
+    match e {
+        syn::Expr::Assign(x) => &x.attrs,
+        syn::Expr::AssignOp(x) => &x.attrs,
+        syn::Expr::Call(x) => &x.attrs,
+        syn::Expr::If(x) => &x.attrs,
+        syn::Expr::Break(x) => &x.attrs,
+        syn::Expr::Continue(x) => &x.attrs,
+        syn::Expr::Return(x) => &x.attrs,
+        syn::Expr::While(x) => &x.attrs,
+        syn::Expr::Loop(x) => &x.attrs,
+        syn::Expr::ForLoop(x) => &x.attrs,
+        _ => &[],
+    }
+}
+
+/// The expressions that are statements on their own, even when there's no `;` after them
+fn is_command(e: &syn::Expr) -> bool {
+    matches!(
+        e,
+        syn::Expr::If(_)
+            | syn::Expr::While(_)
+            | syn::Expr::Loop(_)
+            | syn::Expr::ForLoop(_)
+            | syn::Expr::Return(_)
+            | syn::Expr::Break(_)
+            | syn::Expr::Continue(_)
+    ) || matches!(e, syn::Expr::Macro(x) if is_assert(&x.mac))
+}
+
+/// What the expression is, for the errors
+fn kind(e: &syn::Expr) -> &'static str {
+    match e {
+        syn::Expr::Array(_) => "array",
+        syn::Expr::Assign(_) | syn::Expr::AssignOp(_) => "assignment used as a value",
+        syn::Expr::Async(_) | syn::Expr::Await(_) => "async",
+        syn::Expr::Block(_) => "block",
+        syn::Expr::Box(_) => "box",
+        syn::Expr::Cast(_) => "cast",
+        syn::Expr::Closure(_) => "closure",
+        syn::Expr::If(_) => "if used as a value",
+        syn::Expr::Let(_) => "let in a condition",
+        syn::Expr::Macro(_) => "macro",
+        syn::Expr::Match(_) => "match",
+        syn::Expr::MethodCall(_) => "method call other than len()",
+        syn::Expr::Range(_) => "range",
+        syn::Expr::Repeat(_) => "array repeat expression",
+        syn::Expr::Struct(_) => "struct",
+        syn::Expr::Try(_) | syn::Expr::TryBlock(_) => "? operator",
+        syn::Expr::Unsafe(_) => "unsafe block",
+        syn::Expr::While(_) | syn::Expr::Loop(_) | syn::Expr::ForLoop(_) => "loop used as a value",
+        _ => "expression",
+    }
+}
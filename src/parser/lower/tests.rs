@@ -0,0 +1,202 @@
+use crate::parser::lower::*;
+
+fn nom(source: &str) -> ast::Program {
+    let (rest, program) = crate::parser::program(source).unwrap();
+    assert!(rest.trim().is_empty());
+    program
+}
+
+#[test]
+fn same_as_nom1() {
+    let examples = [
+        include_str!("../../../prove_examples/calls.rs"),
+        include_str!("../../../prove_examples/count.rs"),
+        include_str!("../../../prove_examples/list.rs"),
+        include_str!("../../../prove_examples/no_overflow_sum.rs"),
+        include_str!("../../../prove_examples/overflow_sum.rs"),
+        include_str!("../../../prove_examples/references.rs"),
+        include_str!("../../../prove_examples/remainder.rs"),
+        include_str!("../../../prove_examples/remainder_partial.rs"),
+        include_str!("../../../prove_examples/swap.rs"),
+        include_str!("../../../prove_examples/swap_elements.rs"),
+    ];
+    for source in examples.iter() {
+        assert!(program(source).unwrap().same_code(&nom(source)));
+    }
+}
+
+#[test]
+fn same_as_nom2() {
+    let source = "//%precondition x > 0
+//%postcondition return_value == x + 1
+fn a(x: i32) -> i32 {
+    let mut i: i32 = 0;
+    //%invariant i <= x
+    //%variant x - i
+    while i < x {
+        i = i + 1;
+        //%assert i <= x
+    }
+    if i == x {
+        i = i + 1;
+    } else if i > x {
+        i = 0;
+    } else {
+        return 0;
+    }
+    i
+}
+";
+    assert!(program(source).unwrap().same_code(&nom(source)));
+}
+
+#[test]
+fn attributes1() {
+    let comments = "//%precondition x > 0 && y > 0
+//%postcondition return_value.0 * y + return_value.1 == x
+fn div(x: i32, y: i32) -> (i32, i32) {
+    let mut q: i32 = 0;
+    let mut r: i32 = x;
+    //%invariant q * y + r == x && r >= 0
+    //%variant r
+    while r >= y {
+        q = q + 1;
+        r = r - y;
+        //%assert r >= 0
+    }
+    (q, r)
+}
+";
+    let attributes = "use rustp_contracts::{ensures, requires, rustp_assert};
+
+#[requires(x > 0)]
+#[rustp_contracts::requires(y > 0)]
+#[ensures(
+    return_value.0 * y + return_value.1 == x
+)]
+fn div(x: i32, y: i32) -> (i32, i32) {
+    let mut q: i32 = 0;
+    let mut r: i32 = x;
+    #[invariant(q * y + r == x && r >= 0)]
+    #[variant(r)]
+    while r >= y {
+        q = q + 1;
+        r = r - y;
+        rustp_assert!(
+            r >= 0
+        );
+    }
+    (q, r)
+}
+";
+    assert!(program(attributes)
+        .unwrap()
+        .same_code(&program(comments).unwrap()));
+}
+
+#[test]
+fn attributes2() {
+    let source = "fn a() {\n    #[invariant(true)]\n    let x: i32 = 0;\n}\n";
+    match program(source) {
+        Err(ParseError::Failed(e)) => {
+            assert_eq!(e.message, "#[invariant] can't be written here");
+            assert_eq!(e.span.left, source.len() - source.find("#[").unwrap());
+        }
+        x => panic!("Unexpected parse result: {:?}", x),
+    }
+    assert!(program("#[requires(x >)]\nfn a(x: i32) {}\n").is_err());
+    assert!(program("fn a(x: i32) {\n    rustp_assert!(x >);\n}\n").is_err());
+}
+
+#[test]
+fn parenthesized1() {
+    let a = program("fn a(x: i32) -> i32 {\n    (x + 1) * 2\n}\n").unwrap();
+    let b = program("fn a(x: i32) -> i32 {\n    ((x + 1)) * (2)\n}\n").unwrap();
+    assert!(a.same_code(&b));
+}
+
+#[test]
+fn assign_op1() {
+    let a = program("fn a(mut x: i32) {\n    x *= 2 + x;\n}\n").unwrap();
+    let b = program("fn a(mut x: i32) {\n    x = x * (2 + x);\n}\n").unwrap();
+    assert!(a.same_code(&b));
+}
+
+#[test]
+fn unsupported1() {
+    let source = "fn a(x: i32) -> i32 {\n    match x {\n        _ => 0,\n    }\n}\n";
+    match program(source) {
+        Err(ParseError::Unsupported(e)) => {
+            assert_eq!(e.message, "unsupported construct: match");
+            assert_eq!(e.span.left, source.len() - source.find("match").unwrap());
+        }
+        x => panic!("Unexpected parse result: {:?}", x),
+    }
+}
+
+#[test]
+fn large_number1() {
+    let a = program("fn a(x: u64) -> u64 {\n    x + 4294967296\n}\n").unwrap();
+    let b = nom("fn a(x: u64) -> u64 {\n    (x + 4294967296)\n}\n");
+    assert!(a.same_code(&b));
+
+    let source = "fn a(x: u64) -> u64 {\n    x + 1000000000000000000000000000000000000000\n}\n";
+    match program(source) {
+        Err(ParseError::Unsupported(e)) => {
+            assert_eq!(
+                e.message,
+                "unsupported construct: number out of the i128 range"
+            );
+            assert_eq!(e.span.left, source.len() - source.find("1000").unwrap());
+        }
+        x => panic!("Unexpected parse result: {:?}", x),
+    }
+}
+
+#[test]
+fn unsupported2() {
+    match program("struct A {}\n") {
+        Err(ParseError::Unsupported(e)) => {
+            assert_eq!(
+                e.message,
+                "unsupported construct: item other than a function"
+            )
+        }
+        x => panic!("Unexpected parse result: {:?}", x),
+    }
+}
+
+#[test]
+fn missing_invariant1() {
+    let source = "fn a() {\n    loop {\n        break;\n    }\n}\n";
+    match program(source) {
+        Err(ParseError::Failed(e)) => {
+            assert_eq!(e.span.left, source.len() - source.find("loop").unwrap())
+        }
+        x => panic!("Unexpected parse result: {:?}", x),
+    }
+}
+
+#[test]
+fn failed1() {
+    assert!(matches!(
+        program("fn a() { let a = ; }\n"),
+        Err(ParseError::Failed(_))
+    ));
+    assert!(matches!(
+        program("fn a() {}\n//%assert true\n"),
+        Err(ParseError::Failed(_))
+    ));
+}
+
+#[test]
+fn limits1() {
+    let source = "//%precondition true\n//%timeout 5000\n//%rlimit 10\nfn a() {\n}\n";
+    assert_eq!(program(source).unwrap(), nom(source));
+
+    match program("//%timeout 5s\nfn a() {}\n") {
+        Err(ParseError::Failed(e)) => assert_eq!(e.message, "the limit has to be a number"),
+        x => panic!("Unexpected parse result: {:?}", x),
+    }
+    assert!(program("fn a() {\n    //%timeout 10\n}\n").is_err());
+}
@@ -4,12 +4,10 @@ use crate::parser::astp;
 
 use nom::{
     branch::alt, bytes::complete::tag, bytes::complete::take_while1, character::complete::char,
-    character::complete::one_of, character::complete::space0, multi::many0, sequence::tuple,
-    IResult,
+    character::complete::one_of, character::complete::space0, error::Error, error::ErrorKind,
+    multi::many0, sequence::tuple, IResult,
 };
 
-use std::str::FromStr;
-
 #[cfg(test)]
 mod tests;
 
@@ -117,7 +115,7 @@ fn _expr_number_negative(input: &str) -> IResult<&str, Box<ast::Expr>> {
 }
 
 fn expr_r_value(input: &str) -> IResult<&str, Box<ast::Expr>> {
-    alt((astp::function_call, astp::variable_val))(input)
+    alt((astp::function_call, astp::dereference, astp::variable_val))(input)
         .and_then(|(next_input, res)| Ok((next_input, Box::new(ast::Expr::Value(Box::new(res))))))
 }
 
@@ -158,16 +156,11 @@ fn add_or_subtract(input: &str) -> IResult<&str, ast::Opcode> {
     }
 }
 
-pub fn number(input: &str) -> IResult<&str, i32> {
-    let t = digito(input);
-    match t {
-        Ok(a) => {
-            let (next_input, res) = a;
-            // this unwrap may be a dirty hack :D
-            // on the other hand this is only used when we are sure, that what we are converting is a number
-            // so it may be safe?
-            Ok((next_input, FromStr::from_str(res).unwrap()))
-        }
-        Err(a) => Err(a),
+/// Fails right away if the number doesn't fit in i128, there is nothing else it could be parsed as
+pub fn number(input: &str) -> IResult<&str, i128> {
+    let (next_input, res) = digito(input)?;
+    match res.parse() {
+        Ok(a) => Ok((next_input, a)),
+        Err(_) => Err(nom::Err::Failure(Error::new(input, ErrorKind::TooLarge))),
     }
 }
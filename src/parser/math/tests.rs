@@ -141,6 +141,19 @@ fn expr4() {
     );
 }
 
+#[test]
+fn expr5() {
+    let deref = |x: &str| {
+        Box::new(ast::Expr::Value(Box::new(ast::Value::Dereference(
+            Box::new(ast::Value::Variable(ast::Variable::Named(x.to_string()))),
+        ))))
+    };
+    assert_eq!(
+        expr("*x * *y").unwrap().1,
+        Box::new(ast::Expr::Op(deref("x"), ast::Opcode::Mul, deref("y")))
+    );
+}
+
 #[test]
 fn expr_number1() {
     assert!(expr_number("1").is_ok());
@@ -169,3 +182,16 @@ fn number1() {
     assert!(number("194").is_ok());
     assert!(number("").is_err());
 }
+
+#[test]
+fn number_large1() {
+    assert_eq!(number("4294967296"), Ok(("", 4294967296)));
+    assert_eq!(
+        number("18446744073709551615"),
+        Ok(("", 18446744073709551615))
+    );
+    assert!(matches!(
+        number("1000000000000000000000000000000000000000"),
+        Err(nom::Err::Failure(_))
+    ));
+}
@@ -1,22 +1,428 @@
 use crate::ast::*;
 use crate::set_nonmut as set;
 use log;
-use std::collections::HashSet;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::convert::TryInto;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 use z3;
 
 #[cfg(test)]
 mod tests;
 
-#[cfg(feature = "bounds_prove")]
-mod bitvector;
-#[cfg(feature = "bounds_prove")]
-use bitvector::*;
+mod counterexample;
+pub use counterexample::{Counterexample, ModelValue};
+
+mod report;
+pub use report::{errors_json, FunctionReport, Goal, Obligation, Report, Status};
 
-#[cfg(not(feature = "bounds_prove"))]
+mod bitvector;
 mod int;
-#[cfg(not(feature = "bounds_prove"))]
-use int::*;
+
+mod smtlib;
+mod solver;
+use solver::{SatResult, Solver, Term};
+
+/// Turns the conditions into Z3 formulas, each backend represents the ints in its own way
+trait Backend {
+    fn as_bool<'a>(&self, ctx: &'a z3::Context, b: Bool)
+        -> (HashSet<Check<'a>>, z3::ast::Bool<'a>);
+    /// Get the value of an int from the model (used for the counterexamples)
+    fn eval_int<'a>(&self, ctx: &'a z3::Context, solver: &dyn Solver<'a>, v: Value) -> Option<i64>;
+    /// What's known about the variables regardless of the code, e.g. the ranges of their types
+    fn assumptions<'a>(&self, ctx: &'a z3::Context, p: &Bool, q: &Bool) -> z3::ast::Bool<'a>;
+}
+
+/// Backend of the function being proven
+fn backend() -> &'static dyn Backend {
+    match OPTIONS.with(|x| x.borrow().arith) {
+        Arith::Int => &int::IntBackend,
+        Arith::Bv => &bitvector::BvBackend,
+    }
+}
+
+/// Settings of a single verification run
+#[derive(Clone, Debug)]
+pub struct Options {
+    /// Check all the obligations, instead of stopping at the first failure
+    pub keep_going: bool,
+    /// Overflows fail the proof, instead of being only warned about
+    pub strict_overflow: bool,
+    /// Arithmetic used for the functions that don't choose their own
+    pub arith: Arith,
+    /// How many triples are proven at the same time, each in its own thread with its own Z3 context
+    pub jobs: usize,
+    /// Limits for the functions that don't set their own
+    pub limits: Limits,
+    /// Directory to write every query given to Z3 to, as an SMT-LIB2 file
+    pub emit_smt: Option<PathBuf>,
+    /// Command of a solver to talk SMT-LIB2 to, instead of the Z3 bindings
+    pub smt_solver: Option<Vec<String>>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            keep_going: false,
+            strict_overflow: false,
+            // The feature is kept only to change the default
+            arith: if cfg!(feature = "bounds_prove") {
+                Arith::Bv
+            } else {
+                Arith::Int
+            },
+            jobs: 1,
+            limits: Limits::default(),
+            emit_smt: None,
+            smt_solver: None,
+        }
+    }
+}
+
+thread_local! {
+    /// Options of the current run, they are needed deep inside the weakest precondition calculation
+    static OPTIONS: RefCell<Options> = RefCell::new(Options::default());
+}
+
+fn keep_going() -> bool {
+    OPTIONS.with(|x| x.borrow().keep_going)
+}
+
+fn strict_overflow() -> bool {
+    OPTIONS.with(|x| x.borrow().strict_overflow)
+}
+
+thread_local! {
+    /// Queries given to Z3 so far along with what they check, only kept with emit_smt
+    static QUERIES: RefCell<Vec<(Goal, String)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Keep the asserted formula as a standalone SMT-LIB2 query, if they are to be emitted
+fn emit_query(goal: Goal, condition: &str, formula: &Term) {
+    if OPTIONS.with(|x| x.borrow().emit_smt.is_none()) {
+        return;
+    }
+    let query = format!(
+        "; {}: {}\n{}(check-sat)\n",
+        goal,
+        condition,
+        formula.script()
+    );
+    QUERIES.with(|x| x.borrow_mut().push((goal, query)));
+}
+
+/// Write the queries of the function to the directory, numbered in the order they were checked in
+fn write_queries(dir: &Path, function: &str, queries: &[(Goal, String)]) -> std::io::Result<()> {
+    for (i, (goal, query)) in queries.iter().enumerate() {
+        let name = format!("{}.{}.{}.smt2", function, i + 1, goal.id());
+        std::fs::write(dir.join(name), query)?;
+    }
+    Ok(())
+}
+
+thread_local! {
+    /// Types of the variables of the function being proven, the backends pick the sorts based on them
+    static TYPES: RefCell<HashMap<String, Type>> = RefCell::new(HashMap::new());
+}
+
+/// Where the control flow can jump to, other than the next command
+struct Exits {
+    /// Has to hold when the function returns
+    postcondition: Bool,
+    /// Has to hold when the loop is left with `break` and when it goes on with `continue`, innermost last
+    loops: Vec<(Bool, Bool)>,
+    /// Returns that have to establish the postcondition in the triple being proven
+    returns: Returns,
+}
+
+/// Every return is proven as a triple of its own, the other triples don't look past it
+#[derive(Clone, Copy, PartialEq)]
+enum Returns {
+    Skip,
+    Only(Span),
+}
+
+thread_local! {
+    /// Exits of the function being proven, the jumps take the conditions of where they go to
+    static EXITS: RefCell<Exits> = const {
+        RefCell::new(Exits {
+            postcondition: Bool::True,
+            loops: Vec::new(),
+            returns: Returns::Skip,
+        })
+    };
+}
+
+/// Run the proof of the loop's body, with `break` and `continue` needing the given conditions
+fn with_loop_exits<T>(on_break: Bool, on_continue: Bool, f: impl FnOnce() -> T) -> T {
+    EXITS.with(|x| x.borrow_mut().loops.push((on_break, on_continue)));
+    let result = f();
+    EXITS.with(|x| x.borrow_mut().loops.pop());
+    result
+}
+
+fn loop_exits() -> (Bool, Bool) {
+    match EXITS.with(|x| x.borrow().loops.last().cloned()) {
+        Some(x) => x,
+        None => panic!("break or continue used outside of a loop"),
+    }
+}
+
+/// Type of the variable, the 'old version has the same type as the variable itself
+fn variable_type(name: &str) -> Option<Type> {
+    let name = name.replacen("'old", "", 1);
+    TYPES.with(|x| x.borrow().get(&name).cloned())
+}
+
+/// Set the type of the variable, None makes it unknown
+/// The elements of a tuple get their own types too, a reference gets the type of the value it points to
+fn set_type(name: &str, t: Option<Type>) {
+    let t = match t {
+        Some(Type::Reference(t)) | Some(Type::ReferenceMutable(t)) => Some(*t),
+        t => t,
+    };
+    if let Some(Type::Tuple(types)) = &t {
+        for (i, elem) in types.iter().enumerate() {
+            set_type(&format!("{}.{}", name, i), Some(elem.clone()));
+        }
+    }
+    if let Some(Type::Array(_, _)) | Some(Type::ArraySlice(_)) = &t {
+        set_type(&format!("{}.len()", name), Some(Type::Usize));
+    }
+    // An array of tuples is an array for each element of the tuples, named like `x.0`
+    if let Some(Type::Tuple(types)) = t.as_ref().and_then(|x| x.array_elem()) {
+        for (i, elem) in types.iter().enumerate() {
+            let elem = Box::new(elem.clone());
+            let component = match &t {
+                Some(Type::Array(_, n)) => Type::Array(elem, *n),
+                _ => Type::ArraySlice(elem),
+            };
+            set_type(&format!("{}.{}", name, i), Some(component));
+        }
+    }
+    TYPES.with(|x| match t {
+        Some(t) => x.borrow_mut().insert(name.to_string(), t),
+        None => x.borrow_mut().remove(name),
+    });
+}
+
+/// Whole arrays can be compared, e.g. with their 'old snapshots
+fn is_array(name: &str) -> bool {
+    matches!(
+        variable_type(name),
+        Some(Type::Array(_, _)) | Some(Type::ArraySlice(_))
+    )
+}
+
+/// `x[i] == x[i]` for all the array elements the assignment reads or writes
+/// It's always true, but the backends check the indexes of the accesses in it
+fn accesses(var: &Variable, val: &Value) -> Option<Bool> {
+    let mut vars = var.clone().get_variables();
+    vars.extend(val.clone().get_variables());
+    let mut elems: Vec<Variable> = vars
+        .into_iter()
+        // A whole tuple is written through the arrays of its elements, which check it themselves
+        .filter(|x| match x {
+            Variable::ArrayElem(name, _) => !matches!(array_elem_type(name), Some(Type::Tuple(_))),
+            _ => false,
+        })
+        .collect();
+    // Always in the same order, so the conditions are the same
+    elems.sort_by_key(|x| x.to_string());
+
+    elems
+        .into_iter()
+        .map(|x| {
+            Bool::ValueEqual(
+                Box::new(Value::Variable(x.clone())),
+                Box::new(Value::Variable(x)),
+            )
+        })
+        .reduce(|a, b| Bool::And(Box::new(a), Box::new(b)))
+}
+
+/// `v == v` for the parts of the value that compute something
+/// Like with the accesses, it's always true, but the backends check the arithmetic in it
+/// (divisions by zero, overflows) even when q doesn't use the value
+fn evaluated(val: &Value) -> Option<Bool> {
+    match val {
+        Value::Tuple(vals) | Value::Array(vals) => vals
+            .iter()
+            .filter_map(evaluated)
+            .reduce(|a, b| Bool::And(Box::new(a), Box::new(b))),
+        Value::Reference(v) | Value::ReferenceMutable(v) | Value::Dereference(v) => evaluated(v),
+        Value::Expr(Expr::Value(v)) => evaluated(v),
+        Value::Variable(_)
+        | Value::Unit
+        | Value::Expr(Expr::Number(_))
+        | Value::Bool(Bool::True)
+        | Value::Bool(Bool::False) => None,
+        v => Some(Bool::ValueEqual(Box::new(v.clone()), Box::new(v.clone()))),
+    }
+}
+
+/// The checks of the formula are reported at the span, if it's known
+fn located(b: Bool, span: Span) -> Bool {
+    if span.is_known() {
+        Bool::Located(Box::new(b), span)
+    } else {
+        b
+    }
+}
+
+/// Whether the name is one of the constants `assign` puts in for the typed literals
+/// Their value is the literal, even if it doesn't fit the type
+fn is_literal(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_digit() || c == '-')
+}
+
+/// q with the value in place of the variable
+/// Only the variables give the ints their types, so a number assigned to one (e.g. the 255 of `let r: u8 = 255;`)
+/// is put in as a constant of the type named like the literal, (255u8 == 255) => q[255u8/r], instead of an i32
+fn assign(q: Bool, var: Variable, val: Value) -> Bool {
+    let t = value_int_type(&Value::Variable(var.clone()));
+    let n = match (t.clone(), &val) {
+        (Some(_), Value::Expr(Expr::Number(n))) if q.clone().get_variables().contains(&var) => *n,
+        _ => return q.swap(var, val),
+    };
+    let name = format!("{}{}", n, t.clone().unwrap());
+    set_type(&name, t);
+    let constant = Value::Variable(Variable::Named(name));
+    Bool::Or(
+        Box::new(Bool::Not(Box::new(Bool::ValueEqual(
+            Box::new(constant.clone()),
+            Box::new(val),
+        )))),
+        Box::new(q.swap(var, constant)),
+    )
+}
+
+/// Length of the array, either known from its type or a variable named like `x.len()`
+/// Lengths never change, so `x'old` has the same one as `x`
+fn array_len(name: &str) -> Value {
+    let name = name.replacen("'old", "", 1);
+    // The arrays of an array of tuples share its length
+    let name = match name.split_once('.') {
+        Some((base, _)) if is_array(base) => base.to_string(),
+        _ => name,
+    };
+    match variable_type(&name) {
+        Some(Type::Array(_, n)) => Value::Expr(Expr::Number(n.into())),
+        _ => Value::Variable(Variable::Named(format!("{}.len()", name))),
+    }
+}
+
+/// Type of the elements of the array
+fn array_elem_type(name: &str) -> Option<Type> {
+    variable_type(name).and_then(|x| x.array_elem().cloned())
+}
+
+/// Whether the value is a bool, judging by the types of the variables in it
+fn is_bool(v: &Value) -> bool {
+    match v {
+        Value::Bool(Bool::Value(a)) => is_bool(a),
+        Value::Bool(_) => true,
+        Value::Expr(Expr::Value(a)) => is_bool(a),
+        Value::Variable(Variable::Named(name)) => variable_type(name) == Some(Type::Bool),
+        Value::Variable(Variable::TupleElem(name, index)) => {
+            is_bool(&Value::Variable(tuple_elem(name, index)))
+        }
+        Value::Variable(Variable::ArrayElem(name, _)) => array_elem_type(name) == Some(Type::Bool),
+        Value::Ternary(_, a, b) => is_bool(a) || is_bool(b),
+        Value::Reference(a) | Value::ReferenceMutable(a) | Value::Dereference(a) => is_bool(a),
+        _ => false,
+    }
+}
+
+/// The value without the int expression around it, so it can be used as a bool
+fn bool_value(v: Value) -> Value {
+    match v {
+        Value::Expr(Expr::Value(a)) => bool_value(*a),
+        v => v,
+    }
+}
+
+/// Tuple elements are modelled as separate variables named like `t.0`
+fn tuple_elem(name: &str, index: &Value) -> Variable {
+    match index {
+        Value::Expr(Expr::Number(i)) => Variable::Named(format!("{}.{}", name, i)),
+        _ => panic!(
+            "Tuple {} indexed with {}, only numbers are allowed",
+            name, index
+        ),
+    }
+}
+
+/// Remember the types of all the variables defined in the code
+fn declare_types(code: &[Command]) {
+    for i in code {
+        match i {
+            Command::Binding(Binding::Tuple(vec), _) => declare_types(vec),
+            Command::Binding(Binding::Declaration(Variable::Named(x), t, _), _)
+            | Command::Binding(Binding::Assignment(Variable::Named(x), t, _, _), _) => {
+                set_type(x, Some(t.clone()))
+            }
+            Command::Block(Block::If(_, comms, el), _) => {
+                for c in comms {
+                    declare_types(c);
+                }
+                declare_types(el);
+            }
+            Command::Block(Block::While(_, comms, _, _), _) => declare_types(comms),
+            Command::Block(Block::ForRange(Variable::Named(x), first, last, comms, _), _) => {
+                // The iterator has the type of the range
+                set_type(x, value_int_type(last).or_else(|| value_int_type(first)));
+                declare_types(comms);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Types of all the variables of the function (parameters, locals and the return value)
+fn function_types(f: &Function) -> HashMap<String, Type> {
+    let outer = TYPES.with(|x| x.take());
+    for i in f.input.iter() {
+        if let Binding::Declaration(Variable::Named(x), t, _) = i {
+            set_type(x, Some(t.clone()));
+        }
+    }
+    set_type("return_value", Some(f.output.clone()));
+    declare_types(&f.content);
+    TYPES.with(|x| x.replace(outer))
+}
+
+/// Integer type of the expression, as far as it can be told from the variables used in it
+fn expr_int_type(e: &Expr) -> Option<Type> {
+    match e {
+        Expr::Number(_) => None,
+        Expr::Op(a, _, b) => expr_int_type(a).or_else(|| expr_int_type(b)),
+        Expr::Value(v) => value_int_type(v),
+    }
+}
+
+fn value_int_type(v: &Value) -> Option<Type> {
+    let t = match v {
+        Value::Expr(e) => expr_int_type(e),
+        Value::Bool(Bool::Value(a)) => value_int_type(a),
+        Value::Variable(Variable::Named(name)) => variable_type(name),
+        Value::Variable(Variable::TupleElem(name, index)) => match tuple_elem(name, index) {
+            Variable::Named(x) => variable_type(&x),
+            _ => None,
+        },
+        Value::Variable(Variable::ArrayElem(name, _)) => array_elem_type(name),
+        Value::Variable(Variable::Length(_)) => Some(Type::Usize),
+        Value::Ternary(_, a, b) => value_int_type(a).or_else(|| value_int_type(b)),
+        Value::Reference(a) | Value::ReferenceMutable(a) | Value::Dereference(a) => {
+            value_int_type(a)
+        }
+        _ => None,
+    };
+    t.filter(|x| x.is_int())
+}
 
 fn prove_block(precondition: Bool, code: Vec<Command>, postcondition: Bool) -> ProveBlock {
     ProveBlock {
@@ -25,49 +431,67 @@ fn prove_block(precondition: Bool, code: Vec<Command>, postcondition: Bool) -> P
         postcondition: postcondition.clone(),
         precondition_original: precondition,
         postcondition_original: postcondition,
+        goal: Goal::Postcondition,
+        span: Span::default(),
+    }
+}
+
+/// Solver giving up after the limits of the function being proven
+fn solver(ctx: &z3::Context) -> Box<dyn Solver<'_> + '_> {
+    let (limits, command) = OPTIONS.with(|x| (x.borrow().limits, x.borrow().smt_solver.clone()));
+    match command {
+        Some(command) => Box::new(smtlib::Pipe::new(command, limits)),
+        None => Box::new(solver::Z3::new(ctx, limits)),
     }
 }
 
-fn prove_impl(p: Bool, q: Bool) -> bool {
+fn prove_impl(p: Bool, q: Bool, goal: Goal, span: Span) -> bool {
     let mut cfg = z3::Config::new();
     cfg.set_model_generation(true);
 
     let ctx = z3::Context::new(&cfg);
-    let t = z3::Solver::new(&ctx);
+    let t = solver(&ctx);
 
-    let (mut checks, _pre) = p.clone().as_bool(&ctx);
-    let (checks_post, _post) = q.clone().as_bool(&ctx);
+    let (mut checks, _pre) = backend().as_bool(&ctx, p.clone());
+    let (checks_post, _post) = backend().as_bool(&ctx, q.clone());
     checks.extend(checks_post);
+    let _pre = z3::ast::Bool::and(&ctx, &[&_pre, &backend().assumptions(&ctx, &p, &q)]);
 
-    // Check overflows and underflows
-    for i in checks {
-        t.reset();
-        t.assert(&_pre.implies(&i.check).not());
-        let f = t.check();
-        if f != z3::SatResult::Unsat {
-            log::warn!("Bounds check warning: possible {} {}", i.name, i.object);
-        }
-    }
-    t.reset();
+    let checks_ok = prove_checks(&ctx, t.as_ref(), checks, &_pre, span, &p, &q);
 
-    t.assert(&_pre.implies(&_post).not());
+    let query = Term::new(&_pre.implies(&_post).not());
+    t.assert(&query);
+    emit_query(goal, &q.to_string(), &query);
 
+    let start = Instant::now();
     let f = t.check();
+    let time = start.elapsed();
     log::debug!("{:?}", f);
-    log::debug!("{:?}", t.get_model());
     let result = Some(f);
 
+    record_obligation(
+        &ctx,
+        t.as_ref(),
+        f,
+        time,
+        goal,
+        span,
+        q.to_string(),
+        &p,
+        &q,
+        None,
+    );
+
     match result {
-        Some(z3::SatResult::Sat) => {
-            log::debug!("Model: {:?}", t.get_model());
+        Some(SatResult::Sat) => {
             log::info!("Failed to prove: {} => {}", p, q);
             return false;
         }
-        Some(z3::SatResult::Unsat) => {
+        Some(SatResult::Unsat) => {
             log::info!("Proven: {} => {}", p, q);
         }
-        Some(z3::SatResult::Unknown) => {
-            println!("Proving stopped!");
+        Some(SatResult::Unknown) => {
+            log::warn!("Proving stopped: {:?}", t.reason_unknown());
             return false;
         }
         _ => {
@@ -75,14 +499,98 @@ fn prove_impl(p: Bool, q: Bool) -> bool {
         }
     }
 
-    true
+    checks_ok
 }
 
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
 pub struct Check<'a> {
-    name: String,
+    goal: Goal,
     object: Expr,
     check: z3::ast::Bool<'a>,
+    /// Place of the code the object is in, unknown if it's only in the conditions
+    span: Span,
+}
+
+/// The checks only have to hold when the condition does (e.g. the right side of &&)
+fn guard<'a>(checks: HashSet<Check<'a>>, cond: &z3::ast::Bool<'a>) -> HashSet<Check<'a>> {
+    checks
+        .into_iter()
+        .map(|x| Check {
+            check: cond.implies(&x.check),
+            ..x
+        })
+        .collect()
+}
+
+/// The checks that aren't placed yet come from the code at the span
+fn locate(checks: HashSet<Check>, span: Span) -> HashSet<Check> {
+    checks
+        .into_iter()
+        .map(|x| Check {
+            span: if x.span.is_known() { x.span } else { span },
+            ..x
+        })
+        .collect()
+}
+
+/// Check that there are no overflows or divisions by zero when pre holds
+/// The overflows are only warned about, unless they are strict
+fn prove_checks<'a>(
+    ctx: &'a z3::Context,
+    t: &dyn Solver<'a>,
+    checks: HashSet<Check<'a>>,
+    pre: &z3::ast::Bool<'a>,
+    span: Span,
+    p: &Bool,
+    q: &Bool,
+) -> bool {
+    // Always in the same order, so the reports are the same
+    let mut checks: Vec<Check> = checks.into_iter().collect();
+    checks.sort_by_cached_key(|x| (x.goal.id(), x.object.to_string(), x.check.to_string()));
+    // The same access can be checked under different conditions, it's still one obligation
+    checks.dedup_by(|a, b| {
+        let same = a.goal == b.goal && a.object == b.object;
+        if same {
+            b.check = z3::ast::Bool::and(ctx, &[&b.check, &a.check]);
+            // Reported where it's first evaluated, the later code has it in place of the variables
+            if a.span.left > b.span.left {
+                b.span = a.span;
+            }
+        }
+        same
+    });
+
+    let mut result = true;
+    for i in checks {
+        let query = Term::new(&pre.implies(&i.check).not());
+        t.push();
+        t.assert(&query);
+        emit_query(i.goal, &i.object.to_string(), &query);
+
+        let start = Instant::now();
+        let f = t.check();
+        let time = start.elapsed();
+
+        // Out of bounds accesses and divisions by zero panic no matter the build, so they are never just warnings
+        if strict_overflow() || i.goal == Goal::Bounds || i.goal == Goal::DivisionByZero {
+            let span = if i.span.is_known() { i.span } else { span };
+            let object = i.object.to_string();
+            record_obligation(ctx, t, f, time, i.goal, span, object, p, q, Some(&i.object));
+            if f != SatResult::Unsat {
+                log::info!("Failed to prove {}: {}", i.goal, i.object);
+                result = false;
+            }
+        } else if f != SatResult::Unsat {
+            log::warn!(
+                "Bounds check warning: possible {} {}",
+                i.goal.id(),
+                i.object
+            );
+        }
+        t.pop();
+    }
+
+    result
 }
 
 #[derive(Clone, Debug)]
@@ -92,55 +600,300 @@ struct ProveBlock {
     postcondition: Bool,
     precondition_original: Bool,
     postcondition_original: Bool,
+    goal: Goal,
+    /// Place in the source the postcondition comes from
+    span: Span,
 }
 
-fn define_return_value(output: Type, return_value: Value) -> Command {
+/// Record the result of the solver checking p => q, along with the counterexample if it's found
+/// The object is the checked expression, if the obligation is one of the checks
+#[allow(clippy::too_many_arguments)]
+fn record_obligation<'a>(
+    ctx: &'a z3::Context,
+    solver: &dyn Solver<'a>,
+    result: SatResult,
+    time: Duration,
+    goal: Goal,
+    span: Span,
+    condition: String,
+    p: &Bool,
+    q: &Bool,
+    object: Option<&Expr>,
+) {
+    let (status, counterexample) = match result {
+        SatResult::Sat => (
+            Status::Failed,
+            Some(Counterexample::new(ctx, solver, p, q, object)),
+        ),
+        SatResult::Unsat => (Status::Proven, None),
+        SatResult::Unknown => (Status::Unknown, None),
+    };
+    let reason = match result {
+        SatResult::Unknown => solver.reason_unknown().map(|x| unknown_reason(&x)),
+        _ => None,
+    };
+
+    report::record(Obligation {
+        goal,
+        condition,
+        span,
+        status,
+        time,
+        counterexample,
+        reason,
+        source: None,
+    });
+}
+
+/// Z3's reason for giving up, with the limits named the same way as the options
+fn unknown_reason(reason: &str) -> String {
+    match reason {
+        "timeout" | "canceled" => String::from("timeout"),
+        "max. resource limit exceeded" => String::from("rlimit"),
+        x => x
+            .strip_prefix('(')
+            .and_then(|x| x.strip_suffix(')'))
+            .unwrap_or(x)
+            .to_string(),
+    }
+}
+
+fn define_return_value(output: Type, return_value: Value, span: Span) -> Command {
     match output {
-        Type::Array(_, _) => Command::Binding(Binding::Assignment(
-            Variable::Named(String::from("return_value")),
-            output,
-            return_value,
-            false,
-        )),
-        Type::ArraySlice(_) => Command::Binding(Binding::Assignment(
-            Variable::Named(String::from("return_value")),
-            output,
-            return_value,
-            false,
-        )),
-        Type::Bool => Command::Binding(Binding::Assignment(
-            Variable::Named(String::from("return_value")),
-            output,
-            return_value,
-            false,
-        )),
-        Type::I32 => Command::Binding(Binding::Assignment(
-            Variable::Named(String::from("return_value")),
-            output,
-            return_value,
-            false,
-        )),
-        Type::Reference(_x) => {
-            unimplemented!()
-        }
-        Type::ReferenceMutable(_x) => {
-            unimplemented!()
-        }
-        Type::Tuple(_) => {
-            //Command::Binding(Binding::Assignment(
-            //Variable::Named(String::from("return_value")),
-            //output,
-            //return_value,
-            //false,
-            //))
-            Command::Noop
+        Type::Array(_, _)
+        | Type::ArraySlice(_)
+        | Type::Bool
+        | Type::I8
+        | Type::I16
+        | Type::I32
+        | Type::I64
+        | Type::U8
+        | Type::U16
+        | Type::U32
+        | Type::U64
+        | Type::Usize
+        | Type::Reference(_)
+        | Type::ReferenceMutable(_)
+        | Type::Tuple(_) => {
+            // A returned tuple variable is parsed as an expression
+            let return_value = match (&output, return_value) {
+                (Type::Tuple(_), Value::Expr(Expr::Value(v)))
+                | (Type::Tuple(_), Value::Bool(Bool::Value(v))) => *v,
+                (_, x) => x,
+            };
+            Command::Binding(
+                Binding::Assignment(
+                    Variable::Named(String::from("return_value")),
+                    output,
+                    return_value,
+                    false,
+                ),
+                span,
+            )
         }
         Type::Unit => Command::Noop,
+        Type::Infer => panic!("Function output type left to infer"),
+    }
+}
+
+/// Replace all the aliased names in the condition (or value)
+fn unalias<T: Swapper>(x: T, aliases: &[(Variable, Value)]) -> T {
+    let mut x = x;
+    for (from, to) in aliases {
+        x = x.swap(from.clone(), to.clone());
     }
+    x
+}
+
+fn unalias_variable(var: Variable, aliases: &[(Variable, Value)]) -> Variable {
+    match unalias(Value::Variable(var), aliases) {
+        Value::Variable(v) => v,
+        v => panic!("Reference used as a variable: {}", v),
+    }
+}
+
+/// The borrows of local variables are replaced with the borrowed variables themselves
+/// The borrow checker makes sure that nothing else touches a variable while it's borrowed
+fn resolve_references(code: Vec<Command>, aliases: &mut Vec<(Variable, Value)>) -> Vec<Command> {
+    let mut result = Vec::new();
+    for command in code {
+        match command {
+            Command::Binding(Binding::Assignment(var, t, val, m), span) => {
+                let val = unalias(val, aliases);
+                let target = match (&t, &val) {
+                    (Type::Reference(_), Value::Reference(v))
+                    | (Type::ReferenceMutable(_), Value::ReferenceMutable(v)) => match &**v {
+                        Value::Variable(x @ Variable::Named(_)) => Some(x.clone()),
+                        _ if matches!(t, Type::ReferenceMutable(_)) => {
+                            panic!("Only variables can be mutably borrowed: {}", val)
+                        }
+                        _ => None,
+                    },
+                    // Reborrow of a reference parameter
+                    (Type::Reference(_), Value::Variable(x @ Variable::Named(_)))
+                    | (Type::ReferenceMutable(_), Value::Variable(x @ Variable::Named(_))) => {
+                        Some(x.clone())
+                    }
+                    _ => None,
+                };
+                match target {
+                    Some(x) => aliases.push((var, Value::Variable(x))),
+                    None => {
+                        result.push(Command::Binding(Binding::Assignment(var, t, val, m), span))
+                    }
+                }
+            }
+            Command::Binding(Binding::Tuple(vec), span) => {
+                let vec = resolve_references(vec, aliases);
+                result.push(Command::Binding(Binding::Tuple(vec), span));
+            }
+            Command::Assignment(a, span) => {
+                result.push(Command::Assignment(unalias_assignment(a, aliases), span))
+            }
+            Command::ProveControl(ProveControl::Assert(b), span) => result.push(
+                Command::ProveControl(ProveControl::Assert(unalias(b, aliases)), span),
+            ),
+            Command::Block(Block::If(conds, comms, el), span) => {
+                let conds = conds.into_iter().map(|x| unalias(x, aliases)).collect();
+                let comms = comms
+                    .into_iter()
+                    .map(|x| resolve_references(x, &mut aliases.clone()))
+                    .collect();
+                let el = resolve_references(el, &mut aliases.clone());
+                result.push(Command::Block(Block::If(conds, comms, el), span));
+            }
+            Command::Block(Block::While(cond, comms, inv, var), span) => {
+                result.push(Command::Block(
+                    Block::While(
+                        unalias(cond, aliases),
+                        resolve_references(comms, &mut aliases.clone()),
+                        unalias(inv, aliases),
+                        unalias(var, aliases),
+                    ),
+                    span,
+                ));
+            }
+            Command::Block(Block::ForRange(iter, first, last, comms, inv), span) => {
+                result.push(Command::Block(
+                    Block::ForRange(
+                        iter,
+                        unalias(first, aliases),
+                        unalias(last, aliases),
+                        resolve_references(comms, &mut aliases.clone()),
+                        unalias(inv, aliases),
+                    ),
+                    span,
+                ));
+            }
+            x => result.push(x),
+        }
+    }
+    result
+}
+
+fn unalias_assignment(a: Assignment, aliases: &[(Variable, Value)]) -> Assignment {
+    match a {
+        Assignment::Single(var, val) => {
+            if let (Variable::Named(_), Value::Reference(_) | Value::ReferenceMutable(_)) =
+                (&var, &val)
+            {
+                if aliases.iter().any(|(x, _)| *x == var) {
+                    panic!("Borrowing again into {} is not supported", var);
+                }
+            }
+            Assignment::Single(unalias_variable(var, aliases), unalias(val, aliases))
+        }
+        Assignment::Tuple(vec) => Assignment::Tuple(
+            vec.into_iter()
+                .map(|x| unalias_assignment(x, aliases))
+                .collect(),
+        ),
+    }
+}
+
+/// Put the command before every `continue` of the loop, e.g. the iterator's increment of a for loop
+fn before_continue(code: Vec<Command>, command: &Command) -> Vec<Command> {
+    let mut result = Vec::new();
+    for i in code {
+        match i {
+            Command::Continue(span) => {
+                result.push(command.clone());
+                result.push(Command::Continue(span));
+            }
+            Command::Block(Block::If(conds, comms, el), span) => {
+                let comms = comms
+                    .into_iter()
+                    .map(|x| before_continue(x, command))
+                    .collect();
+                let el = before_continue(el, command);
+                result.push(Command::Block(Block::If(conds, comms, el), span));
+            }
+            // The nested loops have their own continues
+            x => result.push(x),
+        }
+    }
+    result
+}
+
+/// Assign the value of every `return` to the return_value, right before it
+fn lower_returns(code: Vec<Command>, output: &Type) -> Vec<Command> {
+    let mut result = Vec::new();
+    for i in code {
+        match i {
+            Command::Return(val, span) => {
+                result.push(define_return_value(output.clone(), val, span));
+                result.push(Command::Return(Value::Unit, span));
+            }
+            Command::Block(Block::If(conds, comms, el), span) => {
+                let comms = comms
+                    .into_iter()
+                    .map(|x| lower_returns(x, output))
+                    .collect();
+                let el = lower_returns(el, output);
+                result.push(Command::Block(Block::If(conds, comms, el), span));
+            }
+            Command::Block(Block::While(cond, comms, inv, var), span) => {
+                let comms = lower_returns(comms, output);
+                result.push(Command::Block(Block::While(cond, comms, inv, var), span));
+            }
+            Command::Block(Block::ForRange(iter, first, last, comms, inv), span) => {
+                let comms = lower_returns(comms, output);
+                result.push(Command::Block(
+                    Block::ForRange(iter, first, last, comms, inv),
+                    span,
+                ));
+            }
+            x => result.push(x),
+        }
+    }
+    result
+}
+
+/// Places of all the `return`s in the code, nested ones included
+fn return_spans(code: &[Command]) -> Vec<Span> {
+    let mut result = Vec::new();
+    for i in code {
+        match i {
+            Command::Return(_, s) => result.push(*s),
+            Command::Block(Block::If(_, comms, el), _) => {
+                for c in comms {
+                    result.extend(return_spans(c));
+                }
+                result.extend(return_spans(el));
+            }
+            Command::Block(Block::While(_, comms, _, _), _)
+            | Command::Block(Block::ForRange(_, _, _, comms, _), _) => {
+                result.extend(return_spans(comms))
+            }
+            _ => {}
+        }
+    }
+    result
 }
 
 /// Return function with some wrapping for e.g. return_value, so it's ready to be taken by ProveBlock
-fn wrap_function(f: Function) -> Function {
+/// The `functions` are used to replace the function calls with contracts of the called functions
+fn wrap_function(f: Function, functions: &[Function]) -> Function {
     let mut to_prove = f.clone();
 
     let mut temp = f.content;
@@ -148,8 +901,17 @@ fn wrap_function(f: Function) -> Function {
     // Set the noop as first command, so the further generation works fine, even if assertion is first in the code
     temp.insert(0, Command::Noop);
 
-    // Assign the value being returned to the return_value variable
-    temp.push(define_return_value(f.output, f.return_value));
+    // Assign the value being returned to the return_value variable,
+    // there's none at the end when the function never gets there (the typing made sure of it)
+    let mut temp = lower_returns(temp, &f.output);
+    if !matches!(f.return_value, Value::Unit) {
+        temp.push(define_return_value(f.output, f.return_value, f.return_span));
+    }
+
+    let temp = resolve_references(temp, &mut Vec::new());
+
+    let mut counter = 0;
+    let temp = lower_calls(temp, functions, &mut counter);
 
     to_prove.content = temp.clone();
 
@@ -162,12 +924,399 @@ fn wrap_function(f: Function) -> Function {
     to_prove
 }
 
+/// Create the contract of the called function, with its parameters replaced by the arguments
+/// Returns the variable holding the call's result and the commands checking and assuming the contract
+fn call_contract(
+    name: String,
+    args: Vec<Value>,
+    functions: &[Function],
+    counter: &mut usize,
+) -> (Value, Vec<Command>) {
+    let callee = match functions.iter().find(|f| f.name == name) {
+        Some(f) => f.clone(),
+        None => panic!("Called function {} is not defined!", name),
+    };
+    assert!(
+        callee.input.len() == args.len(),
+        "Function {} called with incorrect number of arguments",
+        name
+    );
+
+    *counter += 1;
+    let prefix = format!("__call_{}_{}", name, counter);
+    let fresh = |x: &str| Variable::Named(format!("{}_{}", prefix, x));
+
+    for (x, t) in function_types(&callee) {
+        set_type(&format!("{}_{}", prefix, x), Some(t));
+    }
+
+    let supported = |t: &Type| *t == Type::Bool || t.is_int() || matches!(t, Type::Tuple(_));
+    let mut params = Vec::new();
+    let mut borrowed = Vec::new();
+    for i in callee.input {
+        match i {
+            Binding::Declaration(Variable::Named(x), t, m) if supported(&t) => params.push((x, m)),
+            Binding::Declaration(Variable::Named(x), Type::Reference(t), m) if supported(&t) => {
+                params.push((x, m))
+            }
+            Binding::Declaration(Variable::Named(x), Type::ReferenceMutable(t), m)
+                if supported(&t) =>
+            {
+                borrowed.push(x.clone());
+                params.push((x, m))
+            }
+            _ => panic!("Unsupported parameter in call to {}: {}", name, i),
+        }
+    }
+
+    // The callee's local variables can be used in its postcondition
+    let mut locals = HashSet::new();
+    for i in callee.content {
+        locals.extend(i.get_affected_variables());
+    }
+
+    // First rename all the callee's names to fresh ones, so they can't clash with the caller's
+    let mut pre = callee.precondition;
+    let mut post = callee.postcondition;
+    for (x, _) in params.iter() {
+        for name in [x.clone(), x.clone() + "'old"].iter() {
+            let var = Variable::Named(name.clone());
+            pre = pre.swap(var.clone(), Value::Variable(fresh(name)));
+            post = post.swap(var, Value::Variable(fresh(name)));
+        }
+    }
+    for i in locals {
+        if let Variable::Named(x) = i.clone() {
+            if !params.iter().any(|(p, _)| *p == x) {
+                post = post.swap(i, Value::Variable(fresh(&x)));
+            }
+        }
+    }
+    let result = fresh("return_value");
+    post = post.swap(
+        Variable::Named(String::from("return_value")),
+        Value::Variable(result.clone()),
+    );
+
+    // Then put the arguments in place of the parameters
+    let mut writes = Vec::new();
+    for ((x, m), arg) in params.into_iter().zip(args) {
+        // A reference parameter stands for the value it points to
+        let arg = match arg {
+            Value::Reference(v) | Value::ReferenceMutable(v) => *v,
+            v => v,
+        };
+        let old = fresh(&(x.clone() + "'old"));
+        pre = pre.swap(fresh(&x), arg.clone());
+        pre = pre.swap(old.clone(), arg.clone());
+        post = post.swap(old, arg.clone());
+        if borrowed.contains(&x) {
+            // The borrowed variable ends up with whatever the callee left behind the reference
+            match arg {
+                Value::Variable(v) => writes.push(Command::Assignment(
+                    Assignment::Single(v, Value::Variable(fresh(&x))),
+                    Span::default(),
+                )),
+                _ => panic!("Only variables can be mutably borrowed in call to {}", name),
+            }
+        } else if !m {
+            // Changes made to a mutable parameter are not visible to the caller,
+            // so its final value stays unknown
+            post = post.swap(fresh(&x), arg);
+        }
+    }
+
+    let mut commands = vec![Command::ProveControl(
+        ProveControl::FunctionCall(pre, post),
+        Span::default(),
+    )];
+    commands.extend(writes);
+    (Value::Variable(result), commands)
+}
+
+/// Replace the function calls with fresh variables
+/// The contracts of the called functions are returned as commands that have to run before
+trait CallLowering: Sized {
+    fn lower_calls(self, functions: &[Function], counter: &mut usize) -> (Self, Vec<Command>);
+}
+
+impl CallLowering for Value {
+    fn lower_calls(self, functions: &[Function], counter: &mut usize) -> (Self, Vec<Command>) {
+        match self {
+            Value::Expr(a) => {
+                let (a, calls) = a.lower_calls(functions, counter);
+                (Value::Expr(a), calls)
+            }
+            Value::Bool(a) => {
+                let (a, calls) = a.lower_calls(functions, counter);
+                (Value::Bool(a), calls)
+            }
+            Value::Variable(Variable::ArrayElem(name, index)) => {
+                let (index, calls) = index.lower_calls(functions, counter);
+                (
+                    Value::Variable(Variable::ArrayElem(name, Box::new(index))),
+                    calls,
+                )
+            }
+            Value::Tuple(vec) => {
+                let (vec, calls) = lower_calls_vec(vec, functions, counter);
+                (Value::Tuple(vec), calls)
+            }
+            Value::Array(vec) => {
+                let (vec, calls) = lower_calls_vec(vec, functions, counter);
+                (Value::Array(vec), calls)
+            }
+            Value::FunctionCall(name, args) => {
+                // Arguments are evaluated before the call
+                let (args, mut calls) = lower_calls_vec(args, functions, counter);
+                let (result, contract) = call_contract(name, args, functions, counter);
+                calls.extend(contract);
+                (result, calls)
+            }
+            Value::Dereference(a) => {
+                let (a, calls) = a.lower_calls(functions, counter);
+                (Value::Dereference(Box::new(a)), calls)
+            }
+            Value::Reference(a) => {
+                let (a, calls) = a.lower_calls(functions, counter);
+                (Value::Reference(Box::new(a)), calls)
+            }
+            Value::ReferenceMutable(a) => {
+                let (a, calls) = a.lower_calls(functions, counter);
+                (Value::ReferenceMutable(Box::new(a)), calls)
+            }
+            Value::Ternary(c, a, b) => {
+                // Only the chosen branch is evaluated
+                let (c, mut calls) = c.lower_calls(functions, counter);
+                let (a, calls_a) = a.lower_calls(functions, counter);
+                let (b, calls_b) = b.lower_calls(functions, counter);
+                if !calls_a.is_empty() || !calls_b.is_empty() {
+                    calls.push(Command::Block(
+                        Block::If(vec![c.clone()], vec![calls_a], calls_b),
+                        Span::default(),
+                    ));
+                }
+                (Value::Ternary(c, Box::new(a), Box::new(b)), calls)
+            }
+            x => (x, Vec::new()),
+        }
+    }
+}
+
+impl CallLowering for Expr {
+    fn lower_calls(self, functions: &[Function], counter: &mut usize) -> (Self, Vec<Command>) {
+        match self {
+            Expr::Number(_) => (self, Vec::new()),
+            Expr::Value(a) => {
+                let (a, calls) = a.lower_calls(functions, counter);
+                (Expr::Value(Box::new(a)), calls)
+            }
+            Expr::Op(a, op, b) => {
+                let (a, mut calls) = a.lower_calls(functions, counter);
+                let (b, calls_b) = b.lower_calls(functions, counter);
+                calls.extend(calls_b);
+                (Expr::Op(Box::new(a), op, Box::new(b)), calls)
+            }
+        }
+    }
+}
+
+impl CallLowering for Bool {
+    fn lower_calls(self, functions: &[Function], counter: &mut usize) -> (Self, Vec<Command>) {
+        let two = |a: Expr, b: Expr, counter: &mut usize| {
+            let (a, mut calls) = a.lower_calls(functions, counter);
+            let (b, calls_b) = b.lower_calls(functions, counter);
+            calls.extend(calls_b);
+            (a, b, calls)
+        };
+
+        match self {
+            // The right side is only evaluated when the left one doesn't decide already
+            Bool::And(a, b) => {
+                let (a, mut calls) = a.lower_calls(functions, counter);
+                let (b, calls_b) = b.lower_calls(functions, counter);
+                calls.extend(guard_calls(a.clone(), calls_b));
+                (Bool::And(Box::new(a), Box::new(b)), calls)
+            }
+            Bool::Or(a, b) => {
+                let (a, mut calls) = a.lower_calls(functions, counter);
+                let (b, calls_b) = b.lower_calls(functions, counter);
+                calls.extend(guard_calls(Bool::Not(Box::new(a.clone())), calls_b));
+                (Bool::Or(Box::new(a), Box::new(b)), calls)
+            }
+            Bool::Not(a) => {
+                let (a, calls) = a.lower_calls(functions, counter);
+                (Bool::Not(Box::new(a)), calls)
+            }
+            Bool::Value(a) => {
+                let (a, calls) = a.lower_calls(functions, counter);
+                (Bool::Value(Box::new(a)), calls)
+            }
+            Bool::Equal(a, b) => {
+                let (a, b, calls) = two(a, b, counter);
+                (Bool::Equal(a, b), calls)
+            }
+            Bool::GreaterEqual(a, b) => {
+                let (a, b, calls) = two(a, b, counter);
+                (Bool::GreaterEqual(a, b), calls)
+            }
+            Bool::LowerEqual(a, b) => {
+                let (a, b, calls) = two(a, b, counter);
+                (Bool::LowerEqual(a, b), calls)
+            }
+            Bool::GreaterThan(a, b) => {
+                let (a, b, calls) = two(a, b, counter);
+                (Bool::GreaterThan(a, b), calls)
+            }
+            Bool::LowerThan(a, b) => {
+                let (a, b, calls) = two(a, b, counter);
+                (Bool::LowerThan(a, b), calls)
+            }
+            // Quantifiers are only used in annotations, not in the code
+            x => (x, Vec::new()),
+        }
+    }
+}
+
+/// The contracts are checked where the calling command is
+fn placed(calls: Vec<Command>, span: Span) -> Vec<Command> {
+    calls.into_iter().map(|x| x.with_span(span)).collect()
+}
+
+/// The calls are only made when the condition holds
+fn guard_calls(cond: Bool, calls: Vec<Command>) -> Vec<Command> {
+    if calls.is_empty() {
+        return calls;
+    }
+    vec![Command::Block(
+        Block::If(vec![cond], vec![calls], Vec::new()),
+        Span::default(),
+    )]
+}
+
+fn lower_calls_vec(
+    vec: Vec<Value>,
+    functions: &[Function],
+    counter: &mut usize,
+) -> (Vec<Value>, Vec<Command>) {
+    let mut result = Vec::new();
+    let mut calls = Vec::new();
+    for i in vec {
+        let (i, c) = i.lower_calls(functions, counter);
+        result.push(i);
+        calls.extend(c);
+    }
+    (result, calls)
+}
+
+fn lower_calls_assignment(
+    a: Assignment,
+    functions: &[Function],
+    counter: &mut usize,
+) -> (Assignment, Vec<Command>) {
+    match a {
+        Assignment::Single(var, val) => {
+            let (val, calls) = val.lower_calls(functions, counter);
+            (Assignment::Single(var, val), calls)
+        }
+        Assignment::Tuple(vec) => {
+            let mut result = Vec::new();
+            let mut calls = Vec::new();
+            for i in vec {
+                let (i, c) = lower_calls_assignment(i, functions, counter);
+                result.push(i);
+                calls.extend(c);
+            }
+            (Assignment::Tuple(result), calls)
+        }
+    }
+}
+
+/// Put the contracts of the called functions in place of the calls in the code
+/// Only the values that are evaluated once per command (assignments, bindings, if conditions and range ends) are supported,
+/// the validator rejects the calls anywhere else
+fn lower_calls(code: Vec<Command>, functions: &[Function], counter: &mut usize) -> Vec<Command> {
+    let mut result = Vec::new();
+    for command in code {
+        match command {
+            Command::Binding(Binding::Assignment(var, t, val, m), span) => {
+                let (val, calls) = val.lower_calls(functions, counter);
+                result.extend(placed(calls, span));
+                result.push(Command::Binding(Binding::Assignment(var, t, val, m), span));
+            }
+            Command::Binding(Binding::Tuple(vec), span) => {
+                // All the values are evaluated before any binding happens
+                let mut bindings = Vec::new();
+                for i in lower_calls(vec, functions, counter) {
+                    match i {
+                        Command::ProveControl(_, _) | Command::Assignment(_, _) => {
+                            result.push(i.with_span(span))
+                        }
+                        _ => bindings.push(i),
+                    }
+                }
+                result.push(Command::Binding(Binding::Tuple(bindings), span));
+            }
+            Command::Assignment(a, span) => {
+                let (a, calls) = lower_calls_assignment(a, functions, counter);
+                result.extend(placed(calls, span));
+                result.push(Command::Assignment(a, span));
+            }
+            Command::Block(Block::If(conds, comms, el), span) => {
+                // The condition of an `else if` is only evaluated when the ones before are false
+                let mut new_conds: Vec<Bool> = Vec::new();
+                for i in conds {
+                    let (i, calls) = i.lower_calls(functions, counter);
+                    let skipped = new_conds.iter().fold(Bool::True, |acc, x| {
+                        Bool::And(Box::new(acc), Box::new(Bool::Not(Box::new(x.clone()))))
+                    });
+                    if new_conds.is_empty() {
+                        result.extend(placed(calls, span));
+                    } else {
+                        result.extend(placed(guard_calls(skipped, calls), span));
+                    }
+                    new_conds.push(i);
+                }
+                let mut new_comms = Vec::new();
+                for i in comms {
+                    new_comms.push(lower_calls(i, functions, counter));
+                }
+                let el = lower_calls(el, functions, counter);
+                result.push(Command::Block(Block::If(new_conds, new_comms, el), span));
+            }
+            Command::Block(Block::While(cond, comms, inv, var), span) => {
+                let comms = lower_calls(comms, functions, counter);
+                result.push(Command::Block(Block::While(cond, comms, inv, var), span));
+            }
+            Command::Block(Block::ForRange(iter, first, last, comms, inv), span) => {
+                let (first, calls) = first.lower_calls(functions, counter);
+                result.extend(placed(calls, span));
+                let (last, calls) = last.lower_calls(functions, counter);
+                result.extend(placed(calls, span));
+                let comms = lower_calls(comms, functions, counter);
+                result.push(Command::Block(
+                    Block::ForRange(iter, first, last, comms, inv),
+                    span,
+                ));
+            }
+            x => result.push(x),
+        }
+    }
+    result
+}
+
 impl ProveBlock {
     /// Runs all the commands needed to just check whether the prove is successfull for this block (create, calculate, prove)
     fn simple_check(self) -> bool {
-        let triples = self.create_triples();
+        let goal = self.goal;
+        let mut triples = self.create_triples();
+        // The asserts and calls in the loop are proven along with the invariant, not again here
+        if goal == Goal::VariantDecrease || goal == Goal::LoopExit {
+            triples.retain(|x| x.goal == goal);
+        }
         log::trace!("triples: {:?}", triples.clone());
 
+        let mut result = true;
         for t in triples {
             let mut final_res = true;
             let (to_check, ok) = t.calculate();
@@ -179,10 +1328,13 @@ impl ProveBlock {
                 }
             }
             if !final_res {
-                return false;
+                if !keep_going() {
+                    return false;
+                }
+                result = false;
             }
         }
-        true
+        result
     }
 
     /// Wrap asserts in {p}commands{q} triples, the {q} values are not yet expanded properly
@@ -192,14 +1344,18 @@ impl ProveBlock {
             precondition,
             code: mut commands,
             postcondition,
+            postcondition_original,
+            goal,
+            span,
             ..
         } = self;
 
         // Do this ugly asserting thing in single place (here)
         commands.push(Command::Noop);
-        commands.push(Command::ProveControl(ProveControl::Assert(
-            postcondition.clone(),
-        )));
+        commands.push(Command::ProveControl(
+            ProveControl::Assert(postcondition.clone()),
+            Span::default(),
+        ));
 
         log::trace!("OUR CODE - START");
         for i in commands.clone() {
@@ -210,20 +1366,41 @@ impl ProveBlock {
         let mut triples = Vec::new();
 
         let mut code_till_now = Vec::new();
+        let mut location = span;
 
-        for command in commands {
+        let last = commands.len() - 1;
+        for (n, command) in commands.into_iter().enumerate() {
             log::debug!("{:?}", command);
+            // The commands added by the prover are placed where the ones before them are
+            if command.span().is_known() {
+                location = command.span();
+            }
             match command.clone() {
-                Command::ProveControl(x) => {
-                    let a = match x {
-                        ProveControl::Assert(z) => z,
-                    };
-
+                Command::ProveControl(ProveControl::Assert(a), _) => {
                     code_till_now.push(command.clone());
-                    triples.push(prove_block(
-                        precondition.clone(),
-                        code_till_now.clone(),
-                        a.clone(),
+                    let mut triple =
+                        prove_block(precondition.clone(), code_till_now.clone(), a.clone());
+                    // Only the last assert is the real postcondition of the block
+                    if n == last {
+                        triple.postcondition_original = postcondition_original.clone();
+                        triple.goal = goal;
+                        triple.span = span;
+                    } else {
+                        triple.goal = Goal::Assertion;
+                        triple.span = location;
+                    }
+                    triples.push(triple);
+                }
+                Command::ProveControl(ProveControl::FunctionCall(pre, post), s) => {
+                    // The callee's precondition has to hold right before the call
+                    let mut triple = prove_block(precondition.clone(), code_till_now.clone(), pre);
+                    triple.goal = Goal::CallPrecondition;
+                    triple.span = location;
+                    triples.push(triple);
+                    // It has its own triple now, the code after the call only needs the postcondition
+                    code_till_now.push(Command::ProveControl(
+                        ProveControl::FunctionCall(Bool::True, post),
+                        s,
                     ));
                 }
                 z => {
@@ -248,10 +1425,22 @@ impl ProveBlock {
             postcondition: mut q,
             precondition_original: p_orig,
             postcondition_original: q_orig,
+            goal,
+            span,
         } = self.clone();
 
         let comms_real = _comms.clone();
 
+        // The commands added by the prover are placed where the ones before them are
+        let mut locations = Vec::new();
+        let mut location = span;
+        for comm in comms_real.iter() {
+            if comm.span().is_known() {
+                location = comm.span();
+            }
+            locations.push(location);
+        }
+
         let mut comms = Vec::new();
         // Invert array for proving - backwards
         let mut check = true;
@@ -259,7 +1448,7 @@ impl ProveBlock {
             let t = _comms.pop();
             match t {
                 Some(x) => {
-                    comms.push(x);
+                    comms.push((x, locations.pop().unwrap()));
                 }
                 None => {
                     check = false;
@@ -269,13 +1458,13 @@ impl ProveBlock {
         // Invert vector here - backwards, for proper postcondition expansion
         log::trace!("{}", q);
         log::trace!("NEW TRIPLE");
-        for comm in comms.clone() {
+        for (comm, location) in comms.clone() {
             match comm.clone() {
-                Command::ProveControl(_) => {}
+                Command::ProveControl(ProveControl::Assert(_), _) => {}
                 _ => {
                     log::trace!("{}", comm.clone());
                     log::trace!("PRE BEFORE: {}", q.clone());
-                    let (_q, t) = comm.get_pre(q.clone(), p.clone());
+                    let (_q, t) = comm.get_pre(q.clone(), p.clone(), location);
                     if !t {
                         return (self, false);
                     }
@@ -295,6 +1484,8 @@ impl ProveBlock {
                 postcondition: q,
                 precondition_original: p_orig,
                 postcondition_original: q_orig,
+                goal,
+                span,
             },
             true,
         )
@@ -308,6 +1499,8 @@ impl ProveBlock {
             postcondition: q,
             precondition_original: p_orig,
             postcondition_original: q_orig,
+            goal,
+            span,
         } = self;
 
         log::debug!("START TO PROVE FINAL LIST:");
@@ -327,38 +1520,44 @@ impl ProveBlock {
         cfg.set_model_generation(true);
 
         let ctx = z3::Context::new(&cfg);
-        let t = z3::Solver::new(&ctx);
+        let t = solver(&ctx);
 
-        let (mut checks, _pre) = p.clone().as_bool(&ctx);
-        let (checks_post, _post) = q.clone().as_bool(&ctx);
+        let (mut checks, _pre) = backend().as_bool(&ctx, p.clone());
+        let (checks_post, _post) = backend().as_bool(&ctx, q.clone());
         checks.extend(checks_post);
+        let _pre = z3::ast::Bool::and(&ctx, &[&_pre, &backend().assumptions(&ctx, &p, &q)]);
 
-        // Check overflows and underflows
-        for i in checks {
-            t.reset();
-            t.assert(&_pre.implies(&i.check).not());
-            let f = t.check();
-            if f != z3::SatResult::Unsat {
-                log::warn!("Bounds check warning: possible {} {}", i.name, i.object);
-            }
-        }
-        t.reset();
+        let checks_ok = prove_checks(&ctx, t.as_ref(), checks, &_pre, span, &p, &q);
 
-        t.assert(&_pre.implies(&_post).not());
+        let query = Term::new(&_pre.implies(&_post).not());
+        t.assert(&query);
+        emit_query(goal, &q_orig.to_string(), &query);
 
+        let start = Instant::now();
         let f = t.check();
+        let time = start.elapsed();
         log::debug!("{:?}", f);
-        log::debug!("{:?}", t.get_model());
         let result = Some(f);
 
+        record_obligation(
+            &ctx,
+            t.as_ref(),
+            f,
+            time,
+            goal,
+            span,
+            q_orig.to_string(),
+            &p,
+            &q,
+            None,
+        );
+
         match result {
-            Some(z3::SatResult::Sat) => {
-                log::debug!("Model: {:?}", t.get_model());
+            Some(SatResult::Sat) => {
                 let mut temp = String::from("");
                 for i in commands {
                     temp += &format!("{}\n", i).to_owned();
                 }
-                println!("Failed to prove: {} => {}", p, q);
                 log::info!(
                     "Failed to prove: {} => {} with code:\n{}",
                     p_orig,
@@ -368,11 +1567,11 @@ impl ProveBlock {
                 log::debug!("Failed to prove: {} => {}", p, q);
                 return false;
             }
-            Some(z3::SatResult::Unsat) => {
+            Some(SatResult::Unsat) => {
                 log::debug!("Proven: {:?}", commands);
             }
-            Some(z3::SatResult::Unknown) => {
-                println!("Proving stopped!");
+            Some(SatResult::Unknown) => {
+                log::warn!("Proving stopped: {:?}", t.reason_unknown());
                 return false;
             }
             _ => {
@@ -380,108 +1579,316 @@ impl ProveBlock {
             }
         }
 
-        true
+        checks_ok
     }
 }
 
 /// Prove the program provided as an input.
 /// The funcs_to_prove vec may specify names of the functions to be proved, if empty all the functions are proved by default
 pub fn prove(input: Program, funcs_to_prove: Vec<String>) -> bool {
+    verify(input, funcs_to_prove).proven()
+}
+
+/// Prove the program, same as prove, but returns the results of all the obligations checked
+pub fn verify(input: Program, funcs_to_prove: Vec<String>) -> Report {
+    verify_with(input, funcs_to_prove, Options::default())
+}
+
+/// What the triples of a function need to be proven, the thread-locals are set from it
+struct FunctionSetup {
+    name: String,
+    arith: Arith,
+    limits: Limits,
+    types: HashMap<String, Type>,
+    postcondition: Bool,
+}
+
+/// A single triple to prove, independent of all the others
+struct Task {
+    function: usize,
+    triple: ProveBlock,
+    returns: Returns,
+}
+
+/// Whether the triple was proven, the obligations checked on the way and the queries they took
+type TaskResult = (bool, Vec<Obligation>, Vec<(Goal, String)>);
+
+/// Prove the triple with the thread-locals set up for its function
+fn run_task(setup: &FunctionSetup, task: Task, options: &Options) -> TaskResult {
+    OPTIONS.with(|x| {
+        *x.borrow_mut() = options.clone();
+        x.borrow_mut().arith = setup.arith;
+        x.borrow_mut().limits = setup.limits;
+    });
+    TYPES.with(|x| *x.borrow_mut() = setup.types.clone());
+    EXITS.with(|x| {
+        let mut x = x.borrow_mut();
+        x.postcondition = setup.postcondition.clone();
+        x.returns = task.returns;
+    });
+
+    let (current, temp_res) = task.triple.calculate();
+    let final_res = if temp_res { current.prove() } else { temp_res };
+    let queries = QUERIES.with(|x| x.borrow_mut().drain(..).collect());
+    (final_res, report::take(), queries)
+}
+
+/// Prove the tasks on the given number of threads, the results are in the order of the tasks
+/// Without keep_going the tasks after a failed one are skipped (None), they wouldn't be reported anyway
+fn run_tasks(
+    functions: &[FunctionSetup],
+    tasks: Vec<Task>,
+    options: &Options,
+) -> Vec<Option<TaskResult>> {
+    let count = tasks.len();
+    let tasks: Vec<Mutex<Option<Task>>> = tasks.into_iter().map(|x| Mutex::new(Some(x))).collect();
+    let results: Vec<Mutex<Option<TaskResult>>> = (0..count).map(|_| Mutex::new(None)).collect();
+    let next = AtomicUsize::new(0);
+    let first_failed = AtomicUsize::new(count);
+
+    let worker = || loop {
+        let i = next.fetch_add(1, Ordering::SeqCst);
+        if i >= count || (!options.keep_going && i > first_failed.load(Ordering::SeqCst)) {
+            break;
+        }
+        let task = tasks[i].lock().unwrap().take().unwrap();
+        let result = run_task(&functions[task.function], task, options);
+        if !result.0 {
+            first_failed.fetch_min(i, Ordering::SeqCst);
+        }
+        *results[i].lock().unwrap() = Some(result);
+    };
+
+    if options.jobs <= 1 {
+        worker();
+    } else {
+        std::thread::scope(|s| {
+            for _ in 0..options.jobs.min(count) {
+                // As much stack as the main thread, the weakest precondition calculation is recursive
+                std::thread::Builder::new()
+                    .stack_size(8 << 20)
+                    .spawn_scoped(s, worker)
+                    .expect("failed to start a proving thread");
+            }
+        });
+    }
+
+    results
+        .into_iter()
+        .map(|x| x.into_inner().unwrap())
+        .collect()
+}
+
+/// Same as verify, but with the options other than the default ones
+pub fn verify_with(input: Program, funcs_to_prove: Vec<String>, options: Options) -> Report {
+    let mut result = Report::default();
+    // Drop anything left from the previous runs
+    report::take();
+    QUERIES.with(|x| x.borrow_mut().clear());
+    OPTIONS.with(|x| *x.borrow_mut() = options.clone());
+    // The backends rely on the values being used as their types say
+    let input = crate::typing::infer(input);
+
+    let mut functions = Vec::new();
+    let mut tasks = Vec::new();
     for func in input.content.clone() {
         let f_name = func.name.clone();
         if !funcs_to_prove.contains(&f_name) && !funcs_to_prove.is_empty() {
             log::warn!("Skipping function: {}", f_name);
             continue;
-        } else {
-            log::warn!("Proving function: {}", f_name);
         }
 
-        let wrapped_func = wrap_function(func);
+        let arith = func.arith.unwrap_or(options.arith);
+        let limits = func.limits.or(options.limits);
+        OPTIONS.with(|x| x.borrow_mut().arith = arith);
 
-        let to_prove = prove_block(
-            wrapped_func.precondition,
-            wrapped_func.content,
-            wrapped_func.postcondition,
+        TYPES.with(|x| *x.borrow_mut() = function_types(&func));
+        let wrapped_func = wrap_function(func, &input.content);
+        // Along with the results and parameters of the calls, set while lowering them
+        let types = TYPES.with(|x| x.borrow().clone());
+
+        let mut to_prove = prove_block(
+            wrapped_func.precondition.clone(),
+            wrapped_func.content.clone(),
+            wrapped_func.postcondition.clone(),
         );
+        to_prove.span = wrapped_func.span;
+
+        let mut triples = to_prove.create_triples();
+
+        // The postcondition has to hold at every return too, each of them is proven on its own
+        let mut returns = vec![Returns::Skip; triples.len()];
+        for i in return_spans(&wrapped_func.content) {
+            let mut triple = prove_block(
+                wrapped_func.precondition.clone(),
+                wrapped_func.content.clone(),
+                Bool::True,
+            );
+            triple.postcondition_original = wrapped_func.postcondition.clone();
+            triple.span = i;
+            triples.push(triple);
+            returns.push(Returns::Only(i));
+        }
 
-        let triples = to_prove.create_triples();
+        for (triple, returns) in triples.into_iter().zip(returns) {
+            tasks.push(Task {
+                function: functions.len(),
+                triple,
+                returns,
+            });
+        }
+        functions.push(FunctionSetup {
+            name: f_name,
+            arith,
+            limits,
+            types,
+            postcondition: wrapped_func.postcondition,
+        });
+    }
 
-        for i in triples {
-            let final_res;
-            let (current, temp_res) = i.calculate();
+    let mut per_function: Vec<Vec<_>> = functions.iter().map(|_| Vec::new()).collect();
+    let task_functions: Vec<usize> = tasks.iter().map(|x| x.function).collect();
+    for (f, r) in task_functions
+        .into_iter()
+        .zip(run_tasks(&functions, tasks, &options))
+    {
+        per_function[f].push(r);
+    }
 
-            if temp_res {
-                final_res = current.prove();
-            } else {
-                final_res = temp_res;
+    // Merged in the order of the functions and triples, the same no matter which thread proved what
+    for (setup, results) in functions.iter().zip(per_function) {
+        log::warn!("Proving function: {}", setup.name);
+        log::info!("Using {} arithmetic", setup.arith);
+
+        let mut proven = true;
+        let mut obligations = Vec::new();
+        let mut queries = Vec::new();
+        // Skipped tasks only come after a failure, when the rest isn't looked at anyway
+        for (final_res, checked, queried) in results.into_iter().flatten() {
+            obligations.extend(checked.into_iter().map(|x| Obligation {
+                source: input.source.clone(),
+                ..x
+            }));
+            queries.extend(queried);
+            if !final_res {
+                proven = false;
+                if !options.keep_going {
+                    break;
+                }
             }
+        }
 
-            if !final_res {
-                log::warn!("Failed to prove function: {}", f_name);
-                return false;
+        if let Some(dir) = &options.emit_smt {
+            if let Err(e) = write_queries(dir, &setup.name, &queries) {
+                log::error!(
+                    "Failed to write the SMT-LIB2 queries to {}: {}",
+                    dir.display(),
+                    e
+                );
             }
         }
-        log::warn!("Successfully proved function: {}", f_name);
+
+        result.functions.push(FunctionReport {
+            name: setup.name.clone(),
+            obligations,
+        });
+
+        if !proven {
+            log::warn!("Failed to prove function: {}", setup.name);
+            if !options.keep_going {
+                return result;
+            }
+        } else {
+            log::warn!("Successfully proved function: {}", setup.name);
+        }
     }
 
-    true
+    result
 }
 
 trait Provable {
     /// Find the P for {P} S {Q} to prove
     /// It gets calculated starting from the {Q} and then moving backwards (due to the assignment axiom)
-    fn get_pre(self, q: Bool, p: Bool) -> (Bool, bool);
+    /// The span is the place of the command in the source, used for reporting failures
+    fn get_pre(self, q: Bool, p: Bool, span: Span) -> (Bool, bool);
 }
 
 impl Provable for Command {
-    fn get_pre(self, q: Bool, p: Bool) -> (Bool, bool) {
+    fn get_pre(self, q: Bool, p: Bool, span: Span) -> (Bool, bool) {
         match self {
-            Command::Binding(x) => x.get_pre(q, p),
-            Command::Assignment(x) => x.get_pre(q, p),
-            Command::ProveControl(x) => x.get_pre(q, p),
-            Command::Block(x) => x.get_pre(q, p),
+            Command::Binding(x, _) => x.get_pre(q, p, span),
+            Command::Assignment(x, _) => x.get_pre(q, p, span),
+            Command::ProveControl(x, _) => x.get_pre(q, p, span),
+            Command::Block(x, _) => x.get_pre(q, p, span),
             Command::Noop => (q, true),
+            // Whatever comes after the jump is never run
+            Command::Break(_) => (loop_exits().0, true),
+            Command::Continue(_) => (loop_exits().1, true),
+            // The value is already assigned to the return_value by wrap_function
+            Command::Return(_, s) => EXITS.with(|x| {
+                let x = x.borrow();
+                if x.returns == Returns::Only(s) {
+                    (x.postcondition.clone(), true)
+                } else {
+                    (Bool::True, true)
+                }
+            }),
         }
     }
 }
 
 impl Provable for Binding {
-    fn get_pre(self, q: Bool, _p: Bool) -> (Bool, bool) {
+    fn get_pre(self, q: Bool, _p: Bool, span: Span) -> (Bool, bool) {
         match self {
             Binding::Declaration(_, _, _) => (q, true),
 
-            Binding::Assignment(var, _, val, _) => match val.clone() {
-                Value::Tuple(_) => {
-                    panic! {"This is not supported, something went wrong!"}
-                }
-                _ => Assignment::Single(var, val).get_pre(q, _p),
-            },
+            Binding::Assignment(var, _, val, _) => {
+                Assignment::Single(var, val).get_pre(q, _p, span)
+            }
             Binding::Tuple(vec) => {
                 let mut real_vec = Vec::new();
                 for i in vec {
                     match i {
-                        Command::Assignment(a) => {
+                        Command::Assignment(a, _) => {
                             real_vec.push(a);
                         }
+                        Command::Binding(Binding::Assignment(var, _, val, _), _) => {
+                            real_vec.push(Assignment::Single(var, val));
+                        }
+                        Command::Binding(Binding::Declaration(_, _, _), _) => {}
                         _ => {
                             panic!("This is not supported, something went wrong!")
                         }
                     }
                 }
 
-                Assignment::Tuple(real_vec).get_pre(q, _p)
+                Assignment::Tuple(real_vec).get_pre(q, _p, span)
             }
         }
     }
 }
 
 impl Provable for Assignment {
-    fn get_pre(self, q: Bool, p: Bool) -> (Bool, bool) {
+    fn get_pre(self, q: Bool, p: Bool, _span: Span) -> (Bool, bool) {
         match self {
             Assignment::Tuple(vec) => {
+                // All the values are evaluated before any of the assignments happen,
+                // so the variables are first replaced with temporary ones
                 let mut t = q;
-                for i in vec {
-                    let (_t, l) = i.get_pre(t, p.clone());
+                let mut temps = Vec::new();
+                for (i, a) in vec.into_iter().enumerate() {
+                    match a {
+                        Assignment::Single(var @ Variable::Named(_), val)
+                        | Assignment::Single(var @ Variable::TupleElem(_, _), val) => {
+                            let temp = Variable::Named(format!("__tuple_assignment_{}", i));
+                            t = t.swap(var, Value::Variable(temp.clone()));
+                            temps.push(Assignment::Single(temp, val));
+                        }
+                        a => temps.push(a),
+                    }
+                }
+                for i in temps {
+                    let (_t, l) = i.get_pre(t, p.clone(), _span);
                     if !l {
                         return (Bool::True, false);
                     }
@@ -491,9 +1898,13 @@ impl Provable for Assignment {
             }
 
             Assignment::Single(var, val) => {
+                // The indexes have to be in bounds and the value can't overflow,
+                // even when q doesn't use the accessed elements or the assigned variable
+                let accessed = accesses(&var, &val);
+                let evaluated = evaluated(&val);
                 // Swap all `var` occurences with the `val` in the condition
                 // Supports only simple int, bool and arrayelem assignments for now
-                match var.clone() {
+                let t = match var.clone() {
                     Variable::Named(name) => match val.clone() {
                         Value::Array(vals) => {
                             // Bunch of individual assignments
@@ -505,17 +1916,30 @@ impl Provable for Assignment {
                                     Box::new(Value::Expr(Expr::Number(i))),
                                 );
                                 let ass = Assignment::Single(tvar, v);
-                                let (_temp, _) = ass.get_pre(temp, p.clone());
+                                let (_temp, _) = ass.get_pre(temp, p.clone(), _span);
                                 temp = _temp;
                                 i += 1;
                             }
-                            (temp, true)
+                            temp
                         }
-                        _ => (q.swap(var, val), true),
+                        _ => assign(q, var, val),
                     },
-                    Variable::TupleElem(_, _) => unimplemented!(),
-                    Variable::Empty => (q, true),
-                    Variable::ArrayElem(arr_name, index) => {
+                    Variable::TupleElem(_, _) => assign(q, var, val),
+                    Variable::Length(name) => panic!("Length of {} can't be assigned to", name),
+                    Variable::Empty => q,
+                    Variable::ArrayElem(arr_name, index) => match val.clone() {
+                        // Arrays of tuples are arrays for each of the elements
+                        Value::Tuple(vals) => {
+                            let mut temp = q;
+                            for (i, v) in vals.into_iter().enumerate() {
+                                let name = format!("{}.{}", arr_name, i);
+                                let ass =
+                                    Assignment::Single(Variable::ArrayElem(name, index.clone()), v);
+                                let (_temp, _) = ass.get_pre(temp, p.clone(), _span);
+                                temp = _temp;
+                            }
+                            temp
+                        }
                         // This is conditional
                         // a[X] = Y
                         // for all a[Z] do:
@@ -523,9 +1947,16 @@ impl Provable for Assignment {
                         //         a[Z] = Y
                         //     else:
                         //         a[Z] = a[Z]
-
-                        (q.index_swap(arr_name, *index, val.clone()), true)
-                    }
+                        _ => q.index_swap(arr_name, *index, val),
+                    },
+                };
+                let t = match evaluated {
+                    Some(e) => Bool::And(Box::new(located(e, _span)), Box::new(t)),
+                    None => t,
+                };
+                match accessed {
+                    Some(a) => (Bool::And(Box::new(located(a, _span)), Box::new(t)), true),
+                    None => (t, true),
                 }
             }
         }
@@ -533,19 +1964,36 @@ impl Provable for Assignment {
 }
 
 impl Provable for ProveControl {
-    fn get_pre(self, _q: Bool, _p: Bool) -> (Bool, bool) {
+    fn get_pre(self, q: Bool, _p: Bool, _span: Span) -> (Bool, bool) {
         match self {
             ProveControl::Assert(a) => (a, true),
+            ProveControl::FunctionCall(Bool::True, post) => {
+                // post => q
+                (
+                    Bool::Or(Box::new(Bool::Not(Box::new(post))), Box::new(q)),
+                    true,
+                )
+            }
+            ProveControl::FunctionCall(pre, post) => {
+                // pre && (post => q)
+                (
+                    Bool::And(
+                        Box::new(pre),
+                        Box::new(Bool::Or(Box::new(Bool::Not(Box::new(post))), Box::new(q))),
+                    ),
+                    true,
+                )
+            }
         }
     }
 }
 
 impl Provable for Block {
-    fn get_pre(self, q: Bool, _p: Bool) -> (Bool, bool) {
+    fn get_pre(self, q: Bool, _p: Bool, span: Span) -> (Bool, bool) {
         match self {
             Block::If(mut ifs, mut comms, el) => {
                 // Calculate p for all the possible choices
-                // Then it's (p1 && cond1) || (!p1 && p2 && cond2) || (!p1 && !p2 && p3 && cond3) || ... || (!p1 && !p2 && !p3 && .. && !p3 && p_el)
+                // Then it's if cond1 then p1 else if cond2 then p2 ... else p_el
 
                 // Handle the else case
                 comms.push(el);
@@ -556,12 +2004,17 @@ impl Provable for Block {
                 for mut c in comms {
                     // This is pretty stupid, but hey...
                     c.push(Command::Noop);
-                    c.push(Command::ProveControl(ProveControl::Assert(q.clone())));
+                    c.push(Command::ProveControl(
+                        ProveControl::Assert(q.clone()),
+                        Span::default(),
+                    ));
 
                     log::trace!("q BEFORE: {}", q.clone());
                     log::trace!("c: {:?}", c.clone());
                     // should it have some local context?
-                    let (temp, ok) = prove_block(q.clone(), c, q.clone()).calculate();
+                    let mut block = prove_block(q.clone(), c, q.clone());
+                    block.span = span;
+                    let (temp, ok) = block.calculate();
 
                     if !ok {
                         return (Bool::True, false);
@@ -570,44 +2023,28 @@ impl Provable for Block {
                     ps.push(temp.postcondition);
                 }
 
-                // The ifs generated conditions
-                let mut ifs_combined = Vec::new();
-                for i in ifs {
-                    let mut temp = i;
-                    for l in ifs_combined.clone() {
-                        temp = Bool::And(Box::new(temp), Box::new(Bool::Not(Box::new(l))));
-                    }
-                    ifs_combined.push(temp);
-                }
-
                 // Sanity check
-                assert!(ifs_combined.len() == ps.len());
-
-                // Merge the two
-                let mut merged = Vec::new();
-                while !ifs_combined.is_empty() {
-                    let t = ps.pop().unwrap();
-                    let i = ifs_combined.pop().unwrap();
-
-                    merged.push((i, t));
-                }
-
-                // Create final bool
-                let (i, p) = merged.pop().unwrap();
-                let mut to_return = Bool::And(Box::new(i), Box::new(p));
-                for (i, p) in merged {
-                    log::debug!("{}", i);
-                    log::debug!("{}", p);
-                    to_return = Bool::Or(
-                        Box::new(to_return),
-                        Box::new(Bool::And(Box::new(i), Box::new(p))),
-                    );
+                assert!(ifs.len() == ps.len());
+
+                // Nested from the else branch up, so the checks of each branch only need its own condition
+                ifs.pop();
+                let mut to_return = ps.pop().unwrap();
+                while let Some(p) = ps.pop() {
+                    let i = located(ifs.pop().unwrap(), span);
+                    to_return = Bool::Value(Box::new(Value::Ternary(
+                        i,
+                        Box::new(Value::Bool(p)),
+                        Box::new(Value::Bool(to_return)),
+                    )));
                 }
 
                 (to_return, true)
             }
             Block::While(cond, comms, inv, var) => {
+                let cond = located(cond, span);
                 let strong_inv = inv.clone();
+                // When keeping going the failures are already recorded,
+                // the rest of the code is proven as if the loop was correct
 
                 if var == Expr::Number(0) {
                     log::warn!("No loop variant provided for loop with invariant: {}", inv);
@@ -626,16 +2063,23 @@ impl Provable for Block {
                         )),
                     );
                     let var_post = Bool::LowerThan(var.clone(), old_var);
+                    set_type("__variant_old", expr_int_type(&var));
 
-                    let var_prove = prove_block(var_pre, comms.clone(), var_post);
+                    let mut var_prove = prove_block(var_pre, comms.clone(), var_post.clone());
+                    var_prove.goal = Goal::VariantDecrease;
+                    var_prove.span = span;
 
-                    if !var_prove.simple_check() {
+                    // Leaving the loop ends it, there's nothing to decrease
+                    let ok = with_loop_exits(Bool::True, var_post, || var_prove.simple_check());
+                    if !ok && !keep_going() {
                         return (Bool::True, false);
                     }
 
                     // Now let's prove that invariant implies that variant >= 0
                     let var_post = Bool::GreaterEqual(var.clone(), Expr::Number(0));
-                    if !prove_impl(strong_inv.clone(), var_post) {
+                    if !prove_impl(strong_inv.clone(), var_post, Goal::VariantNonNegative, span)
+                        && !keep_going()
+                    {
                         return (Bool::True, false);
                     }
                 }
@@ -644,12 +2088,29 @@ impl Provable for Block {
 
                 // First check that the invariant works
                 // inv && cond -> inv
-                let inv_prove = prove_block(pre, comms.clone(), strong_inv.clone());
+                let mut inv_prove = prove_block(pre.clone(), comms.clone(), strong_inv.clone());
+                inv_prove.goal = Goal::LoopInvariant;
+                inv_prove.span = span;
 
-                if !inv_prove.simple_check() {
+                let ok =
+                    with_loop_exits(Bool::True, strong_inv.clone(), || inv_prove.simple_check());
+                if !ok && !keep_going() {
                     return (Bool::True, false);
                 }
 
+                // Then that breaking out of the loop gives what's needed after it
+                if crate::typing::breaks(&comms) {
+                    let mut exit_prove = prove_block(pre, comms.clone(), Bool::True);
+                    exit_prove.postcondition_original = q.clone();
+                    exit_prove.goal = Goal::LoopExit;
+                    exit_prove.span = span;
+
+                    let ok = with_loop_exits(q.clone(), Bool::True, || exit_prove.simple_check());
+                    if !ok && !keep_going() {
+                        return (Bool::True, false);
+                    }
+                }
+
                 // Also check the real thing, so if we're out of the loop then it means that post is achieved
                 // not condition && invariant => post
                 let pre_not = Bool::And(
@@ -663,15 +2124,98 @@ impl Provable for Block {
                 //    return (Bool::True, false);
                 //}
 
-                if !prove_impl(pre_not, q.clone()) {
+                if !prove_impl(pre_not, q.clone(), Goal::LoopExit, span) && !keep_going() {
                     return (Bool::True, false);
                 }
 
                 // At this point, just return the real computed {p}
                 (strong_inv, true)
             }
-            Block::ForRange(_iter, _first, _last, _comms, _inv) => {
-                unimplemented!()
+            Block::ForRange(iter, first, last, comms, inv) => {
+                // for i in a..b { code } is proven as:
+                // i = a; while i < b { code; i = i + 1 }
+                // with the bounds a <= i <= b added to the invariant and b - i used as the variant.
+                let name = match iter.clone() {
+                    Variable::Named(x) => x,
+                    _ => panic!("Unsupported for loop iterator: {}", iter),
+                };
+
+                let start = Variable::Named(format!("__range_start_{}", name));
+                let end = Variable::Named(format!("__range_end_{}", name));
+                set_type(&format!("__range_start_{}", name), variable_type(&name));
+                set_type(&format!("__range_end_{}", name), variable_type(&name));
+
+                // The range is evaluated only once, so if the code modifies the variables
+                // used to define its ends, they have to be kept in separate symbols
+                let mut affected = HashSet::new();
+                for i in comms.clone() {
+                    affected.extend(i.get_affected_variables());
+                }
+                let range_end = |val: Value, symbol: Variable| {
+                    if val.clone().get_variables().is_disjoint(&affected) {
+                        Expr::Value(Box::new(val))
+                    } else {
+                        log::warn!(
+                            "Range end {} is modified inside of the loop, the invariant can't refer to it",
+                            val
+                        );
+                        Expr::Value(Box::new(Value::Variable(symbol)))
+                    }
+                };
+
+                let iter_expr = Expr::Value(Box::new(Value::Variable(iter.clone())));
+                let start_expr = range_end(first.clone(), start.clone());
+                let end_expr = range_end(last.clone(), end.clone());
+
+                let strong_inv = Bool::And(
+                    Box::new(inv),
+                    Box::new(Bool::And(
+                        Box::new(Bool::GreaterEqual(iter_expr.clone(), start_expr.clone())),
+                        Box::new(Bool::LowerEqual(iter_expr.clone(), end_expr.clone())),
+                    )),
+                );
+                let cond = Bool::LowerThan(iter_expr.clone(), end_expr.clone());
+                let var = Expr::Op(
+                    Box::new(end_expr.clone()),
+                    Opcode::Sub,
+                    Box::new(iter_expr.clone()),
+                );
+
+                let next = Command::Assignment(
+                    Assignment::Single(
+                        iter.clone(),
+                        Value::Expr(Expr::Op(
+                            Box::new(iter_expr),
+                            Opcode::Add,
+                            Box::new(Expr::Number(1)),
+                        )),
+                    ),
+                    span,
+                );
+                let mut comms = before_continue(comms, &next);
+                comms.push(next);
+
+                let (inv_pre, ok) =
+                    Block::While(cond, comms, strong_inv, var).get_pre(q.clone(), _p, span);
+                if !ok {
+                    return (Bool::True, false);
+                }
+
+                // The loop is entered with the iterator set to the start of the range,
+                // if the range is empty the code is skipped and {q} has to hold right away
+                let non_empty = Bool::LowerThan(start_expr, end_expr);
+                let pre = Bool::Or(
+                    Box::new(Bool::And(
+                        Box::new(non_empty.clone()),
+                        Box::new(inv_pre.swap(iter, Value::Variable(start.clone()))),
+                    )),
+                    Box::new(Bool::And(
+                        Box::new(Bool::Not(Box::new(non_empty))),
+                        Box::new(q),
+                    )),
+                );
+
+                (pre.swap(start, first).swap(end, last), true)
             }
         }
     }
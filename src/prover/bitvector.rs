@@ -1,8 +1,112 @@
 use crate::prover::*;
+use z3::ast::Ast;
+
+/// Width and signedness of the ints with no known type (literals, quantified variables)
+const DEFAULT: (u32, bool) = (32, true);
+/// Arrays are indexed with usize
+const INDEX: (u32, bool) = (64, false);
+
+fn int_kind(t: Option<Type>) -> (u32, bool) {
+    t.and_then(|x| x.int()).unwrap_or(DEFAULT)
+}
+
+/// Change the width of the int, extending it according to its signedness
+fn cast<'a>(x: z3::ast::BV<'a>, from: (u32, bool), to: (u32, bool)) -> z3::ast::BV<'a> {
+    if from.0 < to.0 {
+        if from.1 {
+            x.sign_ext(to.0 - from.0)
+        } else {
+            x.zero_ext(to.0 - from.0)
+        }
+    } else if from.0 > to.0 {
+        x.extract(to.0 - 1, 0)
+    } else {
+        x
+    }
+}
+
+/// The elements are bools or bit-vectors of the width of their type
+fn array<'a>(ctx: &'a z3::Context, name: String) -> z3::ast::Array<'a> {
+    let elem = match array_elem_type(&name) {
+        Some(Type::Bool) => z3::Sort::bool(ctx),
+        t => z3::Sort::bitvector(ctx, int_kind(t).0),
+    };
+    z3::ast::Array::new_const(ctx, name, &z3::Sort::bitvector(ctx, INDEX.0), &elem)
+}
+
+/// Index converted with its own type, so the overflow checks are right
+/// It also has to be smaller than the length of the array
+fn index<'a>(
+    ctx: &'a z3::Context,
+    name: &str,
+    ind: Value,
+) -> (HashSet<Check<'a>>, z3::ast::BV<'a>) {
+    let kind = int_kind(value_int_type(&ind));
+    let object = Expr::Value(Box::new(Value::Variable(Variable::ArrayElem(
+        name.to_string(),
+        Box::new(ind.clone()),
+    ))));
+    let (mut checks, ind) = ind.as_bv(ctx, kind);
+    let (_, len) = array_len(name).as_bv(ctx, INDEX);
+
+    let wide = cast(ind.clone(), kind, INDEX);
+    let mut in_bounds = wide.bvult(&len);
+    if kind.1 {
+        let zero = z3::ast::BV::from_i64(ctx, 0, kind.0);
+        in_bounds = z3::ast::Bool::and(ctx, &[&ind.bvsge(&zero), &in_bounds]);
+    }
+    checks.insert(Check {
+        goal: Goal::Bounds,
+        object,
+        check: in_bounds,
+        span: Span::default(),
+    });
+    (checks, wide)
+}
+
+/// Both sides of a comparison, along with whether it's signed
+fn operands<'a>(
+    ctx: &'a z3::Context,
+    a: Value,
+    b: Value,
+) -> (HashSet<Check<'a>>, z3::ast::BV<'a>, z3::ast::BV<'a>, bool) {
+    let kind = int_kind(value_int_type(&a).or_else(|| value_int_type(&b)));
+    let (mut checks_a, a) = a.as_bv(ctx, kind);
+    let (checks_b, b) = b.as_bv(ctx, kind);
+    checks_a.extend(checks_b);
+    (checks_a, a, b, kind.1)
+}
+
+/// Bit-vectors of the width of the types
+pub struct BvBackend;
+
+impl Backend for BvBackend {
+    fn as_bool<'a>(
+        &self,
+        ctx: &'a z3::Context,
+        b: Bool,
+    ) -> (HashSet<Check<'a>>, z3::ast::Bool<'a>) {
+        b.as_bool(ctx)
+    }
+
+    fn eval_int<'a>(&self, ctx: &'a z3::Context, solver: &dyn Solver<'a>, v: Value) -> Option<i64> {
+        eval_int(ctx, solver, v)
+    }
+
+    fn assumptions<'a>(&self, ctx: &'a z3::Context, p: &Bool, q: &Bool) -> z3::ast::Bool<'a> {
+        assumptions(ctx, p, q)
+    }
+}
 
 pub trait ProvableValue {
     fn as_bool<'a>(self, ctx: &'a z3::Context) -> (HashSet<Check<'a>>, z3::ast::Bool<'a>);
     fn as_int<'a>(self, ctx: &'a z3::Context) -> (HashSet<Check<'a>>, z3::ast::BV<'a>);
+    /// The int with the given width and signedness
+    fn as_bv<'a>(
+        self,
+        ctx: &'a z3::Context,
+        kind: (u32, bool),
+    ) -> (HashSet<Check<'a>>, z3::ast::BV<'a>);
 }
 
 impl ProvableValue for Value {
@@ -15,85 +119,95 @@ impl ProvableValue for Value {
                 Variable::Named(name) => (set![], z3::ast::Bool::new_const(ctx, name)),
                 Variable::Empty => panic!("Empty variable tried to be used as a bool!"),
                 Variable::ArrayElem(arr_name, ind) => {
-                    let t = z3::ast::Array::new_const(
-                        ctx,
-                        arr_name,
-                        &z3::Sort::bitvector(ctx, 32),
-                        &z3::Sort::bitvector(ctx, 32),
-                    );
+                    let (checks_ind, ind) = index(ctx, &arr_name, *ind);
+                    let t = array(ctx, arr_name);
 
-                    let (checks_ind, ind) = ind.as_int(ctx);
                     (checks_ind, t.select(&ind).as_bool().unwrap())
                 }
-                Variable::TupleElem(_name, _ind) => unimplemented!(),
+                Variable::TupleElem(name, ind) => {
+                    Value::Variable(tuple_elem(&name, &ind)).as_bool(ctx)
+                }
+                Variable::Length(name) => panic!("Length of {} tried to be used as a bool!", name),
             },
             Value::Tuple(t) => panic!("Tuple {:?} tried to be used as a bool!", t),
             Value::Array(a) => panic!("Array {:?} tried to be used as a bool!", a),
-            Value::FunctionCall(_name, _args) => unimplemented!(),
-            Value::Reference(_v) => unimplemented!(),
-            Value::ReferenceMutable(_v) => unimplemented!(),
-            Value::Dereference(_v) => unimplemented!(),
+            Value::FunctionCall(name, _args) => {
+                panic!(
+                    "Call to {} can only be used in assignments and bindings!",
+                    name
+                )
+            }
+            // References stand for the values they point to
+            Value::Reference(v) | Value::ReferenceMutable(v) | Value::Dereference(v) => {
+                v.as_bool(ctx)
+            }
             Value::Unit => unimplemented!(),
             Value::Ternary(cond, a, b) => {
                 let (mut checks_t, t) = cond.as_bool(ctx);
                 let (checks_a, a) = a.as_bool(ctx);
                 let (checks_b, b) = b.as_bool(ctx);
-                checks_t.extend(checks_a);
-                checks_t.extend(checks_b);
+                checks_t.extend(guard(checks_a, &t));
+                checks_t.extend(guard(checks_b, &t.not()));
                 (checks_t, t.ite(&a, &b))
             }
         }
     }
 
     fn as_int<'a>(self, ctx: &'a z3::Context) -> (HashSet<Check<'a>>, z3::ast::BV<'a>) {
+        let kind = int_kind(value_int_type(&self));
+        self.as_bv(ctx, kind)
+    }
+
+    fn as_bv<'a>(
+        self,
+        ctx: &'a z3::Context,
+        kind: (u32, bool),
+    ) -> (HashSet<Check<'a>>, z3::ast::BV<'a>) {
         log::trace!("AS_INT: {}", self.clone());
         match self {
-            Value::Expr(e) => e.as_int(ctx),
-            Value::Bool(b) => {
-                // HACK: This is ugly, should be solved on the parsing level somehow?
-                //panic!("Bool value ({}) used as an int", b)
-                log::debug!("Bool value ({}) used as an int", b);
-                match b.clone() {
-                    Bool::Value(a) => match *a {
-                        Value::Variable(x) => {
-                            Value::Expr(Expr::Value(Box::new(Value::Variable(x)))).as_int(ctx)
-                        }
-                        Value::Expr(x) => Value::Expr(x).as_int(ctx),
-                        _ => panic!("Bool value ({}) used as an int", b),
-                    },
-                    _ => panic!("Bool value ({}) used as an int", b),
-                }
-            }
+            Value::Expr(e) => e.as_bv(ctx, kind),
+            Value::Bool(b) => panic!("Bool value ({}) used as an int", b),
             Value::Variable(x) => match x {
-                Variable::Named(name) => (set![], z3::ast::BV::new_const(ctx, name, 32)),
+                Variable::Named(name) => {
+                    let own = int_kind(variable_type(&name));
+                    let t = z3::ast::BV::new_const(ctx, name, own.0);
+                    (set![], cast(t, own, kind))
+                }
                 Variable::Empty => panic!("Empty variable tried to be used as an int!"),
                 Variable::ArrayElem(arr_name, ind) => {
-                    let t = z3::ast::Array::new_const(
-                        ctx,
-                        arr_name,
-                        &z3::Sort::bitvector(ctx, 32),
-                        &z3::Sort::bitvector(ctx, 32),
-                    );
+                    let own = int_kind(value_int_type(&Value::Variable(Variable::ArrayElem(
+                        arr_name.clone(),
+                        ind.clone(),
+                    ))));
+                    let (checks, ind) = index(ctx, &arr_name, *ind);
+                    let t = array(ctx, arr_name);
 
-                    let (checks, ind) = ind.as_int(ctx);
-                    (checks, t.select(&ind).as_bv().unwrap())
+                    (checks, cast(t.select(&ind).as_bv().unwrap(), own, kind))
                 }
-
-                Variable::TupleElem(_name, _ind) => unimplemented!(),
+                Variable::TupleElem(name, ind) => {
+                    Value::Variable(tuple_elem(&name, &ind)).as_bv(ctx, kind)
+                }
+                Variable::Length(name) => array_len(&name).as_bv(ctx, kind),
             },
             Value::Tuple(t) => panic!("Tuple {:?} tried to be used as an intl!", t),
             Value::Array(a) => panic!("Array {:?} tried to be used as an int!", a),
-            Value::FunctionCall(_name, _args) => unimplemented!(),
-            Value::Reference(_v) => unimplemented!(),
-            Value::ReferenceMutable(_v) => unimplemented!(),
-            Value::Dereference(_v) => unimplemented!(),
+            Value::FunctionCall(name, _args) => {
+                panic!(
+                    "Call to {} can only be used in assignments and bindings!",
+                    name
+                )
+            }
+            // References stand for the values they point to
+            Value::Reference(v) | Value::ReferenceMutable(v) | Value::Dereference(v) => {
+                v.as_bv(ctx, kind)
+            }
             Value::Unit => unimplemented!(),
             Value::Ternary(cond, a, b) => {
                 let (mut checks_t, t) = cond.as_bool(ctx);
-                let (checks_a, a) = a.as_int(ctx);
-                let (checks_b, b) = b.as_int(ctx);
-                checks_t.extend(checks_a);
-                checks_t.extend(checks_b);
+                let (checks_a, a) = a.as_bv(ctx, kind);
+                let (checks_b, b) = b.as_bv(ctx, kind);
+                checks_t.extend(guard(checks_a, &t));
+                checks_t.extend(guard(checks_b, &t.not()));
                 (checks_t, t.ite(&a, &b))
             }
         }
@@ -105,106 +219,85 @@ pub trait ProvableBool {
 }
 
 pub trait ProvableInt {
-    fn as_int<'a>(self, ctx: &'a z3::Context) -> (HashSet<Check<'a>>, z3::ast::BV<'a>);
+    /// The int with the given width and signedness
+    fn as_bv<'a>(
+        self,
+        ctx: &'a z3::Context,
+        kind: (u32, bool),
+    ) -> (HashSet<Check<'a>>, z3::ast::BV<'a>);
 }
 
 impl ProvableInt for Expr {
-    fn as_int<'a>(self, ctx: &'a z3::Context) -> (HashSet<Check<'a>>, z3::ast::BV<'a>) {
+    fn as_bv<'a>(
+        self,
+        ctx: &'a z3::Context,
+        kind: (u32, bool),
+    ) -> (HashSet<Check<'a>>, z3::ast::BV<'a>) {
         log::trace!("AS_INT: {}", self.clone());
+        let (bits, signed) = kind;
         match self.clone() {
-            Expr::Number(a) => (set![], z3::ast::BV::from_i64(ctx, a.into(), 32)),
-            Expr::Op(a, op, b) => match op {
-                Opcode::Add => {
-                    // These checks are only done after the solver has already finished
-                    let (mut checks_a, a) = a.as_int(ctx);
-                    let (checks_b, b) = b.as_int(ctx);
-                    checks_a.extend(checks_b);
+            // The types are at most 64 bits wide, the rest is cut off the same way as in the bit-vector
+            Expr::Number(a) => (set![], z3::ast::BV::from_u64(ctx, a as u64, bits)),
+            Expr::Op(a, op, b) => {
+                // These checks are only done after the solver has already finished
+                let (mut checks, a) = a.as_bv(ctx, kind);
+                let (checks_b, b) = b.as_bv(ctx, kind);
+                checks.extend(checks_b);
 
-                    let over = Check {
-                        name: String::from("overflow"),
-                        object: self.clone(),
-                        check: a.bvadd_no_overflow(&b, true),
-                    };
-                    let under = Check {
-                        name: String::from("underflow"),
+                let mut check = |goal, check| {
+                    checks.insert(Check {
+                        goal,
                         object: self.clone(),
-                        check: a.bvadd_no_underflow(&b),
-                    };
+                        check,
+                        span: Span::default(),
+                    });
+                };
+                let zero = z3::ast::BV::from_i64(ctx, 0, bits);
 
-                    checks_a.insert(over);
-                    checks_a.insert(under);
-
-                    (checks_a, a.bvadd(&b))
-                }
-                Opcode::Sub => {
-                    // These checks are only done after the solver has already finished
-                    let (mut checks_a, a) = a.as_int(ctx);
-                    let (checks_b, b) = b.as_int(ctx);
-                    checks_a.extend(checks_b);
-
-                    let over = Check {
-                        name: String::from("overflow"),
-                        object: self.clone(),
-                        check: a.bvsub_no_overflow(&b),
-                    };
-                    let under = Check {
-                        name: String::from("underflow"),
-                        object: self.clone(),
-                        check: a.bvsub_no_underflow(&b, true),
-                    };
-
-                    checks_a.insert(over);
-                    checks_a.insert(under);
-
-                    (checks_a, a.bvsub(&b))
-                }
-                Opcode::Mul => {
-                    // These checks are only done after the solver has already finished
-                    let (mut checks_a, a) = a.as_int(ctx);
-                    let (checks_b, b) = b.as_int(ctx);
-                    checks_a.extend(checks_b);
-
-                    let over = Check {
-                        name: String::from("overflow"),
-                        object: self.clone(),
-                        check: a.bvmul_no_overflow(&b, true),
-                    };
-                    let under = Check {
-                        name: String::from("underflow"),
-                        object: self.clone(),
-                        check: a.bvmul_no_underflow(&b),
-                    };
-
-                    checks_a.insert(over);
-                    checks_a.insert(under);
-
-                    (checks_a, a.bvmul(&b))
-                }
-                Opcode::Div => {
-                    // These checks are only done after the solver has already finished
-                    let (mut checks_a, a) = a.as_int(ctx);
-                    let (checks_b, b) = b.as_int(ctx);
-                    checks_a.extend(checks_b);
-
-                    let over = Check {
-                        name: String::from("overflow"),
-                        object: self.clone(),
-                        check: a.bvsdiv_no_overflow(&b),
-                    };
-
-                    checks_a.insert(over);
-
-                    (checks_a, a.bvsdiv(&b))
-                }
-                Opcode::Rem => {
-                    let (mut checks_a, a) = a.as_int(ctx);
-                    let (checks_b, b) = b.as_int(ctx);
-                    checks_a.extend(checks_b);
+                let result = match op {
+                    Opcode::Add => {
+                        check(Goal::Overflow, a.bvadd_no_overflow(&b, signed));
+                        if signed {
+                            check(Goal::Underflow, a.bvadd_no_underflow(&b));
+                        }
+                        a.bvadd(&b)
+                    }
+                    Opcode::Sub => {
+                        if signed {
+                            check(Goal::Overflow, a.bvsub_no_overflow(&b));
+                        }
+                        check(Goal::Underflow, a.bvsub_no_underflow(&b, signed));
+                        a.bvsub(&b)
+                    }
+                    Opcode::Mul => {
+                        check(Goal::Overflow, a.bvmul_no_overflow(&b, signed));
+                        if signed {
+                            check(Goal::Underflow, a.bvmul_no_underflow(&b));
+                        }
+                        a.bvmul(&b)
+                    }
+                    Opcode::Div => {
+                        check(Goal::DivisionByZero, b._eq(&zero).not());
+                        if signed {
+                            check(Goal::Overflow, a.bvsdiv_no_overflow(&b));
+                            a.bvsdiv(&b)
+                        } else {
+                            a.bvudiv(&b)
+                        }
+                    }
+                    Opcode::Rem => {
+                        check(Goal::DivisionByZero, b._eq(&zero).not());
+                        if signed {
+                            a.bvsrem(&b)
+                        } else {
+                            a.bvurem(&b)
+                        }
+                    }
+                };
 
-                    (checks_a, a.bvsrem(&b))
-                }
-            },
-            Expr::Value(a) => a.as_int(ctx),
+                (checks, result)
+            }
+            Expr::Value(a) => a.as_bv(ctx, kind),
         }
     }
 }
@@ -240,91 +333,82 @@ impl ProvableBool for Bool {
             Bool::And(_a, _b) => {
                 let (mut checks_a, a) = (*_a).as_bool(ctx);
                 let (checks_b, b) = (*_b).as_bool(ctx);
-                checks_a.extend(checks_b);
+                // Evaluated lazily, the right side only matters if the left one holds
+                checks_a.extend(guard(checks_b, &a));
                 (checks_a, z3::ast::Bool::and(ctx, &[&a, &b]))
             }
             Bool::Or(_a, _b) => {
                 let (mut checks_a, a) = (*_a).as_bool(ctx);
                 let (checks_b, b) = (*_b).as_bool(ctx);
-                checks_a.extend(checks_b);
+                checks_a.extend(guard(checks_b, &a.not()));
                 (checks_a, z3::ast::Bool::or(ctx, &[&a, &b]))
             }
             Bool::Not(a) => {
                 let (checks, t) = a.as_bool(ctx);
                 (checks, z3::ast::Bool::not(&t))
             }
-            Bool::ValueEqual(a, b) => {
-                let (mut checks_a, a) = a.as_int(ctx);
-                let (checks_b, b) = b.as_int(ctx);
-                checks_a.extend(checks_b);
-                (
-                    checks_a,
-                    z3::ast::Bool::and(ctx, &[&a.bvsge(&b), &a.bvsle(&b)]),
-                )
+            Bool::Located(a, span) => {
+                let (checks, t) = a.as_bool(ctx);
+                (locate(checks, span), t)
             }
+            Bool::ValueEqual(a, b) => match (*a, *b) {
+                (Value::Variable(Variable::Named(x)), Value::Variable(Variable::Named(y)))
+                    if is_array(&x) =>
+                {
+                    (set![], array(ctx, x)._eq(&array(ctx, y)))
+                }
+                (a, b) if is_bool(&a) || is_bool(&b) => {
+                    let (mut checks_a, a) = bool_value(a).as_bool(ctx);
+                    let (checks_b, b) = bool_value(b).as_bool(ctx);
+                    checks_a.extend(checks_b);
+                    (checks_a, a._eq(&b))
+                }
+                (a, b) => {
+                    let (checks, a, b, _) = operands(ctx, a, b);
+                    (checks, a._eq(&b))
+                }
+            },
+            // Bools can be compared too
             Bool::Equal(a, b) => {
-                let (mut checks_a, a) = a.as_int(ctx);
-                let (checks_b, b) = b.as_int(ctx);
-                checks_a.extend(checks_b);
-                (
-                    checks_a,
-                    z3::ast::Bool::and(ctx, &[&a.bvsge(&b), &a.bvsle(&b)]),
-                )
+                Bool::ValueEqual(Box::new(Value::Expr(a)), Box::new(Value::Expr(b))).as_bool(ctx)
             }
             Bool::GreaterEqual(a, b) => {
-                let (mut checks_a, a) = a.as_int(ctx);
-                let (checks_b, b) = b.as_int(ctx);
-                checks_a.extend(checks_b);
-
-                (checks_a, a.bvsge(&b))
+                let (checks, a, b, signed) = operands(ctx, Value::Expr(a), Value::Expr(b));
+                (checks, if signed { a.bvsge(&b) } else { a.bvuge(&b) })
             }
             Bool::LowerEqual(a, b) => {
-                let (mut checks_a, a) = a.as_int(ctx);
-                let (checks_b, b) = b.as_int(ctx);
-                checks_a.extend(checks_b);
-
-                (checks_a, a.bvsle(&b))
+                let (checks, a, b, signed) = operands(ctx, Value::Expr(a), Value::Expr(b));
+                (checks, if signed { a.bvsle(&b) } else { a.bvule(&b) })
             }
             Bool::GreaterThan(a, b) => {
-                let (mut checks_a, a) = a.as_int(ctx);
-                let (checks_b, b) = b.as_int(ctx);
-                checks_a.extend(checks_b);
-
-                (checks_a, a.bvsgt(&b))
+                let (checks, a, b, signed) = operands(ctx, Value::Expr(a), Value::Expr(b));
+                (checks, if signed { a.bvsgt(&b) } else { a.bvugt(&b) })
             }
             Bool::LowerThan(a, b) => {
-                let (checks_a, a) = a.as_int(ctx);
-                let (checks_b, b) = b.as_int(ctx);
-                checks_a.union(&checks_b);
-
-                (checks_a, a.bvslt(&b))
+                let (checks, a, b, signed) = operands(ctx, Value::Expr(a), Value::Expr(b));
+                (checks, if signed { a.bvslt(&b) } else { a.bvult(&b) })
             }
             Bool::Value(v) => v.as_bool(ctx),
         }
     }
 }
 
-pub trait ProvableCommand {
-    fn as_bool<'a>(self, ctx: &'a z3::Context) -> (bool, HashSet<Check<'a>>, z3::ast::Bool<'a>);
+/// Get the value of an int from the model (used for the counterexamples)
+pub fn eval_int<'a>(ctx: &'a z3::Context, solver: &dyn Solver<'a>, v: Value) -> Option<i64> {
+    let (bits, signed) = int_kind(value_int_type(&v));
+    let (_, t) = v.as_int(ctx);
+    // Numerals are unsigned, reinterpret them according to the type
+    let shift = 64 - bits;
+    solver.value(&Term::new(&t))?.bv().map(|x| {
+        if signed {
+            ((x << shift) as i64) >> shift
+        } else {
+            x as i64
+        }
+    })
 }
 
-impl ProvableCommand for Command {
-    // Concept: Try to prove not (negate everything) to try to find if there exists an incorrect mapping?
-    fn as_bool<'a>(self, ctx: &'a z3::Context) -> (bool, HashSet<Check<'a>>, z3::ast::Bool<'a>) {
-        log::trace!("AS_BOOL: {}", self.clone());
-        match self {
-            Command::ProveControl(a) => match a {
-                // We are trying to find COUNTER example here.
-                // So if we get sat, then it means that the assertion is actually incorrect
-                ProveControl::Assert(b) => {
-                    let (checks, t) = b.as_bool(ctx);
-                    (true, checks, t.not())
-                }
-            },
-            _ => {
-                // Nothing to prove here
-                (false, set![], z3::ast::Bool::from_bool(&ctx, true))
-            }
-        }
-    }
+/// The widths of the bit-vectors already limit the values
+pub fn assumptions<'a>(ctx: &'a z3::Context, _p: &Bool, _q: &Bool) -> z3::ast::Bool<'a> {
+    z3::ast::Bool::from_bool(ctx, true)
 }
@@ -0,0 +1,336 @@
+use crate::prover::report::json_string;
+use crate::prover::*;
+use std::collections::BTreeMap;
+use std::fmt;
+
+#[cfg(test)]
+mod tests;
+
+/// The kind of Z3 constant a variable is turned into by the backend
+#[derive(Clone, Debug, PartialEq)]
+enum Symbol {
+    Int,
+    Bool,
+    /// Indices the array is accessed with (only the ones not depending on quantified variables)
+    Array(Vec<Value>),
+}
+
+/// Value of a single variable read from the model
+#[derive(Clone, Debug, PartialEq)]
+pub enum ModelValue {
+    Int(i64),
+    Bool(bool),
+    /// Only the elements the proof looks at, index -> value
+    Array(BTreeMap<i32, ModelValue>),
+}
+
+impl fmt::Display for ModelValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ModelValue::Int(x) => write!(f, "{}", x),
+            ModelValue::Bool(x) => write!(f, "{}", x),
+            ModelValue::Array(elems) => {
+                let elems: Vec<String> =
+                    elems.iter().map(|(i, v)| format!("{}: {}", i, v)).collect();
+                write!(f, "[{}]", elems.join(", "))
+            }
+        }
+    }
+}
+
+impl ModelValue {
+    pub fn to_json(&self) -> String {
+        match self {
+            ModelValue::Int(_) | ModelValue::Bool(_) => self.to_string(),
+            ModelValue::Array(elems) => {
+                let elems: Vec<String> = elems
+                    .iter()
+                    .map(|(i, v)| format!("\"{}\": {}", i, v.to_json()))
+                    .collect();
+                format!("{{{}}}", elems.join(", "))
+            }
+        }
+    }
+}
+
+/// Concrete values of the variables a failed proof depends on
+#[derive(Clone, Debug, PartialEq)]
+pub struct Counterexample {
+    pub values: BTreeMap<String, ModelValue>,
+}
+
+impl fmt::Display for Counterexample {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (name, value) in &self.values {
+            writeln!(f, "    {} = {}", name, value)?;
+        }
+        Ok(())
+    }
+}
+
+impl Counterexample {
+    /// Read the values of all the variables present in p => q from the model,
+    /// and of the ones in the object of a failed check (e.g. the index of an access)
+    pub fn new<'a>(
+        ctx: &'a z3::Context,
+        solver: &dyn Solver<'a>,
+        p: &Bool,
+        q: &Bool,
+        object: Option<&Expr>,
+    ) -> Self {
+        let mut symbols = BTreeMap::new();
+        if let Some(e) = object {
+            expr_symbols(e, &mut Vec::new(), &mut symbols);
+        }
+        let mut old_names = Vec::new();
+        for b in [p, q] {
+            for conjunct in conjuncts(b) {
+                match old_equality(conjunct) {
+                    Some(name) => old_names.push(name),
+                    None => bool_symbols(conjunct, &mut Vec::new(), &mut symbols),
+                }
+            }
+        }
+
+        // The x == x'old equalities are skipped when collecting,
+        // x'old is only interesting if x is and it has the same kind
+        let names: Vec<String> = symbols.keys().cloned().collect();
+        for name in names {
+            let old = old_name(&name);
+            if !old_names.contains(&old) {
+                continue;
+            }
+            // The 'old array is shown at the same indices
+            let symbol = symbols[&name].clone();
+            match (symbol, symbols.get_mut(&old)) {
+                (Symbol::Array(indices), Some(Symbol::Array(old_indices))) => {
+                    for i in indices {
+                        if !old_indices.contains(&i) {
+                            old_indices.push(i);
+                        }
+                    }
+                }
+                (_, Some(_)) => {}
+                (symbol, None) => {
+                    symbols.insert(old, symbol);
+                }
+            }
+        }
+
+        let mut values = BTreeMap::new();
+        for (name, symbol) in symbols {
+            let value = match symbol {
+                Symbol::Int => backend()
+                    .eval_int(ctx, solver, Value::Variable(Variable::Named(name.clone())))
+                    .map(ModelValue::Int),
+                Symbol::Bool => eval_bool(ctx, solver, Variable::Named(name.clone())),
+                Symbol::Array(indices) => {
+                    let mut elems = BTreeMap::new();
+                    for ind in indices {
+                        let i: i32 =
+                            match backend().eval_int(ctx, solver, ind).map(|x| x.try_into()) {
+                                Some(Ok(i)) => i,
+                                _ => continue,
+                            };
+                        let elem = Variable::ArrayElem(
+                            name.clone(),
+                            Box::new(Value::Expr(Expr::Number(i.into()))),
+                        );
+                        let v = match array_elem_type(&name) {
+                            Some(Type::Bool) => eval_bool(ctx, solver, elem),
+                            _ => backend()
+                                .eval_int(ctx, solver, Value::Variable(elem))
+                                .map(ModelValue::Int),
+                        };
+                        if let Some(v) = v {
+                            elems.insert(i, v);
+                        }
+                    }
+                    Some(ModelValue::Array(elems))
+                }
+            };
+
+            if let Some(v) = value {
+                values.insert(name, v);
+            }
+        }
+
+        Counterexample { values }
+    }
+
+    pub fn to_json(&self) -> String {
+        let values: Vec<String> = self
+            .values
+            .iter()
+            .map(|(name, value)| format!("{}: {}", json_string(name), value.to_json()))
+            .collect();
+        format!("{{{}}}", values.join(", "))
+    }
+}
+
+fn eval_bool<'a>(ctx: &'a z3::Context, solver: &dyn Solver<'a>, x: Variable) -> Option<ModelValue> {
+    let (_, b) = backend().as_bool(ctx, Bool::Value(Box::new(Value::Variable(x))));
+    solver.value(&Term::new(&b))?.bool().map(ModelValue::Bool)
+}
+
+fn add_symbol(
+    name: &str,
+    symbol: Symbol,
+    bound: &[String],
+    symbols: &mut BTreeMap<String, Symbol>,
+) {
+    if bound.iter().any(|x| x == name) || generated(name) {
+        return;
+    }
+
+    match (symbols.get_mut(name), symbol) {
+        (Some(Symbol::Array(old)), Symbol::Array(new)) => {
+            for i in new {
+                if !old.contains(&i) {
+                    old.push(i);
+                }
+            }
+        }
+        (Some(_), _) => {}
+        (None, s) => {
+            symbols.insert(name.to_string(), s);
+        }
+    }
+}
+
+fn conjuncts(b: &Bool) -> Vec<&Bool> {
+    match b {
+        Bool::And(a, b) => {
+            let mut t = conjuncts(a);
+            t.extend(conjuncts(b));
+            t
+        }
+        _ => vec![b],
+    }
+}
+
+/// Names made up by the prover, e.g. `__call_f_0_x` or the typed literal `255u8`, not the user's variables
+fn generated(name: &str) -> bool {
+    name.starts_with("__") || is_literal(name)
+}
+
+/// Returns x'old for x == x'old added by the PreconditionCreator
+fn old_equality(b: &Bool) -> Option<String> {
+    if let Bool::Equal(Expr::Value(a), Expr::Value(b)) | Bool::ValueEqual(a, b) = b {
+        if let (Value::Variable(Variable::Named(a)), Value::Variable(Variable::Named(b))) =
+            (&**a, &**b)
+        {
+            if *b == old_name(a) {
+                return Some(b.clone());
+            }
+        }
+    }
+    None
+}
+
+/// Mirrors the way backends decide whether a variable is an int or a bool
+fn bool_symbols(b: &Bool, bound: &mut Vec<String>, symbols: &mut BTreeMap<String, Symbol>) {
+    match b {
+        Bool::ForAll(var, b) | Bool::Exists(var, b) => {
+            if let Variable::Named(name) = var {
+                bound.push(name.clone());
+                bool_symbols(b, bound, symbols);
+                bound.pop();
+            } else {
+                bool_symbols(b, bound, symbols);
+            }
+        }
+        Bool::And(a, b) | Bool::Or(a, b) => {
+            bool_symbols(a, bound, symbols);
+            bool_symbols(b, bound, symbols);
+        }
+        Bool::Not(a) | Bool::Located(a, _) => bool_symbols(a, bound, symbols),
+        Bool::Value(v) => value_symbols(v, false, bound, symbols),
+        Bool::True | Bool::False => {}
+        Bool::ValueEqual(a, b) => {
+            value_symbols(a, true, bound, symbols);
+            value_symbols(b, true, bound, symbols);
+        }
+        Bool::Equal(a, b)
+        | Bool::GreaterEqual(a, b)
+        | Bool::LowerEqual(a, b)
+        | Bool::GreaterThan(a, b)
+        | Bool::LowerThan(a, b) => {
+            expr_symbols(a, bound, symbols);
+            expr_symbols(b, bound, symbols);
+        }
+    }
+}
+
+fn expr_symbols(e: &Expr, bound: &mut Vec<String>, symbols: &mut BTreeMap<String, Symbol>) {
+    match e {
+        Expr::Number(_) => {}
+        Expr::Op(a, _, b) => {
+            expr_symbols(a, bound, symbols);
+            expr_symbols(b, bound, symbols);
+        }
+        Expr::Value(v) => value_symbols(v, true, bound, symbols),
+    }
+}
+
+fn value_symbols(
+    v: &Value,
+    as_int: bool,
+    bound: &mut Vec<String>,
+    symbols: &mut BTreeMap<String, Symbol>,
+) {
+    match v {
+        Value::Expr(e) => expr_symbols(e, bound, symbols),
+        Value::Bool(b) => match b {
+            // Same HACK as in the backends, bool variables used as ints are ints
+            Bool::Value(a) if as_int => value_symbols(a, true, bound, symbols),
+            _ => bool_symbols(b, bound, symbols),
+        },
+        Value::Variable(Variable::Named(name)) => {
+            let symbol = if as_int && !is_bool(v) {
+                Symbol::Int
+            } else {
+                Symbol::Bool
+            };
+            add_symbol(name, symbol, bound, symbols);
+        }
+        Value::Variable(Variable::TupleElem(name, ind)) => {
+            if let Variable::Named(name) = tuple_elem(name, ind) {
+                let symbol = if as_int && !is_bool(v) {
+                    Symbol::Int
+                } else {
+                    Symbol::Bool
+                };
+                add_symbol(&name, symbol, bound, symbols);
+            }
+        }
+        Value::Variable(Variable::Length(name)) => {
+            value_symbols(&array_len(name), true, bound, symbols)
+        }
+        Value::Variable(Variable::ArrayElem(name, ind)) => {
+            value_symbols(ind, true, bound, symbols);
+            value_symbols(&array_len(name), true, bound, symbols);
+
+            let quantified = (**ind)
+                .clone()
+                .get_variables()
+                .into_iter()
+                .any(|x| matches!(x, Variable::Named(n) if bound.contains(&n)));
+            let indices = if quantified {
+                Vec::new()
+            } else {
+                vec![*ind.clone()]
+            };
+            add_symbol(name, Symbol::Array(indices), bound, symbols);
+        }
+        Value::Reference(a) | Value::ReferenceMutable(a) | Value::Dereference(a) => {
+            value_symbols(a, as_int, bound, symbols)
+        }
+        Value::Ternary(cond, a, b) => {
+            bool_symbols(cond, bound, symbols);
+            value_symbols(a, as_int, bound, symbols);
+            value_symbols(b, as_int, bound, symbols);
+        }
+        _ => {}
+    }
+}
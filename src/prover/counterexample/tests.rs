@@ -0,0 +1,141 @@
+use crate::prover::counterexample::*;
+
+fn var(name: &str) -> Expr {
+    Expr::Value(Box::new(Value::Variable(Variable::Named(String::from(
+        name,
+    )))))
+}
+
+/// Find the counterexample for p => q, the implication has to be false
+fn find(p: Bool, q: Bool) -> Counterexample {
+    let cfg = z3::Config::new();
+    let ctx = z3::Context::new(&cfg);
+    let t = crate::prover::solver::Z3::new(&ctx, Limits::default());
+
+    let (_, _p) = backend().as_bool(&ctx, p.clone());
+    let (_, _q) = backend().as_bool(&ctx, q.clone());
+    t.assert(&Term::new(&_p.implies(&_q).not()));
+    assert_eq!(t.check(), SatResult::Sat);
+
+    Counterexample::new(&ctx, &t, &p, &q, None)
+}
+
+#[test]
+fn counterexample_int1() {
+    let c = find(
+        Bool::And(
+            Box::new(Bool::Equal(var("x"), Expr::Number(5))),
+            Box::new(Bool::Equal(var("x"), var("x'old"))),
+        ),
+        Bool::GreaterThan(var("x'old"), Expr::Number(5)),
+    );
+
+    assert_eq!(c.values.get("x"), Some(&ModelValue::Int(5)));
+    assert_eq!(c.values.get("x'old"), Some(&ModelValue::Int(5)));
+}
+
+#[test]
+fn counterexample_negative1() {
+    let c = find(
+        Bool::Equal(var("x"), Expr::Number(-3)),
+        Bool::GreaterThan(var("x"), Expr::Number(0)),
+    );
+
+    assert_eq!(c.values.get("x"), Some(&ModelValue::Int(-3)));
+}
+
+#[test]
+fn counterexample_bool1() {
+    let c = find(
+        Bool::And(
+            Box::new(Bool::True),
+            Box::new(Bool::Equal(var("b"), var("b'old"))),
+        ),
+        Bool::Value(Box::new(Value::Variable(Variable::Named(String::from(
+            "b",
+        ))))),
+    );
+
+    assert_eq!(c.values.get("b"), Some(&ModelValue::Bool(false)));
+    assert_eq!(c.values.get("b'old"), Some(&ModelValue::Bool(false)));
+}
+
+#[test]
+fn counterexample_unused_old1() {
+    // y is only present in the 'old equality, so it's not interesting
+    let c = find(
+        Bool::And(
+            Box::new(Bool::Equal(var("x"), Expr::Number(1))),
+            Box::new(Bool::Equal(var("y"), var("y'old"))),
+        ),
+        Bool::Equal(var("x"), Expr::Number(2)),
+    );
+
+    assert_eq!(c.values.get("x"), Some(&ModelValue::Int(1)));
+    assert_eq!(c.values.get("y"), None);
+    assert_eq!(c.values.get("y'old"), None);
+}
+
+#[test]
+fn counterexample_array1() {
+    let elem = |i| {
+        Expr::Value(Box::new(Value::Variable(Variable::ArrayElem(
+            String::from("arr"),
+            Box::new(Value::Expr(i)),
+        ))))
+    };
+
+    let c = find(
+        Bool::And(
+            Box::new(Bool::Equal(var("i"), Expr::Number(2))),
+            Box::new(Bool::Equal(elem(var("i")), Expr::Number(7))),
+        ),
+        Bool::Equal(elem(Expr::Number(0)), Expr::Number(7)),
+    );
+
+    assert_eq!(c.values.get("i"), Some(&ModelValue::Int(2)));
+    let arr = c.values.get("arr").unwrap().to_string();
+    assert!(arr.starts_with("[0: "));
+    assert!(arr.ends_with(", 2: 7]"));
+    assert!(c.to_json().starts_with("{\"arr\": {\"0\": "));
+}
+
+#[test]
+fn counterexample_quantified1() {
+    // The quantified variable is not a part of the counterexample
+    let c = find(
+        Bool::GreaterThan(var("n"), Expr::Number(0)),
+        Bool::ForAll(
+            Variable::Named(String::from("y")),
+            Box::new(Bool::LowerThan(var("y"), var("n"))),
+        ),
+    );
+
+    assert!(c.values.contains_key("n"));
+    assert_eq!(c.values.get("y"), None);
+}
+
+#[test]
+fn counterexample_bool_array1() {
+    set_type("flags", Some(Type::ArraySlice(Box::new(Type::Bool))));
+    let elem = |i| {
+        Value::Variable(Variable::ArrayElem(
+            String::from("flags"),
+            Box::new(Value::Expr(Expr::Number(i))),
+        ))
+    };
+
+    let c = find(
+        Bool::Value(Box::new(elem(1))),
+        Bool::And(
+            Box::new(Bool::Value(Box::new(elem(1)))),
+            Box::new(Bool::Value(Box::new(elem(2)))),
+        ),
+    );
+    set_type("flags", None);
+
+    let mut expected = BTreeMap::new();
+    expected.insert(1, ModelValue::Bool(true));
+    expected.insert(2, ModelValue::Bool(false));
+    assert_eq!(c.values.get("flags"), Some(&ModelValue::Array(expected)));
+}
@@ -1,4 +1,57 @@
 use crate::prover::*;
+use z3::ast::Ast;
+
+/// Unbounded integers
+pub struct IntBackend;
+
+impl Backend for IntBackend {
+    fn as_bool<'a>(
+        &self,
+        ctx: &'a z3::Context,
+        b: Bool,
+    ) -> (HashSet<Check<'a>>, z3::ast::Bool<'a>) {
+        b.as_bool(ctx)
+    }
+
+    fn eval_int<'a>(&self, ctx: &'a z3::Context, solver: &dyn Solver<'a>, v: Value) -> Option<i64> {
+        eval_int(ctx, solver, v)
+    }
+
+    fn assumptions<'a>(&self, ctx: &'a z3::Context, p: &Bool, q: &Bool) -> z3::ast::Bool<'a> {
+        assumptions(ctx, p, q)
+    }
+}
+
+/// Arrays are indexed with ints, the elements are bools or ints depending on the type
+fn array<'a>(ctx: &'a z3::Context, name: String) -> z3::ast::Array<'a> {
+    let elem = match array_elem_type(&name) {
+        Some(Type::Bool) => z3::Sort::bool(ctx),
+        _ => z3::Sort::int(ctx),
+    };
+    z3::ast::Array::new_const(ctx, name, &z3::Sort::int(ctx), &elem)
+}
+
+/// The index of the access has to be smaller than the length of the array
+fn bounds<'a>(
+    ctx: &'a z3::Context,
+    name: String,
+    index: Value,
+    ind: &z3::ast::Int<'a>,
+) -> Check<'a> {
+    let (_, len) = array_len(&name).as_int(ctx);
+    Check {
+        goal: Goal::Bounds,
+        object: Expr::Value(Box::new(Value::Variable(Variable::ArrayElem(
+            name,
+            Box::new(index),
+        )))),
+        check: z3::ast::Bool::and(
+            ctx,
+            &[&ind.ge(&z3::ast::Int::from_i64(ctx, 0)), &ind.lt(&len)],
+        ),
+        span: Span::default(),
+    }
+}
 
 pub trait ProvableValue {
     fn as_bool<'a>(self, ctx: &'a z3::Context) -> (HashSet<Check<'a>>, z3::ast::Bool<'a>);
@@ -15,31 +68,36 @@ impl ProvableValue for Value {
                 Variable::Named(name) => (set![], z3::ast::Bool::new_const(ctx, name)),
                 Variable::Empty => panic!("Empty variable tried to be used as a bool!"),
                 Variable::ArrayElem(arr_name, ind) => {
-                    let t = z3::ast::Array::new_const(
-                        ctx,
-                        arr_name,
-                        &z3::Sort::int(ctx),
-                        &z3::Sort::int(ctx),
-                    );
+                    let t = array(ctx, arr_name.clone());
 
-                    let (checks_ind, ind) = ind.as_int(ctx);
-                    (checks_ind, t.select(&ind).as_bool().unwrap())
+                    let (mut checks_ind, ind_int) = ind.clone().as_int(ctx);
+                    checks_ind.insert(bounds(ctx, arr_name, *ind, &ind_int));
+                    (checks_ind, t.select(&ind_int).as_bool().unwrap())
+                }
+                Variable::TupleElem(name, ind) => {
+                    Value::Variable(tuple_elem(&name, &ind)).as_bool(ctx)
                 }
-                Variable::TupleElem(_name, _ind) => unimplemented!(),
+                Variable::Length(name) => panic!("Length of {} tried to be used as a bool!", name),
             },
             Value::Tuple(t) => panic!("Tuple {:?} tried to be used as a bool!", t),
             Value::Array(a) => panic!("Array {:?} tried to be used as a bool!", a),
-            Value::FunctionCall(_name, _args) => unimplemented!(),
-            Value::Reference(_v) => unimplemented!(),
-            Value::ReferenceMutable(_v) => unimplemented!(),
-            Value::Dereference(_v) => unimplemented!(),
+            Value::FunctionCall(name, _args) => {
+                panic!(
+                    "Call to {} can only be used in assignments and bindings!",
+                    name
+                )
+            }
+            // References stand for the values they point to
+            Value::Reference(v) | Value::ReferenceMutable(v) | Value::Dereference(v) => {
+                v.as_bool(ctx)
+            }
             Value::Unit => unimplemented!(),
             Value::Ternary(cond, a, b) => {
                 let (mut checks_t, t) = cond.as_bool(ctx);
                 let (checks_a, a) = a.as_bool(ctx);
                 let (checks_b, b) = b.as_bool(ctx);
-                checks_t.extend(checks_a);
-                checks_t.extend(checks_b);
+                checks_t.extend(guard(checks_a, &t));
+                checks_t.extend(guard(checks_b, &t.not()));
                 (checks_t, t.ite(&a, &b))
             }
         }
@@ -49,57 +107,111 @@ impl ProvableValue for Value {
         log::trace!("AS_INT: {}", self.clone());
         match self {
             Value::Expr(e) => e.as_int(ctx),
-            Value::Bool(b) => {
-                // HACK: This is ugly, should be solved on the parsing level somehow?
-                //panic!("Bool value ({}) used as an int", b)
-                log::debug!("Bool value ({}) used as an int", b);
-                match b.clone() {
-                    Bool::Value(a) => match *a {
-                        Value::Variable(x) => {
-                            Value::Expr(Expr::Value(Box::new(Value::Variable(x)))).as_int(ctx)
-                        }
-                        Value::Expr(x) => Value::Expr(x).as_int(ctx),
-                        _ => panic!("Bool value ({}) used as an int", b),
-                    },
-                    _ => panic!("Bool value ({}) used as an int", b),
-                }
-            }
+            Value::Bool(b) => panic!("Bool value ({}) used as an int", b),
             Value::Variable(x) => match x {
                 Variable::Named(name) => (set![], z3::ast::Int::new_const(ctx, name)),
                 Variable::Empty => panic!("Empty variable tried to be used as an int!"),
                 Variable::ArrayElem(arr_name, ind) => {
-                    let t = z3::ast::Array::new_const(
-                        ctx,
-                        arr_name,
-                        &z3::Sort::int(ctx),
-                        &z3::Sort::int(ctx),
-                    );
+                    let t = array(ctx, arr_name.clone());
 
-                    let (checks, ind) = ind.as_int(ctx);
-                    (checks, t.select(&ind).as_int().unwrap())
+                    let (mut checks, ind_int) = ind.clone().as_int(ctx);
+                    checks.insert(bounds(ctx, arr_name, *ind, &ind_int));
+                    (checks, t.select(&ind_int).as_int().unwrap())
                 }
-
-                Variable::TupleElem(_name, _ind) => unimplemented!(),
+                Variable::TupleElem(name, ind) => {
+                    Value::Variable(tuple_elem(&name, &ind)).as_int(ctx)
+                }
+                Variable::Length(name) => array_len(&name).as_int(ctx),
             },
             Value::Tuple(t) => panic!("Tuple {:?} tried to be used as an intl!", t),
             Value::Array(a) => panic!("Array {:?} tried to be used as an int!", a),
-            Value::FunctionCall(_name, _args) => unimplemented!(),
-            Value::Reference(_v) => unimplemented!(),
-            Value::ReferenceMutable(_v) => unimplemented!(),
-            Value::Dereference(_v) => unimplemented!(),
+            Value::FunctionCall(name, _args) => {
+                panic!(
+                    "Call to {} can only be used in assignments and bindings!",
+                    name
+                )
+            }
+            // References stand for the values they point to
+            Value::Reference(v) | Value::ReferenceMutable(v) | Value::Dereference(v) => {
+                v.as_int(ctx)
+            }
             Value::Unit => unimplemented!(),
             Value::Ternary(cond, a, b) => {
                 let (mut checks_t, t) = cond.as_bool(ctx);
                 let (checks_a, a) = a.as_int(ctx);
                 let (checks_b, b) = b.as_int(ctx);
-                checks_t.extend(checks_a);
-                checks_t.extend(checks_b);
+                checks_t.extend(guard(checks_a, &t));
+                checks_t.extend(guard(checks_b, &t.not()));
                 (checks_t, t.ite(&a, &b))
             }
         }
     }
 }
 
+/// Division rounding toward zero as in Rust, Z3's div is Euclidean (-7 / 2 is -4 there)
+/// For a non-negative dividend both are the same
+fn div_trunc<'a>(
+    ctx: &'a z3::Context,
+    a: &z3::ast::Int<'a>,
+    b: &z3::ast::Int<'a>,
+) -> z3::ast::Int<'a> {
+    let zero = z3::ast::Int::from_i64(ctx, 0);
+    a.ge(&zero)
+        .ite(&a.div(b), &a.unary_minus().div(b).unary_minus())
+}
+
+/// Remainder with the sign of the dividend as in Rust, i.e. `a - b * (a / b)` with the division above
+fn rem_trunc<'a>(
+    ctx: &'a z3::Context,
+    a: &z3::ast::Int<'a>,
+    b: &z3::ast::Int<'a>,
+) -> z3::ast::Int<'a> {
+    let zero = z3::ast::Int::from_i64(ctx, 0);
+    a.ge(&zero)
+        .ite(&a.modulo(b), &a.unary_minus().modulo(b).unary_minus())
+}
+
+/// The ints don't wrap, with `--strict-overflow` the result has to be in the range of its type
+/// The goals are the same as the bit-vectors have (e.g. an unsigned addition can only overflow)
+fn range_checks<'a>(
+    ctx: &'a z3::Context,
+    e: &Expr,
+    op: &Opcode,
+    result: &z3::ast::Int<'a>,
+) -> HashSet<Check<'a>> {
+    let (min, max) = match expr_int_type(e).and_then(|x| x.int_range()) {
+        Some(range) if strict_overflow() => range,
+        _ => return set![],
+    };
+    let signed = min < 0;
+    let (overflow, underflow) = match op {
+        Opcode::Add | Opcode::Mul => (true, signed),
+        Opcode::Sub => (signed, true),
+        Opcode::Div => (signed, false),
+        Opcode::Rem => (false, false),
+    };
+
+    let int = |x: i128| z3::ast::Int::from_str(ctx, &x.to_string()).unwrap();
+    let mut checks = set![];
+    if overflow {
+        checks.insert(Check {
+            goal: Goal::Overflow,
+            object: e.clone(),
+            check: result.le(&int(max)),
+            span: Span::default(),
+        });
+    }
+    if underflow {
+        checks.insert(Check {
+            goal: Goal::Underflow,
+            object: e.clone(),
+            check: result.ge(&int(min)),
+            span: Span::default(),
+        });
+    }
+    checks
+}
+
 pub trait ProvableBool {
     fn as_bool<'a>(self, ctx: &'a z3::Context) -> (HashSet<Check<'a>>, z3::ast::Bool<'a>);
 }
@@ -112,7 +224,7 @@ impl ProvableInt for Expr {
     fn as_int<'a>(self, ctx: &'a z3::Context) -> (HashSet<Check<'a>>, z3::ast::Int<'a>) {
         log::trace!("AS_INT: {}", self.clone());
         match self.clone() {
-            Expr::Number(a) => (set![], z3::ast::Int::from_i64(ctx, a.into())),
+            Expr::Number(a) => (set![], z3::ast::Int::from_str(ctx, &a.to_string()).unwrap()),
             Expr::Op(a, op, b) => match op {
                 Opcode::Add => {
                     // These checks are only done after the solver has already finished
@@ -120,7 +232,9 @@ impl ProvableInt for Expr {
                     let (checks_b, b) = b.as_int(ctx);
                     checks_a.extend(checks_b);
 
-                    (checks_a, z3::ast::Int::add(ctx, &[&a, &b]))
+                    let result = z3::ast::Int::add(ctx, &[&a, &b]);
+                    checks_a.extend(range_checks(ctx, &self, &op, &result));
+                    (checks_a, result)
                 }
                 Opcode::Sub => {
                     // These checks are only done after the solver has already finished
@@ -128,7 +242,9 @@ impl ProvableInt for Expr {
                     let (checks_b, b) = b.as_int(ctx);
                     checks_a.extend(checks_b);
 
-                    (checks_a, z3::ast::Int::sub(ctx, &[&a, &b]))
+                    let result = z3::ast::Int::sub(ctx, &[&a, &b]);
+                    checks_a.extend(range_checks(ctx, &self, &op, &result));
+                    (checks_a, result)
                 }
                 Opcode::Mul => {
                     // These checks are only done after the solver has already finished
@@ -136,7 +252,9 @@ impl ProvableInt for Expr {
                     let (checks_b, b) = b.as_int(ctx);
                     checks_a.extend(checks_b);
 
-                    (checks_a, z3::ast::Int::mul(ctx, &[&a, &b]))
+                    let result = z3::ast::Int::mul(ctx, &[&a, &b]);
+                    checks_a.extend(range_checks(ctx, &self, &op, &result));
+                    (checks_a, result)
                 }
                 Opcode::Div => {
                     // These checks are only done after the solver has already finished
@@ -144,14 +262,33 @@ impl ProvableInt for Expr {
                     let (checks_b, b) = b.as_int(ctx);
                     checks_a.extend(checks_b);
 
-                    (checks_a, a.div(&b))
+                    let non_zero = b._eq(&z3::ast::Int::from_i64(ctx, 0)).not();
+                    checks_a.insert(Check {
+                        goal: Goal::DivisionByZero,
+                        object: self.clone(),
+                        check: non_zero.clone(),
+                        span: Span::default(),
+                    });
+
+                    let result = div_trunc(ctx, &a, &b);
+                    // The result of a division by zero is anything, that's failed on its own
+                    let range = range_checks(ctx, &self, &op, &result);
+                    checks_a.extend(guard(range, &non_zero));
+                    (checks_a, result)
                 }
                 Opcode::Rem => {
                     let (mut checks_a, a) = a.as_int(ctx);
                     let (checks_b, b) = b.as_int(ctx);
                     checks_a.extend(checks_b);
 
-                    (checks_a, a.rem(&b))
+                    checks_a.insert(Check {
+                        goal: Goal::DivisionByZero,
+                        object: self.clone(),
+                        check: b._eq(&z3::ast::Int::from_i64(ctx, 0)).not(),
+                        span: Span::default(),
+                    });
+
+                    (checks_a, rem_trunc(ctx, &a, &b))
                 }
             },
             Expr::Value(a) => a.as_int(ctx),
@@ -190,30 +327,46 @@ impl ProvableBool for Bool {
             Bool::And(_a, _b) => {
                 let (mut checks_a, a) = (*_a).as_bool(ctx);
                 let (checks_b, b) = (*_b).as_bool(ctx);
-                checks_a.extend(checks_b);
+                // Evaluated lazily, the right side only matters if the left one holds
+                checks_a.extend(guard(checks_b, &a));
                 (checks_a, z3::ast::Bool::and(ctx, &[&a, &b]))
             }
             Bool::Or(_a, _b) => {
                 let (mut checks_a, a) = (*_a).as_bool(ctx);
                 let (checks_b, b) = (*_b).as_bool(ctx);
-                checks_a.extend(checks_b);
+                checks_a.extend(guard(checks_b, &a.not()));
                 (checks_a, z3::ast::Bool::or(ctx, &[&a, &b]))
             }
             Bool::Not(a) => {
                 let (checks, t) = a.as_bool(ctx);
                 (checks, z3::ast::Bool::not(&t))
             }
-            Bool::ValueEqual(a, b) => {
-                let (mut checks_a, a) = a.as_int(ctx);
-                let (checks_b, b) = b.as_int(ctx);
-                checks_a.extend(checks_b);
-                (checks_a, z3::ast::Bool::and(ctx, &[&a.ge(&b), &a.le(&b)]))
+            Bool::Located(a, span) => {
+                let (checks, t) = a.as_bool(ctx);
+                (locate(checks, span), t)
             }
+            Bool::ValueEqual(a, b) => match (*a, *b) {
+                (Value::Variable(Variable::Named(x)), Value::Variable(Variable::Named(y)))
+                    if is_array(&x) =>
+                {
+                    (set![], array(ctx, x)._eq(&array(ctx, y)))
+                }
+                (a, b) if is_bool(&a) || is_bool(&b) => {
+                    let (mut checks_a, a) = bool_value(a).as_bool(ctx);
+                    let (checks_b, b) = bool_value(b).as_bool(ctx);
+                    checks_a.extend(checks_b);
+                    (checks_a, a._eq(&b))
+                }
+                (a, b) => {
+                    let (mut checks_a, a) = a.as_int(ctx);
+                    let (checks_b, b) = b.as_int(ctx);
+                    checks_a.extend(checks_b);
+                    (checks_a, z3::ast::Bool::and(ctx, &[&a.ge(&b), &a.le(&b)]))
+                }
+            },
+            // Bools can be compared too
             Bool::Equal(a, b) => {
-                let (mut checks_a, a) = a.as_int(ctx);
-                let (checks_b, b) = b.as_int(ctx);
-                checks_a.extend(checks_b);
-                (checks_a, z3::ast::Bool::and(ctx, &[&a.ge(&b), &a.le(&b)]))
+                Bool::ValueEqual(Box::new(Value::Expr(a)), Box::new(Value::Expr(b))).as_bool(ctx)
             }
             Bool::GreaterEqual(a, b) => {
                 let (mut checks_a, a) = a.as_int(ctx);
@@ -237,9 +390,9 @@ impl ProvableBool for Bool {
                 (checks_a, a.gt(&b))
             }
             Bool::LowerThan(a, b) => {
-                let (checks_a, a) = a.as_int(ctx);
+                let (mut checks_a, a) = a.as_int(ctx);
                 let (checks_b, b) = b.as_int(ctx);
-                checks_a.union(&checks_b);
+                checks_a.extend(checks_b);
 
                 (checks_a, a.lt(&b))
             }
@@ -248,27 +401,74 @@ impl ProvableBool for Bool {
     }
 }
 
-pub trait ProvableCommand {
-    fn as_bool<'a>(self, ctx: &'a z3::Context) -> (bool, HashSet<Check<'a>>, z3::ast::Bool<'a>);
+/// Get the value of an int from the model (used for the counterexamples)
+pub fn eval_int<'a>(ctx: &'a z3::Context, solver: &dyn Solver<'a>, v: Value) -> Option<i64> {
+    let (_, t) = v.as_int(ctx);
+    solver.value(&Term::new(&t))?.int()
 }
 
-impl ProvableCommand for Command {
-    // Concept: Try to prove not (negate everything) to try to find if there exists an incorrect mapping?
-    fn as_bool<'a>(self, ctx: &'a z3::Context) -> (bool, HashSet<Check<'a>>, z3::ast::Bool<'a>) {
-        log::trace!("AS_BOOL: {}", self.clone());
-        match self {
-            Command::ProveControl(a) => match a {
-                // We are trying to find COUNTER example here.
-                // So if we get sat, then it means that the assertion is actually incorrect
-                ProveControl::Assert(b) => {
-                    let (checks, t) = b.as_bool(ctx);
-                    (true, checks, t.not())
-                }
+/// Ints are unbounded, so the variables are limited to the ranges of their types
+pub fn assumptions<'a>(ctx: &'a z3::Context, p: &Bool, q: &Bool) -> z3::ast::Bool<'a> {
+    let mut vars = p.clone().get_variables();
+    vars.extend(q.clone().get_variables());
+    // Always in the same order, so the same triple gives the same query
+    let mut vars: Vec<Variable> = vars.into_iter().collect();
+    vars.sort_by_key(|x| x.to_string());
+
+    let int = |x: i128| z3::ast::Int::from_str(ctx, &x.to_string()).unwrap();
+    let mut names = HashSet::new();
+    let mut result = Vec::new();
+    for i in vars {
+        let i = match i {
+            Variable::TupleElem(name, ind) => tuple_elem(&name, &ind),
+            // The length of a slice is a usize of its own
+            Variable::Length(name) => match array_len(&name) {
+                Value::Variable(x) => x,
+                _ => continue,
             },
-            _ => {
-                // Nothing to prove here
-                (false, set![], z3::ast::Bool::from_bool(&ctx, true))
+            _ => i,
+        };
+        match i {
+            // A literal is the value it's equal to, assuming the range would make it contradict an out-of-range one
+            Variable::Named(name) if is_literal(&name) => {}
+            Variable::Named(name) => {
+                if let Some((min, max)) = variable_type(&name).and_then(|x| x.int_range()) {
+                    let x = z3::ast::Int::new_const(ctx, name);
+                    result.push(z3::ast::Bool::and(
+                        ctx,
+                        &[&x.ge(&int(min)), &x.le(&int(max))],
+                    ));
+                }
+            }
+            Variable::ArrayElem(name, _) => {
+                if !names.insert(name.clone()) {
+                    continue;
+                }
+                let elem = Value::Variable(Variable::ArrayElem(
+                    name.clone(),
+                    Box::new(Value::Expr(Expr::Number(0))),
+                ));
+                if let Some((min, max)) = value_int_type(&elem).and_then(|x| x.int_range()) {
+                    // All the elements are in the range
+                    let t = array(ctx, name);
+                    let ind = z3::ast::Int::new_const(ctx, "__elem_index");
+                    let x = t.select(&ind).as_int().unwrap();
+                    let range = z3::ast::Bool::and(ctx, &[&x.ge(&int(min)), &x.le(&int(max))]);
+                    let forall: z3::ast::Bool =
+                        z3::ast::forall_const(ctx, &[&ind.into()], &[], &range)
+                            .try_into()
+                            .unwrap();
+                    result.push(forall);
+                }
             }
+            _ => {}
         }
     }
+
+    // An empty `and` isn't valid SMT-LIB2, the queries have to be readable by other solvers too
+    if result.is_empty() {
+        return z3::ast::Bool::from_bool(ctx, true);
+    }
+    let result: Vec<&z3::ast::Bool> = result.iter().collect();
+    z3::ast::Bool::and(ctx, &result)
 }
@@ -0,0 +1,279 @@
+use crate::ast::Span;
+use crate::diagnostic::{self, Diagnostic, Source};
+use crate::prover::counterexample::Counterexample;
+use std::cell::RefCell;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[cfg(test)]
+mod tests;
+
+/// What the proven condition stands for
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum Goal {
+    Postcondition,
+    Assertion,
+    CallPrecondition,
+    /// Invariant is preserved by the loop body
+    LoopInvariant,
+    /// Variant gets smaller with each iteration
+    VariantDecrease,
+    /// Variant is never negative when the invariant holds
+    VariantNonNegative,
+    /// Condition following the loop holds once it ends
+    LoopExit,
+    Overflow,
+    Underflow,
+    /// Divisor of / or % is not zero
+    DivisionByZero,
+    /// Index of an array access is smaller than its length
+    Bounds,
+}
+
+impl fmt::Display for Goal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Goal::Postcondition => write!(f, "postcondition"),
+            Goal::Assertion => write!(f, "assertion"),
+            Goal::CallPrecondition => write!(f, "precondition of the called function"),
+            Goal::LoopInvariant => write!(f, "loop invariant"),
+            Goal::VariantDecrease => write!(f, "decrease of the loop variant"),
+            Goal::VariantNonNegative => write!(f, "non-negativity of the loop variant"),
+            Goal::LoopExit => write!(f, "condition after the loop"),
+            Goal::Overflow => write!(f, "absence of overflow"),
+            Goal::Underflow => write!(f, "absence of underflow"),
+            Goal::DivisionByZero => write!(f, "non-zero divisor"),
+            Goal::Bounds => write!(f, "index in bounds"),
+        }
+    }
+}
+
+impl Goal {
+    /// Name used in the machine readable output
+    pub fn id(&self) -> &'static str {
+        match self {
+            Goal::Postcondition => "postcondition",
+            Goal::Assertion => "assertion",
+            Goal::CallPrecondition => "call_precondition",
+            Goal::LoopInvariant => "loop_invariant",
+            Goal::VariantDecrease => "variant_decrease",
+            Goal::VariantNonNegative => "variant_non_negative",
+            Goal::LoopExit => "loop_exit",
+            Goal::Overflow => "overflow",
+            Goal::Underflow => "underflow",
+            Goal::DivisionByZero => "division_by_zero",
+            Goal::Bounds => "bounds",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Status {
+    Proven,
+    Failed,
+    /// Z3 gave up
+    Unknown,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Status::Proven => write!(f, "proven"),
+            Status::Failed => write!(f, "failed"),
+            Status::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+/// Result of proving a single condition
+#[derive(Clone, Debug)]
+pub struct Obligation {
+    pub goal: Goal,
+    /// The condition to be proven, printed the way the prover sees it (the arithmetic in prefix form)
+    pub condition: String,
+    pub span: Span,
+    pub status: Status,
+    /// Time spent in Z3
+    pub time: Duration,
+    pub counterexample: Option<Counterexample>,
+    /// Why Z3 gave up, e.g. "timeout", only for the unknown ones
+    pub reason: Option<String>,
+    /// The file the span points to
+    pub source: Option<Arc<Source>>,
+}
+
+impl Obligation {
+    /// Describe the failure rustc style, with the counterexample
+    pub fn diagnostic(&self) -> String {
+        let mut result = match self.status {
+            Status::Unknown => diagnostic::format(
+                &format!(
+                    "could not decide {} ({}): {}",
+                    self.goal,
+                    self.reason.as_deref().unwrap_or("unknown"),
+                    self.condition
+                ),
+                self.span,
+                self.source.as_deref(),
+            ),
+            _ => diagnostic::format(
+                &format!("failed to prove {}: {}", self.goal, self.condition),
+                self.span,
+                self.source.as_deref(),
+            ),
+        };
+
+        if let Some(c) = &self.counterexample {
+            result += &format!("\nCounterexample:\n{}", c);
+        }
+        result
+    }
+
+    pub fn to_json(&self) -> String {
+        let counterexample = match &self.counterexample {
+            Some(c) => c.to_json(),
+            None => String::from("null"),
+        };
+        let reason = match &self.reason {
+            Some(r) => json_string(r),
+            None => String::from("null"),
+        };
+
+        format!(
+            "{{\"kind\": \"{}\", \"condition\": {}, \"status\": \"{}\", \"reason\": {}, \"time_ms\": {:.3}, \"location\": {}, \"counterexample\": {}}}",
+            self.goal.id(),
+            json_string(&self.condition),
+            self.status,
+            reason,
+            self.time.as_secs_f64() * 1000.0,
+            location_json(self.span, self.source.as_deref()),
+            counterexample
+        )
+    }
+}
+
+/// All the obligations checked for a single function
+#[derive(Clone, Debug)]
+pub struct FunctionReport {
+    pub name: String,
+    pub obligations: Vec<Obligation>,
+}
+
+impl FunctionReport {
+    pub fn status(&self) -> Status {
+        if self.obligations.iter().any(|x| x.status == Status::Failed) {
+            Status::Failed
+        } else if self.obligations.iter().any(|x| x.status == Status::Unknown) {
+            Status::Unknown
+        } else {
+            Status::Proven
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        let obligations: Vec<String> = self.obligations.iter().map(|x| x.to_json()).collect();
+        format!(
+            "{{\"name\": {}, \"status\": \"{}\", \"obligations\": [{}]}}",
+            json_string(&self.name),
+            self.status(),
+            obligations.join(", ")
+        )
+    }
+}
+
+/// Result of proving the whole program
+#[derive(Clone, Debug, Default)]
+pub struct Report {
+    pub functions: Vec<FunctionReport>,
+}
+
+impl Report {
+    pub fn proven(&self) -> bool {
+        self.functions.iter().all(|x| x.status() == Status::Proven)
+    }
+
+    /// Failed if anything is disproven, unknown if it's only not decided
+    pub fn status(&self) -> Status {
+        if self.proven() {
+            Status::Proven
+        } else if self.functions.iter().any(|x| x.status() == Status::Failed) {
+            Status::Failed
+        } else {
+            Status::Unknown
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        let functions: Vec<String> = self.functions.iter().map(|x| x.to_json()).collect();
+
+        format!(
+            "{{\"status\": \"{}\", \"functions\": [{}]}}",
+            self.status(),
+            functions.join(", ")
+        )
+    }
+}
+
+/// Errors that stopped the program from being proven (`stage` is e.g. "parse" or "validation")
+pub fn errors_json(stage: &str, errors: &[Diagnostic]) -> String {
+    let errors: Vec<String> = errors
+        .iter()
+        .map(|x| {
+            format!(
+                "{{\"message\": {}, \"location\": {}}}",
+                json_string(&x.message),
+                location_json(x.span, x.source.as_deref())
+            )
+        })
+        .collect();
+
+    format!(
+        "{{\"status\": \"error\", \"stage\": {}, \"errors\": [{}]}}",
+        json_string(stage),
+        errors.join(", ")
+    )
+}
+
+fn location_json(span: Span, source: Option<&Source>) -> String {
+    match diagnostic::location(source, span) {
+        Some((file, line, column)) => format!(
+            "{{\"file\": {}, \"line\": {}, \"column\": {}}}",
+            json_string(&file),
+            line,
+            column
+        ),
+        None => String::from("null"),
+    }
+}
+
+thread_local! {
+    /// Obligations checked so far, they are proven deep inside the weakest precondition calculation
+    static OBLIGATIONS: RefCell<Vec<Obligation>> = const { RefCell::new(Vec::new()) };
+}
+
+pub fn record(obligation: Obligation) {
+    OBLIGATIONS.with(|x| x.borrow_mut().push(obligation));
+}
+
+/// Get all the obligations recorded so far and start over
+pub fn take() -> Vec<Obligation> {
+    OBLIGATIONS.with(|x| x.borrow_mut().drain(..).collect())
+}
+
+pub fn json_string(s: &str) -> String {
+    let mut result = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => result += "\\\"",
+            '\\' => result += "\\\\",
+            '\n' => result += "\\n",
+            '\r' => result += "\\r",
+            '\t' => result += "\\t",
+            c if (c as u32) < 0x20 => result += &format!("\\u{:04x}", c as u32),
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
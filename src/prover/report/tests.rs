@@ -0,0 +1,105 @@
+use crate::prover::report::*;
+
+fn obligation(goal: Goal, status: Status) -> Obligation {
+    Obligation {
+        goal,
+        condition: String::from("x > 0"),
+        span: Span::default(),
+        status,
+        time: Duration::from_millis(2),
+        counterexample: None,
+        reason: None,
+        source: None,
+    }
+}
+
+#[test]
+fn json_string1() {
+    assert_eq!(json_string("a"), "\"a\"");
+    assert_eq!(json_string("x'old"), "\"x'old\"");
+    assert_eq!(json_string("\"a\"\n\\"), "\"\\\"a\\\"\\n\\\\\"");
+}
+
+#[test]
+fn function_status1() {
+    let mut f = FunctionReport {
+        name: String::from("f"),
+        obligations: vec![
+            obligation(Goal::Assertion, Status::Proven),
+            obligation(Goal::Postcondition, Status::Proven),
+        ],
+    };
+    assert_eq!(f.status(), Status::Proven);
+
+    f.obligations
+        .push(obligation(Goal::LoopInvariant, Status::Unknown));
+    assert_eq!(f.status(), Status::Unknown);
+
+    f.obligations
+        .push(obligation(Goal::VariantDecrease, Status::Failed));
+    assert_eq!(f.status(), Status::Failed);
+}
+
+#[test]
+fn report_json1() {
+    let report = Report {
+        functions: vec![FunctionReport {
+            name: String::from("f"),
+            obligations: vec![obligation(Goal::VariantNonNegative, Status::Failed)],
+        }],
+    };
+
+    assert!(!report.proven());
+    assert_eq!(
+        report.to_json(),
+        "{\"status\": \"failed\", \"functions\": [{\"name\": \"f\", \"status\": \"failed\", \"obligations\": [\
+         {\"kind\": \"variant_non_negative\", \"condition\": \"x > 0\", \"status\": \"failed\", \"reason\": null, \
+         \"time_ms\": 2.000, \"location\": null, \"counterexample\": null}]}]}"
+    );
+}
+
+#[test]
+fn report_unknown1() {
+    let mut unknown = obligation(Goal::Postcondition, Status::Unknown);
+    unknown.reason = Some(String::from("timeout"));
+    let mut report = Report {
+        functions: vec![FunctionReport {
+            name: String::from("f"),
+            obligations: vec![obligation(Goal::Assertion, Status::Proven), unknown],
+        }],
+    };
+
+    assert!(!report.proven());
+    assert_eq!(report.status(), Status::Unknown);
+    let diagnostic = report.functions[0].obligations[1].diagnostic();
+    assert!(diagnostic.starts_with("error: could not decide postcondition (timeout): x > 0"));
+    assert!(report.to_json().contains("\"reason\": \"timeout\""));
+
+    report.functions[0]
+        .obligations
+        .push(obligation(Goal::Assertion, Status::Failed));
+    assert_eq!(report.status(), Status::Failed);
+}
+
+#[test]
+fn errors_json1() {
+    let errors = vec![
+        Diagnostic {
+            message: String::from("variable redeclared: x"),
+            span: Span::default(),
+            source: None,
+        },
+        Diagnostic {
+            message: String::from("undefined variable: \"y\""),
+            span: Span::default(),
+            source: None,
+        },
+    ];
+
+    assert_eq!(
+        errors_json("validation", &errors),
+        "{\"status\": \"error\", \"stage\": \"validation\", \"errors\": [\
+         {\"message\": \"variable redeclared: x\", \"location\": null}, \
+         {\"message\": \"undefined variable: \\\"y\\\"\", \"location\": null}]}"
+    );
+}
@@ -0,0 +1,368 @@
+use crate::ast::Limits;
+use crate::prover::solver::{SatResult, Solver, Term};
+use nom::{
+    branch::alt, bytes::complete::is_not, bytes::complete::tag, bytes::complete::take_while,
+    bytes::complete::take_while1, character::complete::multispace0, combinator::recognize,
+    multi::many0, sequence::tuple, IResult,
+};
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fmt;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+#[cfg(test)]
+mod tests;
+
+/// Answer of the solver, the atoms are kept the way they are written
+#[derive(Clone, Debug, PartialEq)]
+pub enum Sexp {
+    Atom(String),
+    List(Vec<Sexp>),
+}
+
+impl fmt::Display for Sexp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Sexp::Atom(x) => write!(f, "{}", x),
+            Sexp::List(x) => {
+                let elems: Vec<String> = x.iter().map(|x| x.to_string()).collect();
+                write!(f, "({})", elems.join(" "))
+            }
+        }
+    }
+}
+
+impl Sexp {
+    /// Int numeral, the negative ones are written as (- n)
+    pub fn int(&self) -> Option<i64> {
+        match self {
+            Sexp::Atom(x) => x.parse().ok(),
+            Sexp::List(x) => match x.as_slice() {
+                [Sexp::Atom(minus), n] if minus == "-" => n.int().map(|n| -n),
+                _ => None,
+            },
+        }
+    }
+
+    /// Bit-vector literal, #b.., #x.. or (_ bvN width)
+    pub fn bv(&self) -> Option<u64> {
+        match self {
+            Sexp::Atom(x) if x.starts_with("#b") => u64::from_str_radix(&x[2..], 2).ok(),
+            Sexp::Atom(x) if x.starts_with("#x") => u64::from_str_radix(&x[2..], 16).ok(),
+            Sexp::List(x) => match x.as_slice() {
+                [Sexp::Atom(u), Sexp::Atom(n), _] if u == "_" && n.starts_with("bv") => {
+                    n[2..].parse().ok()
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    pub fn bool(&self) -> Option<bool> {
+        match self {
+            Sexp::Atom(x) if x == "true" => Some(true),
+            Sexp::Atom(x) if x == "false" => Some(false),
+            _ => None,
+        }
+    }
+
+    /// Content of the string literal or the symbol, without the quotes
+    fn text(&self) -> Option<String> {
+        match self {
+            Sexp::Atom(x) if x.starts_with('"') => Some(x[1..x.len() - 1].replace("\"\"", "\"")),
+            Sexp::Atom(x) if x.starts_with('|') => Some(x[1..x.len() - 1].to_string()),
+            Sexp::Atom(x) => Some(x.clone()),
+            Sexp::List(_) => None,
+        }
+    }
+}
+
+/// The commands of a script, or the values in an answer
+pub fn commands(input: &str) -> Vec<Sexp> {
+    many0(sexp)(input).map(|(_, x)| x).unwrap_or_default()
+}
+
+fn sexp(input: &str) -> IResult<&str, Sexp> {
+    tuple((multispace0, alt((list, atom))))(input).map(|(next_input, (_, x))| (next_input, x))
+}
+
+fn list(input: &str) -> IResult<&str, Sexp> {
+    tuple((tag("("), many0(sexp), multispace0, tag(")")))(input)
+        .map(|(next_input, (_, x, _, _))| (next_input, Sexp::List(x)))
+}
+
+/// Symbol, numeral, keyword or string literal ("" is a quote inside it)
+fn atom(input: &str) -> IResult<&str, Sexp> {
+    alt((
+        recognize(tuple((
+            tag("\""),
+            many0(alt((tag("\"\""), is_not("\"")))),
+            tag("\""),
+        ))),
+        recognize(tuple((tag("|"), take_while(|c| c != '|'), tag("|")))),
+        take_while1(|c: char| !c.is_whitespace() && !"()\"|".contains(c)),
+    ))(input)
+    .map(|(next_input, x)| (next_input, Sexp::Atom(x.to_string())))
+}
+
+/// A running solver, its output is read line by line on a thread of its own
+struct Process {
+    child: Option<Child>,
+    input: Box<dyn Write>,
+    output: Receiver<String>,
+    /// Output read, but not taken as an answer yet
+    pending: String,
+}
+
+impl Process {
+    fn spawn(command: &[String]) -> std::io::Result<Self> {
+        let mut child = Command::new(&command[0])
+            .args(&command[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let input = Box::new(child.stdin.take().unwrap());
+        let stdout = child.stdout.take().unwrap();
+        let (sender, output) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let sent = line.map(|x| sender.send(x).is_ok());
+                if sent.ok() != Some(true) {
+                    break;
+                }
+            }
+        });
+        Ok(Process {
+            child: Some(child),
+            input,
+            output,
+            pending: String::new(),
+        })
+    }
+
+    /// Send the command and read the answer to it, giving up after the deadline
+    fn ask(&mut self, command: &str, deadline: Option<Instant>) -> Result<Sexp, String> {
+        log::trace!("To the solver: {}", command);
+        let exited = || String::from("solver exited");
+        writeln!(self.input, "{}", command)
+            .and_then(|_| self.input.flush())
+            .map_err(|_| exited())?;
+        loop {
+            if let Ok((rest, answer)) = sexp(&self.pending) {
+                self.pending = rest.to_string();
+                log::trace!("From the solver: {}", answer);
+                return Ok(answer);
+            }
+            let line = match deadline {
+                Some(d) => self
+                    .output
+                    .recv_timeout(d.saturating_duration_since(Instant::now()))
+                    .map_err(|e| match e {
+                        RecvTimeoutError::Timeout => String::from("timeout"),
+                        RecvTimeoutError::Disconnected => exited(),
+                    }),
+                None => self.output.recv().map_err(|_| exited()),
+            }?;
+            self.pending += &line;
+            self.pending.push('\n');
+        }
+    }
+}
+
+impl Drop for Process {
+    fn drop(&mut self) {
+        if let Some(child) = &mut self.child {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+/// Any solver reading SMT-LIB2 from stdin and answering on stdout (e.g. `z3 -in` or `cvc5 --incremental`)
+pub struct Pipe {
+    command: Vec<String>,
+    limits: Limits,
+    state: RefCell<State>,
+}
+
+struct State {
+    /// Started by the first check, stopped on a timeout and started again by the next one
+    process: Option<Process>,
+    /// Declarations and assertions of every scope, a started solver gets all of them
+    scopes: Vec<Vec<String>>,
+    /// Names of the constants declared in every scope
+    declared: Vec<HashSet<String>>,
+    reason_unknown: Option<String>,
+}
+
+impl Pipe {
+    pub fn new(command: Vec<String>, limits: Limits) -> Self {
+        Pipe {
+            command,
+            limits,
+            state: RefCell::new(State {
+                process: None,
+                scopes: vec![Vec::new()],
+                declared: vec![HashSet::new()],
+                reason_unknown: None,
+            }),
+        }
+    }
+
+    fn running(&self) -> bool {
+        self.state.borrow().process.is_some()
+    }
+
+    /// Start the solver if it isn't running, ask it and stop it if it doesn't answer
+    fn ask(&self, command: &str, deadline: Option<Instant>) -> Result<Sexp, String> {
+        let mut state = self.state.borrow_mut();
+        let state = &mut *state;
+        if state.process.is_none() {
+            let command = self.command.join(" ");
+            let mut process = Process::spawn(&self.command).map_err(|e| {
+                log::error!("Failed to start {}: {}", command, e);
+                format!("failed to start {}", command)
+            })?;
+            let mut setup = vec![
+                String::from("(set-option :print-success true)"),
+                String::from("(set-option :produce-models true)"),
+            ];
+            // Not a standard option, the solvers that don't know it answer unsupported
+            if let Some(r) = self.limits.rlimit {
+                setup.push(format!("(set-option :rlimit {})", r));
+            }
+            for (i, scope) in state.scopes.iter().enumerate() {
+                if i > 0 {
+                    setup.push(String::from("(push 1)"));
+                }
+                setup.extend(scope.iter().cloned());
+            }
+            for c in setup {
+                let answer = process.ask(&c, None)?;
+                expect_success(&c, &answer);
+            }
+            state.process = Some(process);
+        }
+
+        let answer = state.process.as_mut().unwrap().ask(command, deadline);
+        if answer.is_err() {
+            state.process = None;
+        }
+        answer
+    }
+
+    /// Send the command to the running solver, a solver started later gets it then
+    fn command(&self, command: String) {
+        if self.running() {
+            match self.ask(&command, None) {
+                Ok(answer) => expect_success(&command, &answer),
+                Err(e) => log::warn!("The solver didn't take {}: {}", command, e),
+            }
+        }
+        let mut state = self.state.borrow_mut();
+        state.scopes.last_mut().unwrap().push(command);
+    }
+
+    /// Value of the term in the model of the last check
+    fn get_value(&self, term: String) -> Option<Sexp> {
+        if !self.running() {
+            return None;
+        }
+        match self.ask(&format!("(get-value ({}))", term), None) {
+            Ok(Sexp::List(x)) => match x.into_iter().next() {
+                Some(Sexp::List(pair)) => pair.into_iter().nth(1),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+fn expect_success(command: &str, answer: &Sexp) {
+    match answer {
+        Sexp::Atom(x) if x == "success" || x == "unsupported" => {}
+        x => log::warn!("The solver answered {} to {}", x, command),
+    }
+}
+
+impl<'a> Solver<'a> for Pipe {
+    fn assert(&self, b: &Term<'a>) {
+        for d in b.declarations() {
+            let declared = self
+                .state
+                .borrow()
+                .declared
+                .iter()
+                .any(|x| x.contains(&d.name));
+            if !declared {
+                self.command(d.to_string());
+                let mut state = self.state.borrow_mut();
+                state.declared.last_mut().unwrap().insert(d.name);
+            }
+        }
+        self.command(format!("(assert {})", b.text()));
+    }
+
+    fn push(&self) {
+        if self.running() {
+            if let Err(e) = self.ask("(push 1)", None) {
+                log::warn!("The solver didn't push: {}", e);
+            }
+        }
+        let mut state = self.state.borrow_mut();
+        state.scopes.push(Vec::new());
+        state.declared.push(HashSet::new());
+    }
+
+    fn pop(&self) {
+        {
+            let mut state = self.state.borrow_mut();
+            state.scopes.pop();
+            state.declared.pop();
+        }
+        // A stopped solver starts again without the scope anyway
+        if self.running() {
+            if let Err(e) = self.ask("(pop 1)", None) {
+                log::warn!("The solver didn't pop: {}", e);
+            }
+        }
+    }
+
+    fn check(&self) -> SatResult {
+        let deadline = self
+            .limits
+            .timeout
+            .map(|ms| Instant::now() + Duration::from_millis(ms.into()));
+        let (result, reason) = match self.ask("(check-sat)", deadline) {
+            Ok(Sexp::Atom(x)) if x == "sat" => (SatResult::Sat, None),
+            Ok(Sexp::Atom(x)) if x == "unsat" => (SatResult::Unsat, None),
+            Ok(Sexp::Atom(x)) if x == "unknown" => {
+                let reason = match self.ask("(get-info :reason-unknown)", None) {
+                    Ok(Sexp::List(x)) => x.get(1).and_then(|x| x.text()),
+                    _ => None,
+                };
+                (SatResult::Unknown, reason)
+            }
+            Ok(Sexp::List(x)) if x.len() == 2 && x[0] == Sexp::Atom(String::from("error")) => {
+                (SatResult::Unknown, x[1].text())
+            }
+            Ok(x) => (SatResult::Unknown, Some(format!("unexpected answer {}", x))),
+            Err(e) => (SatResult::Unknown, Some(e)),
+        };
+        self.state.borrow_mut().reason_unknown = reason;
+        result
+    }
+
+    fn reason_unknown(&self) -> Option<String> {
+        self.state.borrow().reason_unknown.clone()
+    }
+
+    fn value(&self, t: &Term<'a>) -> Option<Sexp> {
+        self.get_value(t.text())
+    }
+}
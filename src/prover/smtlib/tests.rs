@@ -0,0 +1,191 @@
+use crate::prover::smtlib::*;
+use crate::prover::solver::Declaration;
+use std::rc::Rc;
+use std::sync::mpsc::Sender;
+
+fn atom(x: &str) -> Sexp {
+    Sexp::Atom(String::from(x))
+}
+
+#[test]
+fn sexp1() {
+    assert_eq!(sexp("sat\n").unwrap().1, atom("sat"));
+    assert_eq!(
+        sexp(" ((x 5) (|x'old| (- 3)))").unwrap().1,
+        Sexp::List(vec![
+            Sexp::List(vec![atom("x"), atom("5")]),
+            Sexp::List(vec![
+                atom("|x'old|"),
+                Sexp::List(vec![atom("-"), atom("3")])
+            ]),
+        ])
+    );
+    assert_eq!(
+        sexp("(error \"line 1: \"\"x\"\" (unknown)\")").unwrap().1,
+        Sexp::List(vec![atom("error"), atom("\"line 1: \"\"x\"\" (unknown)\"")])
+    );
+    // Not finished yet
+    assert!(sexp("((x 5)\n").is_err());
+    assert!(sexp("\n").is_err());
+}
+
+#[test]
+fn sexp_values1() {
+    assert_eq!(sexp("(- 3)").unwrap().1.int(), Some(-3));
+    assert_eq!(atom("42").int(), Some(42));
+    assert_eq!(atom("#x0000000f").bv(), Some(15));
+    assert_eq!(atom("#b101").bv(), Some(5));
+    assert_eq!(sexp("(_ bv7 32)").unwrap().1.bv(), Some(7));
+    assert_eq!(atom("false").bool(), Some(false));
+    assert_eq!(
+        atom("\"say \"\"hi\"\"\"").text(),
+        Some(String::from("say \"hi\""))
+    );
+    assert_eq!(atom("|x'old|").text(), Some(String::from("x'old")));
+}
+
+/// Everything written to the solver
+#[derive(Clone, Default)]
+struct Written(Rc<RefCell<Vec<u8>>>);
+
+impl Write for Written {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Written {
+    fn commands(&self) -> String {
+        String::from_utf8(self.0.borrow().clone()).unwrap()
+    }
+}
+
+/// Pipe to a solver answering what it's given in advance
+fn fake(limits: Limits) -> (Pipe, Written, Sender<String>) {
+    let written = Written::default();
+    let (sender, output) = mpsc::channel();
+    let pipe = Pipe::new(vec![], limits);
+    pipe.state.borrow_mut().process = Some(Process {
+        child: None,
+        input: Box::new(written.clone()),
+        output,
+        pending: String::new(),
+    });
+    (pipe, written, sender)
+}
+
+fn answer(sender: &Sender<String>, lines: &[&str]) {
+    for x in lines {
+        sender.send(x.to_string()).unwrap();
+    }
+}
+
+#[test]
+fn declarations1() {
+    let cfg = z3::Config::new();
+    let ctx = z3::Context::new(&cfg);
+    let a = z3::ast::Array::new_const(&ctx, "a", &z3::Sort::int(&ctx), &z3::Sort::bool(&ctx));
+    let i = z3::ast::Int::new_const(&ctx, "i");
+    // The array is only used in the quantifier
+    let b = z3::ast::forall_const(
+        &ctx,
+        &[&i.clone().into()],
+        &[],
+        &a.select(&i).as_bool().unwrap(),
+    )
+    .as_bool()
+    .unwrap();
+    let t = Term::new(&z3::ast::Bool::and(
+        &ctx,
+        &[&b, &z3::ast::Bool::new_const(&ctx, "(declare-fun")],
+    ));
+
+    let mut declarations = t.declarations();
+    declarations.sort_by(|x, y| x.name.cmp(&y.name));
+    assert_eq!(
+        declarations,
+        vec![
+            Declaration {
+                name: String::from("a"),
+                domain: vec![],
+                range: sexp("(Array Int Bool)").unwrap().1,
+            },
+            Declaration {
+                name: String::from("|(declare-fun|"),
+                domain: vec![],
+                range: atom("Bool"),
+            },
+        ]
+    );
+    assert_eq!(
+        declarations[1].to_string(),
+        "(declare-fun |(declare-fun| () Bool)"
+    );
+}
+
+#[test]
+fn pipe1() {
+    let cfg = z3::Config::new();
+    let ctx = z3::Context::new(&cfg);
+    let x = z3::ast::Int::new_const(&ctx, "x");
+    let (pipe, written, sender) = fake(Limits::default());
+
+    answer(&sender, &["success", "success"]);
+    pipe.assert(&Term::new(&x.gt(&z3::ast::Int::from_i64(&ctx, 3))));
+    answer(&sender, &["success", "success", "sat", "((x", " 4))"]);
+    pipe.push();
+    pipe.assert(&Term::new(&x.lt(&z3::ast::Int::from_i64(&ctx, 5))));
+    assert_eq!(pipe.check(), SatResult::Sat);
+    assert_eq!(pipe.value(&Term::new(&x)), Some(atom("4")));
+
+    answer(
+        &sender,
+        &["success", "unknown", "(:reason-unknown \"incomplete\")"],
+    );
+    pipe.pop();
+    assert_eq!(pipe.check(), SatResult::Unknown);
+    assert_eq!(pipe.reason_unknown(), Some(String::from("incomplete")));
+
+    assert_eq!(
+        written.commands(),
+        "(declare-fun x () Int)
+(assert (> x 3))
+(push 1)
+(assert (< x 5))
+(check-sat)
+(get-value (x))
+(pop 1)
+(check-sat)
+(get-info :reason-unknown)
+"
+    );
+}
+
+#[test]
+fn pipe_timeout1() {
+    let cfg = z3::Config::new();
+    let ctx = z3::Context::new(&cfg);
+    let limits = Limits {
+        timeout: Some(10),
+        ..Default::default()
+    };
+    let (pipe, _written, sender) = fake(limits);
+
+    answer(&sender, &["success", "success"]);
+    pipe.assert(&Term::new(&z3::ast::Bool::new_const(&ctx, "b")));
+    assert_eq!(pipe.check(), SatResult::Unknown);
+    assert_eq!(pipe.reason_unknown(), Some(String::from("timeout")));
+    // Stopped, the next check starts it again with what was asserted
+    assert!(pipe.state.borrow().process.is_none());
+    assert_eq!(
+        pipe.state.borrow().scopes,
+        vec![vec![
+            String::from("(declare-fun b () Bool)"),
+            String::from("(assert b)")
+        ]]
+    );
+}
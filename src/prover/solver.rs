@@ -0,0 +1,227 @@
+use crate::ast::Limits;
+use crate::prover::smtlib::{commands, Sexp};
+use std::cell::RefCell;
+use std::fmt;
+use std::sync::{mpsc, Mutex};
+use std::time::{Duration, Instant};
+use z3::ast::Ast;
+
+/// Checks the queries, the formulas are built with Z3's API but the solvers only see them as terms
+pub trait Solver<'a> {
+    fn assert(&self, b: &Term<'a>);
+    fn push(&self);
+    fn pop(&self);
+    fn check(&self) -> SatResult;
+    /// Why the last check gave up
+    fn reason_unknown(&self) -> Option<String>;
+    /// Value in the model of the last check, if it was satisfiable
+    fn value(&self, t: &Term<'a>) -> Option<Sexp>;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SatResult {
+    Sat,
+    Unsat,
+    Unknown,
+}
+
+/// A formula or a term for the solvers, the external ones get it as SMT-LIB2
+#[derive(Clone, Debug)]
+pub struct Term<'a>(z3::ast::Dynamic<'a>);
+
+impl<'a> Term<'a> {
+    pub fn new(ast: &impl Ast<'a>) -> Self {
+        Term(z3::ast::Dynamic::from_ast(ast))
+    }
+
+    /// SMT-LIB2 text of the term, the constants in it are not declared
+    pub fn text(&self) -> String {
+        self.0.to_string()
+    }
+
+    /// Declarations of the constants and functions in the term
+    pub fn declarations(&self) -> Vec<Declaration> {
+        // Z3 doesn't give the bodies of the quantifiers, so they come from its script instead
+        let t = z3::Solver::new(self.0.get_ctx());
+        t.assert(&self.0._eq(&self.0));
+        commands(&t.to_string())
+            .into_iter()
+            .filter_map(Declaration::new)
+            .collect()
+    }
+
+    /// The declarations and the assertion of the formula, without the check
+    pub fn script(&self) -> String {
+        let mut result = String::new();
+        for d in self.declarations() {
+            result += &format!("{}\n", d);
+        }
+        result + &format!("(assert {})\n", self.text())
+    }
+}
+
+/// Constant or uninterpreted function used by a term
+#[derive(Clone, Debug, PartialEq)]
+pub struct Declaration {
+    pub name: String,
+    /// Sorts of the arguments, none for the constants
+    pub domain: Vec<Sexp>,
+    pub range: Sexp,
+}
+
+impl Declaration {
+    /// Read from declare-fun or declare-const, other commands aren't declarations
+    fn new(command: Sexp) -> Option<Self> {
+        let x = match command {
+            Sexp::List(x) => x,
+            Sexp::Atom(_) => return None,
+        };
+        match x.as_slice() {
+            [Sexp::Atom(c), Sexp::Atom(name), Sexp::List(domain), range] if c == "declare-fun" => {
+                Some(Declaration {
+                    name: name.clone(),
+                    domain: domain.clone(),
+                    range: range.clone(),
+                })
+            }
+            [Sexp::Atom(c), Sexp::Atom(name), range] if c == "declare-const" => Some(Declaration {
+                name: name.clone(),
+                domain: Vec::new(),
+                range: range.clone(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Declaration {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "(declare-fun {} {} {})",
+            self.name,
+            Sexp::List(self.domain.clone()),
+            self.range
+        )
+    }
+}
+
+/// The solver from the z3 crate, running in the same process
+pub struct Z3<'a> {
+    solver: z3::Solver<'a>,
+    limits: Limits,
+    model: RefCell<Option<z3::Model<'a>>>,
+    reason_unknown: RefCell<Option<String>>,
+}
+
+impl<'a> Z3<'a> {
+    /// Solver giving up after the limits
+    pub fn new(ctx: &'a z3::Context, limits: Limits) -> Self {
+        let solver = z3::Solver::new(ctx);
+        let mut params = z3::Params::new(ctx);
+        if let Some(ms) = limits.timeout {
+            params.set_u32("timeout", ms);
+        }
+        if let Some(r) = limits.rlimit {
+            params.set_u32("rlimit", r);
+        }
+        solver.set_params(&params);
+        // Only the incremental kernel stops when it's interrupted, the solver switches to it once pushed
+        if limits.timeout.is_some() {
+            solver.push();
+        }
+        Z3 {
+            solver,
+            limits,
+            model: RefCell::new(None),
+            reason_unknown: RefCell::new(None),
+        }
+    }
+
+    /// Z3 doesn't always stop at its timeout param (e.g. for the nonlinear ints),
+    /// so the check is also interrupted from another thread once it's past the timeout
+    fn check_until_timeout(&self) -> z3::SatResult {
+        let ms = match self.limits.timeout {
+            Some(ms) => ms,
+            None => return self.solver.check(),
+        };
+        let handle = self.solver.get_context().handle();
+        // Not interrupted once it's done, the next check would be canceled right away
+        let running = Mutex::new(true);
+        let (done, wait) = mpsc::channel::<()>();
+        std::thread::scope(|s| {
+            let running = &running;
+            s.spawn(move || {
+                if wait.recv_timeout(Duration::from_millis(ms.into()))
+                    == Err(mpsc::RecvTimeoutError::Timeout)
+                    && *running.lock().unwrap()
+                {
+                    handle.interrupt();
+                }
+            });
+            let result = self.solver.check();
+            *running.lock().unwrap() = false;
+            drop(done);
+            result
+        })
+    }
+
+    /// Once it's been pushed, Z3 doesn't say it ran out of the time or the rlimit
+    /// (it's unknown, canceled or whatever made it incomplete before), so they're told apart by the time the check took
+    fn reason(&self, elapsed: Duration) -> Option<String> {
+        let reason = self.solver.get_reason_unknown()?;
+        let timeout = self
+            .limits
+            .timeout
+            .map_or(false, |ms| elapsed >= Duration::from_millis(ms.into()));
+        match reason.as_str() {
+            _ if timeout => Some(String::from("timeout")),
+            "unknown" | "canceled" if self.limits.rlimit.is_some() => {
+                Some(String::from("max. resource limit exceeded"))
+            }
+            _ => Some(reason),
+        }
+    }
+}
+
+impl<'a> Solver<'a> for Z3<'a> {
+    fn assert(&self, b: &Term<'a>) {
+        self.solver
+            .assert(&b.0.as_bool().expect("Only formulas can be asserted"));
+    }
+
+    fn push(&self) {
+        self.solver.push();
+    }
+
+    fn pop(&self) {
+        self.solver.pop(1);
+    }
+
+    fn check(&self) -> SatResult {
+        let start = Instant::now();
+        let result = self.check_until_timeout();
+        *self.model.borrow_mut() = match result {
+            z3::SatResult::Sat => self.solver.get_model(),
+            _ => None,
+        };
+        *self.reason_unknown.borrow_mut() = match result {
+            z3::SatResult::Unknown => self.reason(start.elapsed()),
+            _ => None,
+        };
+        match result {
+            z3::SatResult::Sat => SatResult::Sat,
+            z3::SatResult::Unsat => SatResult::Unsat,
+            z3::SatResult::Unknown => SatResult::Unknown,
+        }
+    }
+
+    fn reason_unknown(&self) -> Option<String> {
+        self.reason_unknown.borrow().clone()
+    }
+
+    fn value(&self, t: &Term<'a>) -> Option<Sexp> {
+        let value = self.model.borrow().as_ref()?.eval(&t.0)?;
+        commands(&value.to_string()).into_iter().next()
+    }
+}
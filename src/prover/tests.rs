@@ -11,8 +11,13 @@ fn prove_empty1() {
                 output: Type::Unit,
                 precondition: Bool::True,
                 postcondition: Bool::True,
-                return_value: Value::Unit
-            }]
+                return_value: Value::Unit,
+                return_span: Span::default(),
+                span: Span::default(),
+                arith: None,
+                limits: Limits::default(),
+            }],
+            source: None,
         },
         vec![]
     ));
@@ -25,25 +30,36 @@ fn prove_1() {
             content: vec![Function {
                 name: String::from("test"),
                 content: vec![
-                    Command::Binding(Binding::Assignment(
-                        Variable::Named(String::from("x")),
-                        Type::I32,
-                        Value::Expr(Expr::Number(1)),
-                        false
-                    )),
-                    Command::ProveControl(ProveControl::Assert(Bool::Equal(
-                        Expr::Value(Box::new(Value::Variable(Variable::Named(String::from(
-                            "x"
-                        ))))),
-                        Expr::Number(1)
-                    )))
+                    Command::Binding(
+                        Binding::Assignment(
+                            Variable::Named(String::from("x")),
+                            Type::I32,
+                            Value::Expr(Expr::Number(1)),
+                            false
+                        ),
+                        Span::default()
+                    ),
+                    Command::ProveControl(
+                        ProveControl::Assert(Bool::Equal(
+                            Expr::Value(Box::new(Value::Variable(Variable::Named(String::from(
+                                "x"
+                            ))))),
+                            Expr::Number(1)
+                        )),
+                        Span::default()
+                    )
                 ],
                 input: vec![],
                 output: Type::Unit,
                 precondition: Bool::True,
                 postcondition: Bool::True,
-                return_value: Value::Unit
-            }]
+                return_value: Value::Unit,
+                return_span: Span::default(),
+                span: Span::default(),
+                arith: None,
+                limits: Limits::default(),
+            }],
+            source: None,
         },
         vec![]
     ));
@@ -56,37 +72,54 @@ fn prove_2() {
             content: vec![Function {
                 name: String::from("test"),
                 content: vec![
-                    Command::Binding(Binding::Assignment(
-                        Variable::Named(String::from("x")),
-                        Type::I32,
-                        Value::Expr(Expr::Number(1)),
-                        false
-                    )),
-                    Command::ProveControl(ProveControl::Assert(Bool::Equal(
-                        Expr::Value(Box::new(Value::Variable(Variable::Named(String::from(
-                            "x"
-                        ))))),
-                        Expr::Number(1)
-                    ))),
-                    Command::Binding(Binding::Assignment(
-                        Variable::Named(String::from("x")),
-                        Type::I32,
-                        Value::Expr(Expr::Number(2)),
-                        false
-                    )),
-                    Command::ProveControl(ProveControl::Assert(Bool::Equal(
-                        Expr::Value(Box::new(Value::Variable(Variable::Named(String::from(
-                            "x"
-                        ))))),
-                        Expr::Number(2)
-                    )))
+                    Command::Binding(
+                        Binding::Assignment(
+                            Variable::Named(String::from("x")),
+                            Type::I32,
+                            Value::Expr(Expr::Number(1)),
+                            false
+                        ),
+                        Span::default()
+                    ),
+                    Command::ProveControl(
+                        ProveControl::Assert(Bool::Equal(
+                            Expr::Value(Box::new(Value::Variable(Variable::Named(String::from(
+                                "x"
+                            ))))),
+                            Expr::Number(1)
+                        )),
+                        Span::default()
+                    ),
+                    Command::Binding(
+                        Binding::Assignment(
+                            Variable::Named(String::from("x")),
+                            Type::I32,
+                            Value::Expr(Expr::Number(2)),
+                            false
+                        ),
+                        Span::default()
+                    ),
+                    Command::ProveControl(
+                        ProveControl::Assert(Bool::Equal(
+                            Expr::Value(Box::new(Value::Variable(Variable::Named(String::from(
+                                "x"
+                            ))))),
+                            Expr::Number(2)
+                        )),
+                        Span::default()
+                    )
                 ],
                 input: vec![],
                 output: Type::Unit,
                 precondition: Bool::True,
                 postcondition: Bool::True,
-                return_value: Value::Unit
-            }]
+                return_value: Value::Unit,
+                return_span: Span::default(),
+                span: Span::default(),
+                arith: None,
+                limits: Limits::default(),
+            }],
+            source: None,
         },
         vec![]
     ));
@@ -99,67 +132,96 @@ fn prove_3() {
             content: vec![Function {
                 name: String::from("test"),
                 content: vec![
-                    Command::Binding(Binding::Assignment(
-                        Variable::Named(String::from("x")),
-                        Type::I32,
-                        Value::Expr(Expr::Number(1)),
-                        false
-                    )),
-                    Command::ProveControl(ProveControl::Assert(Bool::Equal(
-                        Expr::Value(Box::new(Value::Variable(Variable::Named(String::from(
-                            "x"
-                        ))))),
-                        Expr::Number(1)
-                    ))),
-                    Command::Binding(Binding::Assignment(
-                        Variable::Named(String::from("x")),
-                        Type::I32,
-                        Value::Expr(Expr::Number(2)),
-                        false
-                    )),
-                    Command::ProveControl(ProveControl::Assert(Bool::Equal(
-                        Expr::Value(Box::new(Value::Variable(Variable::Named(String::from(
-                            "x"
-                        ))))),
-                        Expr::Number(2)
-                    ))),
-                    Command::Binding(Binding::Assignment(
-                        Variable::Named(String::from("x")),
-                        Type::I32,
-                        Value::Expr(Expr::Op(
-                            Box::new(Expr::Value(Box::new(Value::Variable(Variable::Named(
-                                String::from("x")
+                    Command::Binding(
+                        Binding::Assignment(
+                            Variable::Named(String::from("x")),
+                            Type::I32,
+                            Value::Expr(Expr::Number(1)),
+                            false
+                        ),
+                        Span::default()
+                    ),
+                    Command::ProveControl(
+                        ProveControl::Assert(Bool::Equal(
+                            Expr::Value(Box::new(Value::Variable(Variable::Named(String::from(
+                                "x"
                             ))))),
-                            Opcode::Add,
-                            Box::new(Expr::Number(3))
+                            Expr::Number(1)
                         )),
-                        false
-                    )),
-                    Command::ProveControl(ProveControl::Assert(Bool::Equal(
-                        Expr::Value(Box::new(Value::Variable(Variable::Named(String::from(
-                            "x"
-                        ))))),
-                        Expr::Number(5)
-                    ))),
-                    Command::Binding(Binding::Assignment(
-                        Variable::Named(String::from("x")),
-                        Type::I32,
-                        Value::Expr(Expr::Number(7)),
-                        false
-                    )),
-                    Command::ProveControl(ProveControl::Assert(Bool::Equal(
-                        Expr::Value(Box::new(Value::Variable(Variable::Named(String::from(
-                            "x"
-                        ))))),
-                        Expr::Number(7)
-                    ))),
+                        Span::default()
+                    ),
+                    Command::Binding(
+                        Binding::Assignment(
+                            Variable::Named(String::from("x")),
+                            Type::I32,
+                            Value::Expr(Expr::Number(2)),
+                            false
+                        ),
+                        Span::default()
+                    ),
+                    Command::ProveControl(
+                        ProveControl::Assert(Bool::Equal(
+                            Expr::Value(Box::new(Value::Variable(Variable::Named(String::from(
+                                "x"
+                            ))))),
+                            Expr::Number(2)
+                        )),
+                        Span::default()
+                    ),
+                    Command::Binding(
+                        Binding::Assignment(
+                            Variable::Named(String::from("x")),
+                            Type::I32,
+                            Value::Expr(Expr::Op(
+                                Box::new(Expr::Value(Box::new(Value::Variable(Variable::Named(
+                                    String::from("x")
+                                ))))),
+                                Opcode::Add,
+                                Box::new(Expr::Number(3))
+                            )),
+                            false
+                        ),
+                        Span::default()
+                    ),
+                    Command::ProveControl(
+                        ProveControl::Assert(Bool::Equal(
+                            Expr::Value(Box::new(Value::Variable(Variable::Named(String::from(
+                                "x"
+                            ))))),
+                            Expr::Number(5)
+                        )),
+                        Span::default()
+                    ),
+                    Command::Binding(
+                        Binding::Assignment(
+                            Variable::Named(String::from("x")),
+                            Type::I32,
+                            Value::Expr(Expr::Number(7)),
+                            false
+                        ),
+                        Span::default()
+                    ),
+                    Command::ProveControl(
+                        ProveControl::Assert(Bool::Equal(
+                            Expr::Value(Box::new(Value::Variable(Variable::Named(String::from(
+                                "x"
+                            ))))),
+                            Expr::Number(7)
+                        )),
+                        Span::default()
+                    ),
                 ],
                 input: vec![],
                 output: Type::Unit,
                 precondition: Bool::True,
                 postcondition: Bool::True,
-                return_value: Value::Unit
-            }]
+                return_value: Value::Unit,
+                return_span: Span::default(),
+                span: Span::default(),
+                arith: None,
+                limits: Limits::default(),
+            }],
+            source: None,
         },
         vec![]
     ));
@@ -172,25 +234,36 @@ fn prove_fail_1() {
             content: vec![Function {
                 name: String::from("test"),
                 content: vec![
-                    Command::Binding(Binding::Assignment(
-                        Variable::Named(String::from("x")),
-                        Type::I32,
-                        Value::Expr(Expr::Number(1)),
-                        false
-                    )),
-                    Command::ProveControl(ProveControl::Assert(Bool::Equal(
-                        Expr::Value(Box::new(Value::Variable(Variable::Named(String::from(
-                            "x"
-                        ))))),
-                        Expr::Number(2)
-                    )))
+                    Command::Binding(
+                        Binding::Assignment(
+                            Variable::Named(String::from("x")),
+                            Type::I32,
+                            Value::Expr(Expr::Number(1)),
+                            false
+                        ),
+                        Span::default()
+                    ),
+                    Command::ProveControl(
+                        ProveControl::Assert(Bool::Equal(
+                            Expr::Value(Box::new(Value::Variable(Variable::Named(String::from(
+                                "x"
+                            ))))),
+                            Expr::Number(2)
+                        )),
+                        Span::default()
+                    )
                 ],
                 input: vec![],
                 output: Type::Unit,
                 precondition: Bool::True,
                 postcondition: Bool::True,
-                return_value: Value::Unit
-            }]
+                return_value: Value::Unit,
+                return_span: Span::default(),
+                span: Span::default(),
+                arith: None,
+                limits: Limits::default(),
+            }],
+            source: None,
         },
         vec![]
     ));
@@ -202,12 +275,15 @@ fn prove_return_value1() {
         Program {
             content: vec![Function {
                 name: String::from("test"),
-                content: vec![Command::Binding(Binding::Assignment(
-                    Variable::Named(String::from("x")),
-                    Type::I32,
-                    Value::Expr(Expr::Number(1)),
-                    false
-                ))],
+                content: vec![Command::Binding(
+                    Binding::Assignment(
+                        Variable::Named(String::from("x")),
+                        Type::I32,
+                        Value::Expr(Expr::Number(1)),
+                        false
+                    ),
+                    Span::default()
+                )],
                 input: vec![],
                 output: Type::I32,
                 precondition: Bool::True,
@@ -217,8 +293,13 @@ fn prove_return_value1() {
                     ))))),
                     Expr::Number(1)
                 ),
-                return_value: Value::Variable(Variable::Named(String::from("x")))
-            }]
+                return_value: Value::Variable(Variable::Named(String::from("x"))),
+                return_span: Span::default(),
+                span: Span::default(),
+                arith: None,
+                limits: Limits::default(),
+            }],
+            source: None,
         },
         vec![]
     ));
@@ -230,12 +311,15 @@ fn prove_return_value_fail1() {
         Program {
             content: vec![Function {
                 name: String::from("test"),
-                content: vec![Command::Binding(Binding::Assignment(
-                    Variable::Named(String::from("x")),
-                    Type::I32,
-                    Value::Expr(Expr::Number(1)),
-                    false
-                ))],
+                content: vec![Command::Binding(
+                    Binding::Assignment(
+                        Variable::Named(String::from("x")),
+                        Type::I32,
+                        Value::Expr(Expr::Number(1)),
+                        false
+                    ),
+                    Span::default()
+                )],
                 input: vec![],
                 output: Type::I32,
                 precondition: Bool::True,
@@ -245,8 +329,13 @@ fn prove_return_value_fail1() {
                     ))))),
                     Expr::Number(2)
                 ),
-                return_value: Value::Variable(Variable::Named(String::from("x")))
-            }]
+                return_value: Value::Variable(Variable::Named(String::from("x"))),
+                return_span: Span::default(),
+                span: Span::default(),
+                arith: None,
+                limits: Limits::default(),
+            }],
+            source: None,
         },
         vec![]
     ));
@@ -258,12 +347,15 @@ fn prove_postcondition1() {
         Program {
             content: vec![Function {
                 name: String::from("test"),
-                content: vec![Command::Binding(Binding::Assignment(
-                    Variable::Named(String::from("x")),
-                    Type::I32,
-                    Value::Expr(Expr::Number(1)),
-                    false
-                ))],
+                content: vec![Command::Binding(
+                    Binding::Assignment(
+                        Variable::Named(String::from("x")),
+                        Type::I32,
+                        Value::Expr(Expr::Number(1)),
+                        false
+                    ),
+                    Span::default()
+                )],
                 input: vec![],
                 output: Type::Unit,
                 precondition: Bool::True,
@@ -273,8 +365,13 @@ fn prove_postcondition1() {
                     ))))),
                     Expr::Number(1)
                 ),
-                return_value: Value::Unit
-            }]
+                return_value: Value::Unit,
+                return_span: Span::default(),
+                span: Span::default(),
+                arith: None,
+                limits: Limits::default(),
+            }],
+            source: None,
         },
         vec![]
     ));
@@ -286,12 +383,15 @@ fn prove_postcondition2() {
         Program {
             content: vec![Function {
                 name: String::from("test"),
-                content: vec![Command::Binding(Binding::Assignment(
-                    Variable::Named(String::from("x")),
-                    Type::I32,
-                    Value::Expr(Expr::Number(3)),
-                    false
-                ))],
+                content: vec![Command::Binding(
+                    Binding::Assignment(
+                        Variable::Named(String::from("x")),
+                        Type::I32,
+                        Value::Expr(Expr::Number(3)),
+                        false
+                    ),
+                    Span::default()
+                )],
                 input: vec![],
                 output: Type::Unit,
                 precondition: Bool::Equal(
@@ -306,8 +406,13 @@ fn prove_postcondition2() {
                     ))))),
                     Expr::Number(3)
                 ),
-                return_value: Value::Unit
-            }]
+                return_value: Value::Unit,
+                return_span: Span::default(),
+                span: Span::default(),
+                arith: None,
+                limits: Limits::default(),
+            }],
+            source: None,
         },
         vec![]
     ));
@@ -319,12 +424,15 @@ fn prove_postcondition_fail1() {
         Program {
             content: vec![Function {
                 name: String::from("test"),
-                content: vec![Command::Binding(Binding::Assignment(
-                    Variable::Named(String::from("x")),
-                    Type::I32,
-                    Value::Expr(Expr::Number(1)),
-                    false
-                ))],
+                content: vec![Command::Binding(
+                    Binding::Assignment(
+                        Variable::Named(String::from("x")),
+                        Type::I32,
+                        Value::Expr(Expr::Number(1)),
+                        false
+                    ),
+                    Span::default()
+                )],
                 input: vec![],
                 output: Type::Unit,
                 precondition: Bool::True,
@@ -334,8 +442,13 @@ fn prove_postcondition_fail1() {
                     ))))),
                     Expr::Number(2)
                 ),
-                return_value: Value::Unit
-            }]
+                return_value: Value::Unit,
+                return_span: Span::default(),
+                span: Span::default(),
+                arith: None,
+                limits: Limits::default(),
+            }],
+            source: None,
         },
         vec![]
     ));
@@ -347,12 +460,15 @@ fn prove_postcondition_fail2() {
         Program {
             content: vec![Function {
                 name: String::from("test"),
-                content: vec![Command::Binding(Binding::Assignment(
-                    Variable::Named(String::from("x")),
-                    Type::I32,
-                    Value::Expr(Expr::Number(2)),
-                    false
-                ))],
+                content: vec![Command::Binding(
+                    Binding::Assignment(
+                        Variable::Named(String::from("x")),
+                        Type::I32,
+                        Value::Expr(Expr::Number(2)),
+                        false
+                    ),
+                    Span::default()
+                )],
                 input: vec![],
                 output: Type::Unit,
                 precondition: Bool::Equal(
@@ -367,8 +483,13 @@ fn prove_postcondition_fail2() {
                     ))))),
                     Expr::Number(3)
                 ),
-                return_value: Value::Unit
-            }]
+                return_value: Value::Unit,
+                return_span: Span::default(),
+                span: Span::default(),
+                arith: None,
+                limits: Limits::default(),
+            }],
+            source: None,
         },
         vec![]
     ));
@@ -395,8 +516,13 @@ fn prove_postcondition_fail3() {
                     ))))),
                     Expr::Number(3)
                 ),
-                return_value: Value::Unit
-            }]
+                return_value: Value::Unit,
+                return_span: Span::default(),
+                span: Span::default(),
+                arith: None,
+                limits: Limits::default(),
+            }],
+            source: None,
         },
         vec![]
     ));
@@ -408,12 +534,15 @@ fn prove_old1() {
         Program {
             content: vec![Function {
                 name: String::from("test"),
-                content: vec![Command::Binding(Binding::Assignment(
-                    Variable::Named(String::from("x")),
-                    Type::I32,
-                    Value::Expr(Expr::Number(3)),
-                    false
-                ))],
+                content: vec![Command::Binding(
+                    Binding::Assignment(
+                        Variable::Named(String::from("x")),
+                        Type::I32,
+                        Value::Expr(Expr::Number(3)),
+                        false
+                    ),
+                    Span::default()
+                )],
                 input: vec![],
                 output: Type::Unit,
                 precondition: Bool::And(
@@ -442,8 +571,13 @@ fn prove_old1() {
                     ),
                     Expr::Number(3)
                 ),
-                return_value: Value::Unit
-            }]
+                return_value: Value::Unit,
+                return_span: Span::default(),
+                span: Span::default(),
+                arith: None,
+                limits: Limits::default(),
+            }],
+            source: None,
         },
         vec![]
     ));
@@ -455,12 +589,15 @@ fn prove_old2() {
         Program {
             content: vec![Function {
                 name: String::from("test"),
-                content: vec![Command::Binding(Binding::Assignment(
-                    Variable::Named(String::from("x")),
-                    Type::I32,
-                    Value::Expr(Expr::Number(3)),
-                    false
-                ))],
+                content: vec![Command::Binding(
+                    Binding::Assignment(
+                        Variable::Named(String::from("x")),
+                        Type::I32,
+                        Value::Expr(Expr::Number(3)),
+                        false
+                    ),
+                    Span::default()
+                )],
                 input: vec![],
                 output: Type::Unit,
                 precondition: Bool::And(
@@ -491,8 +628,13 @@ fn prove_old2() {
                         "x"
                     )))))
                 ),
-                return_value: Value::Unit
-            }]
+                return_value: Value::Unit,
+                return_span: Span::default(),
+                span: Span::default(),
+                arith: None,
+                limits: Limits::default(),
+            }],
+            source: None,
         },
         vec![]
     ));
@@ -504,12 +646,15 @@ fn prove_old_fail1() {
         Program {
             content: vec![Function {
                 name: String::from("test"),
-                content: vec![Command::Binding(Binding::Assignment(
-                    Variable::Named(String::from("x")),
-                    Type::I32,
-                    Value::Expr(Expr::Number(3)),
-                    false
-                ))],
+                content: vec![Command::Binding(
+                    Binding::Assignment(
+                        Variable::Named(String::from("x")),
+                        Type::I32,
+                        Value::Expr(Expr::Number(3)),
+                        false
+                    ),
+                    Span::default()
+                )],
                 input: vec![],
                 output: Type::Unit,
                 precondition: Bool::And(
@@ -538,8 +683,13 @@ fn prove_old_fail1() {
                     ),
                     Expr::Number(4)
                 ),
-                return_value: Value::Unit
-            }]
+                return_value: Value::Unit,
+                return_span: Span::default(),
+                span: Span::default(),
+                arith: None,
+                limits: Limits::default(),
+            }],
+            source: None,
         },
         vec![]
     ));
@@ -551,16 +701,22 @@ fn prove_if1() {
         Program {
             content: vec![Function {
                 name: String::from("test"),
-                content: vec![Command::Block(Block::If(
-                    vec![Bool::True],
-                    vec![vec![Command::Binding(Binding::Assignment(
-                        Variable::Named(String::from("x")),
-                        Type::I32,
-                        Value::Expr(Expr::Number(3)),
-                        false
-                    ))]],
-                    vec![]
-                ))],
+                content: vec![Command::Block(
+                    Block::If(
+                        vec![Bool::True],
+                        vec![vec![Command::Binding(
+                            Binding::Assignment(
+                                Variable::Named(String::from("x")),
+                                Type::I32,
+                                Value::Expr(Expr::Number(3)),
+                                false
+                            ),
+                            Span::default()
+                        )]],
+                        vec![]
+                    ),
+                    Span::default()
+                )],
                 input: vec![],
                 output: Type::Unit,
                 precondition: Bool::And(
@@ -586,8 +742,13 @@ fn prove_if1() {
                     Expr::Number(3)
                 ),
 
-                return_value: Value::Unit
-            }]
+                return_value: Value::Unit,
+                return_span: Span::default(),
+                span: Span::default(),
+                arith: None,
+                limits: Limits::default(),
+            }],
+            source: None,
         },
         vec![]
     ));
@@ -599,21 +760,30 @@ fn prove_if2() {
         Program {
             content: vec![Function {
                 name: String::from("test"),
-                content: vec![Command::Block(Block::If(
-                    vec![Bool::True],
-                    vec![vec![Command::Binding(Binding::Assignment(
-                        Variable::Named(String::from("x")),
-                        Type::I32,
-                        Value::Expr(Expr::Number(3)),
-                        false
-                    ))]],
-                    vec![Command::Binding(Binding::Assignment(
-                        Variable::Named(String::from("x")),
-                        Type::I32,
-                        Value::Expr(Expr::Number(1)),
-                        false
-                    ))]
-                ))],
+                content: vec![Command::Block(
+                    Block::If(
+                        vec![Bool::True],
+                        vec![vec![Command::Binding(
+                            Binding::Assignment(
+                                Variable::Named(String::from("x")),
+                                Type::I32,
+                                Value::Expr(Expr::Number(3)),
+                                false
+                            ),
+                            Span::default()
+                        )]],
+                        vec![Command::Binding(
+                            Binding::Assignment(
+                                Variable::Named(String::from("x")),
+                                Type::I32,
+                                Value::Expr(Expr::Number(1)),
+                                false
+                            ),
+                            Span::default()
+                        )]
+                    ),
+                    Span::default()
+                )],
                 input: vec![],
                 output: Type::Unit,
                 precondition: Bool::And(
@@ -639,8 +809,13 @@ fn prove_if2() {
                     Expr::Number(3)
                 ),
 
-                return_value: Value::Unit
-            }]
+                return_value: Value::Unit,
+                return_span: Span::default(),
+                span: Span::default(),
+                arith: None,
+                limits: Limits::default(),
+            }],
+            source: None,
         },
         vec![]
     ));
@@ -652,21 +827,30 @@ fn prove_if_fail1() {
         Program {
             content: vec![Function {
                 name: String::from("test"),
-                content: vec![Command::Block(Block::If(
-                    vec![Bool::False],
-                    vec![vec![Command::Binding(Binding::Assignment(
-                        Variable::Named(String::from("x")),
-                        Type::I32,
-                        Value::Expr(Expr::Number(3)),
-                        false
-                    ))]],
-                    vec![Command::Binding(Binding::Assignment(
-                        Variable::Named(String::from("x")),
-                        Type::I32,
-                        Value::Expr(Expr::Number(1)),
-                        false
-                    ))]
-                ))],
+                content: vec![Command::Block(
+                    Block::If(
+                        vec![Bool::False],
+                        vec![vec![Command::Binding(
+                            Binding::Assignment(
+                                Variable::Named(String::from("x")),
+                                Type::I32,
+                                Value::Expr(Expr::Number(3)),
+                                false
+                            ),
+                            Span::default()
+                        )]],
+                        vec![Command::Binding(
+                            Binding::Assignment(
+                                Variable::Named(String::from("x")),
+                                Type::I32,
+                                Value::Expr(Expr::Number(1)),
+                                false
+                            ),
+                            Span::default()
+                        )]
+                    ),
+                    Span::default()
+                )],
                 input: vec![],
                 output: Type::Unit,
                 precondition: Bool::And(
@@ -692,8 +876,13 @@ fn prove_if_fail1() {
                     Expr::Number(3)
                 ),
 
-                return_value: Value::Unit
-            }]
+                return_value: Value::Unit,
+                return_span: Span::default(),
+                span: Span::default(),
+                arith: None,
+                limits: Limits::default(),
+            }],
+            source: None,
         },
         vec![]
     ));
@@ -705,30 +894,45 @@ fn prove_if_nested1() {
         Program {
             content: vec![Function {
                 name: String::from("test"),
-                content: vec![Command::Block(Block::If(
-                    vec![Bool::True],
-                    vec![vec![Command::Block(Block::If(
+                content: vec![Command::Block(
+                    Block::If(
                         vec![Bool::True],
-                        vec![vec![Command::Binding(Binding::Assignment(
-                            Variable::Named(String::from("x")),
-                            Type::I32,
-                            Value::Expr(Expr::Number(3)),
-                            false
-                        ))]],
-                        vec![Command::Binding(Binding::Assignment(
-                            Variable::Named(String::from("x")),
-                            Type::I32,
-                            Value::Expr(Expr::Number(1)),
-                            false
-                        ))]
-                    ))]],
-                    vec![Command::Binding(Binding::Assignment(
-                        Variable::Named(String::from("x")),
-                        Type::I32,
-                        Value::Expr(Expr::Number(1)),
-                        false
-                    ))]
-                ))],
+                        vec![vec![Command::Block(
+                            Block::If(
+                                vec![Bool::True],
+                                vec![vec![Command::Binding(
+                                    Binding::Assignment(
+                                        Variable::Named(String::from("x")),
+                                        Type::I32,
+                                        Value::Expr(Expr::Number(3)),
+                                        false
+                                    ),
+                                    Span::default()
+                                )]],
+                                vec![Command::Binding(
+                                    Binding::Assignment(
+                                        Variable::Named(String::from("x")),
+                                        Type::I32,
+                                        Value::Expr(Expr::Number(1)),
+                                        false
+                                    ),
+                                    Span::default()
+                                )]
+                            ),
+                            Span::default()
+                        )]],
+                        vec![Command::Binding(
+                            Binding::Assignment(
+                                Variable::Named(String::from("x")),
+                                Type::I32,
+                                Value::Expr(Expr::Number(1)),
+                                false
+                            ),
+                            Span::default()
+                        )]
+                    ),
+                    Span::default()
+                )],
                 input: vec![],
                 output: Type::Unit,
                 precondition: Bool::And(
@@ -754,8 +958,13 @@ fn prove_if_nested1() {
                     Expr::Number(3)
                 ),
 
-                return_value: Value::Unit
-            }]
+                return_value: Value::Unit,
+                return_span: Span::default(),
+                span: Span::default(),
+                arith: None,
+                limits: Limits::default(),
+            }],
+            source: None,
         },
         vec![]
     ));
@@ -767,30 +976,45 @@ fn prove_if_nested_fail1() {
         Program {
             content: vec![Function {
                 name: String::from("test"),
-                content: vec![Command::Block(Block::If(
-                    vec![Bool::True],
-                    vec![vec![Command::Block(Block::If(
-                        vec![Bool::False],
-                        vec![vec![Command::Binding(Binding::Assignment(
-                            Variable::Named(String::from("x")),
-                            Type::I32,
-                            Value::Expr(Expr::Number(3)),
-                            false
-                        ))]],
-                        vec![Command::Binding(Binding::Assignment(
-                            Variable::Named(String::from("x")),
-                            Type::I32,
-                            Value::Expr(Expr::Number(1)),
-                            false
-                        ))]
-                    ))]],
-                    vec![Command::Binding(Binding::Assignment(
-                        Variable::Named(String::from("x")),
-                        Type::I32,
-                        Value::Expr(Expr::Number(1)),
-                        false
-                    ))]
-                ))],
+                content: vec![Command::Block(
+                    Block::If(
+                        vec![Bool::True],
+                        vec![vec![Command::Block(
+                            Block::If(
+                                vec![Bool::False],
+                                vec![vec![Command::Binding(
+                                    Binding::Assignment(
+                                        Variable::Named(String::from("x")),
+                                        Type::I32,
+                                        Value::Expr(Expr::Number(3)),
+                                        false
+                                    ),
+                                    Span::default()
+                                )]],
+                                vec![Command::Binding(
+                                    Binding::Assignment(
+                                        Variable::Named(String::from("x")),
+                                        Type::I32,
+                                        Value::Expr(Expr::Number(1)),
+                                        false
+                                    ),
+                                    Span::default()
+                                )]
+                            ),
+                            Span::default()
+                        )]],
+                        vec![Command::Binding(
+                            Binding::Assignment(
+                                Variable::Named(String::from("x")),
+                                Type::I32,
+                                Value::Expr(Expr::Number(1)),
+                                false
+                            ),
+                            Span::default()
+                        )]
+                    ),
+                    Span::default()
+                )],
                 input: vec![],
                 output: Type::Unit,
                 precondition: Bool::And(
@@ -816,8 +1040,13 @@ fn prove_if_nested_fail1() {
                     Expr::Number(3)
                 ),
 
-                return_value: Value::Unit
-            }]
+                return_value: Value::Unit,
+                return_span: Span::default(),
+                span: Span::default(),
+                arith: None,
+                limits: Limits::default(),
+            }],
+            source: None,
         },
         vec![]
     ));
@@ -829,35 +1058,50 @@ fn prove_if_multiple1() {
         Program {
             content: vec![Function {
                 name: String::from("test"),
-                content: vec![Command::Block(Block::If(
-                    vec![Bool::False, Bool::False, Bool::True],
-                    vec![
-                        vec![Command::Binding(Binding::Assignment(
-                            Variable::Named(String::from("x")),
-                            Type::I32,
-                            Value::Expr(Expr::Number(5)),
-                            false
-                        ))],
-                        vec![Command::Binding(Binding::Assignment(
-                            Variable::Named(String::from("x")),
-                            Type::I32,
-                            Value::Expr(Expr::Number(6)),
-                            false
-                        ))],
-                        vec![Command::Binding(Binding::Assignment(
-                            Variable::Named(String::from("x")),
-                            Type::I32,
-                            Value::Expr(Expr::Number(3)),
-                            false
-                        ))]
-                    ],
-                    vec![Command::Binding(Binding::Assignment(
-                        Variable::Named(String::from("x")),
-                        Type::I32,
-                        Value::Expr(Expr::Number(9)),
-                        false
-                    ))]
-                ))],
+                content: vec![Command::Block(
+                    Block::If(
+                        vec![Bool::False, Bool::False, Bool::True],
+                        vec![
+                            vec![Command::Binding(
+                                Binding::Assignment(
+                                    Variable::Named(String::from("x")),
+                                    Type::I32,
+                                    Value::Expr(Expr::Number(5)),
+                                    false
+                                ),
+                                Span::default()
+                            )],
+                            vec![Command::Binding(
+                                Binding::Assignment(
+                                    Variable::Named(String::from("x")),
+                                    Type::I32,
+                                    Value::Expr(Expr::Number(6)),
+                                    false
+                                ),
+                                Span::default()
+                            )],
+                            vec![Command::Binding(
+                                Binding::Assignment(
+                                    Variable::Named(String::from("x")),
+                                    Type::I32,
+                                    Value::Expr(Expr::Number(3)),
+                                    false
+                                ),
+                                Span::default()
+                            )]
+                        ],
+                        vec![Command::Binding(
+                            Binding::Assignment(
+                                Variable::Named(String::from("x")),
+                                Type::I32,
+                                Value::Expr(Expr::Number(9)),
+                                false
+                            ),
+                            Span::default()
+                        )]
+                    ),
+                    Span::default()
+                )],
                 input: vec![],
                 output: Type::Unit,
                 precondition: Bool::And(
@@ -883,8 +1127,13 @@ fn prove_if_multiple1() {
                     Expr::Number(3)
                 ),
 
-                return_value: Value::Unit
-            }]
+                return_value: Value::Unit,
+                return_span: Span::default(),
+                span: Span::default(),
+                arith: None,
+                limits: Limits::default(),
+            }],
+            source: None,
         },
         vec![]
     ));
@@ -896,35 +1145,50 @@ fn prove_if_multiple_fail1() {
         Program {
             content: vec![Function {
                 name: String::from("test"),
-                content: vec![Command::Block(Block::If(
-                    vec![Bool::False, Bool::True, Bool::False],
-                    vec![
-                        vec![Command::Binding(Binding::Assignment(
-                            Variable::Named(String::from("x")),
-                            Type::I32,
-                            Value::Expr(Expr::Number(3)),
-                            false
-                        ))],
-                        vec![Command::Binding(Binding::Assignment(
-                            Variable::Named(String::from("x")),
-                            Type::I32,
-                            Value::Expr(Expr::Number(5)),
-                            false
-                        ))],
-                        vec![Command::Binding(Binding::Assignment(
-                            Variable::Named(String::from("x")),
-                            Type::I32,
-                            Value::Expr(Expr::Number(3)),
-                            false
-                        ))]
-                    ],
-                    vec![Command::Binding(Binding::Assignment(
-                        Variable::Named(String::from("x")),
-                        Type::I32,
-                        Value::Expr(Expr::Number(3)),
-                        false
-                    ))]
-                ))],
+                content: vec![Command::Block(
+                    Block::If(
+                        vec![Bool::False, Bool::True, Bool::False],
+                        vec![
+                            vec![Command::Binding(
+                                Binding::Assignment(
+                                    Variable::Named(String::from("x")),
+                                    Type::I32,
+                                    Value::Expr(Expr::Number(3)),
+                                    false
+                                ),
+                                Span::default()
+                            )],
+                            vec![Command::Binding(
+                                Binding::Assignment(
+                                    Variable::Named(String::from("x")),
+                                    Type::I32,
+                                    Value::Expr(Expr::Number(5)),
+                                    false
+                                ),
+                                Span::default()
+                            )],
+                            vec![Command::Binding(
+                                Binding::Assignment(
+                                    Variable::Named(String::from("x")),
+                                    Type::I32,
+                                    Value::Expr(Expr::Number(3)),
+                                    false
+                                ),
+                                Span::default()
+                            )]
+                        ],
+                        vec![Command::Binding(
+                            Binding::Assignment(
+                                Variable::Named(String::from("x")),
+                                Type::I32,
+                                Value::Expr(Expr::Number(3)),
+                                false
+                            ),
+                            Span::default()
+                        )]
+                    ),
+                    Span::default()
+                )],
                 input: vec![],
                 output: Type::Unit,
                 precondition: Bool::And(
@@ -950,8 +1214,13 @@ fn prove_if_multiple_fail1() {
                     Expr::Number(3)
                 ),
 
-                return_value: Value::Unit
-            }]
+                return_value: Value::Unit,
+                return_span: Span::default(),
+                span: Span::default(),
+                arith: None,
+                limits: Limits::default(),
+            }],
+            source: None,
         },
         vec![]
     ));
@@ -973,57 +1242,66 @@ fn prove_while_invariant1() {
         Program {
             content: vec![Function {
                 name: String::from("test"),
-                content: vec![Command::Block(Block::While(
-                    Bool::GreaterEqual(
-                        Expr::Value(Box::new(Value::Variable(Variable::Named(String::from(
-                            "z"
-                        ))))),
-                        Expr::Value(Box::new(Value::Variable(Variable::Named(String::from(
-                            "q"
-                        )))))
-                    ),
-                    vec![
-                        Command::Assignment(Assignment::Single(
-                            Variable::Named(String::from("z")),
-                            Value::Expr(Expr::Op(
+                content: vec![Command::Block(
+                    Block::While(
+                        Bool::GreaterEqual(
+                            Expr::Value(Box::new(Value::Variable(Variable::Named(String::from(
+                                "z"
+                            ))))),
+                            Expr::Value(Box::new(Value::Variable(Variable::Named(String::from(
+                                "q"
+                            )))))
+                        ),
+                        vec![
+                            Command::Assignment(
+                                Assignment::Single(
+                                    Variable::Named(String::from("z")),
+                                    Value::Expr(Expr::Op(
+                                        Box::new(Expr::Value(Box::new(Value::Variable(
+                                            Variable::Named(String::from("z"))
+                                        )))),
+                                        Opcode::Add,
+                                        Box::new(Expr::Number(1))
+                                    ))
+                                ),
+                                Span::default()
+                            ),
+                            Command::Assignment(
+                                Assignment::Single(
+                                    Variable::Named(String::from("q")),
+                                    Value::Expr(Expr::Op(
+                                        Box::new(Expr::Value(Box::new(Value::Variable(
+                                            Variable::Named(String::from("q"))
+                                        )))),
+                                        Opcode::Add,
+                                        Box::new(Expr::Number(2))
+                                    ))
+                                ),
+                                Span::default()
+                            )
+                        ],
+                        Bool::Equal(
+                            Expr::Op(
                                 Box::new(Expr::Value(Box::new(Value::Variable(Variable::Named(
-                                    String::from("z")
+                                    String::from("q")
                                 ))))),
                                 Opcode::Add,
-                                Box::new(Expr::Number(1))
-                            ))
-                        )),
-                        Command::Assignment(Assignment::Single(
-                            Variable::Named(String::from("q")),
-                            Value::Expr(Expr::Op(
                                 Box::new(Expr::Value(Box::new(Value::Variable(Variable::Named(
-                                    String::from("q")
+                                    String::from("r")
+                                )))))
+                            ),
+                            Expr::Op(
+                                Box::new(Expr::Value(Box::new(Value::Variable(Variable::Named(
+                                    String::from("z")
                                 ))))),
-                                Opcode::Add,
+                                Opcode::Mul,
                                 Box::new(Expr::Number(2))
-                            ))
-                        ))
-                    ],
-                    Bool::Equal(
-                        Expr::Op(
-                            Box::new(Expr::Value(Box::new(Value::Variable(Variable::Named(
-                                String::from("q")
-                            ))))),
-                            Opcode::Add,
-                            Box::new(Expr::Value(Box::new(Value::Variable(Variable::Named(
-                                String::from("r")
-                            )))))
+                            )
                         ),
-                        Expr::Op(
-                            Box::new(Expr::Value(Box::new(Value::Variable(Variable::Named(
-                                String::from("z")
-                            ))))),
-                            Opcode::Mul,
-                            Box::new(Expr::Number(2))
-                        )
+                        Expr::Number(0)
                     ),
-                    Expr::Number(0)
-                ))],
+                    Span::default()
+                )],
                 input: vec![],
                 output: Type::Unit,
                 precondition: Bool::And(
@@ -1049,8 +1327,13 @@ fn prove_while_invariant1() {
                     ))
                 ),
                 postcondition: Bool::True,
-                return_value: Value::Unit
-            }]
+                return_value: Value::Unit,
+                return_span: Span::default(),
+                span: Span::default(),
+                arith: None,
+                limits: Limits::default(),
+            }],
+            source: None,
         },
         vec![]
     ));
@@ -1072,57 +1355,66 @@ fn prove_while_invariant_fail_1() {
         Program {
             content: vec![Function {
                 name: String::from("test"),
-                content: vec![Command::Block(Block::While(
-                    Bool::GreaterEqual(
-                        Expr::Value(Box::new(Value::Variable(Variable::Named(String::from(
-                            "z"
-                        ))))),
-                        Expr::Value(Box::new(Value::Variable(Variable::Named(String::from(
-                            "q"
-                        )))))
-                    ),
-                    vec![
-                        Command::Assignment(Assignment::Single(
-                            Variable::Named(String::from("z")),
-                            Value::Expr(Expr::Op(
+                content: vec![Command::Block(
+                    Block::While(
+                        Bool::GreaterEqual(
+                            Expr::Value(Box::new(Value::Variable(Variable::Named(String::from(
+                                "z"
+                            ))))),
+                            Expr::Value(Box::new(Value::Variable(Variable::Named(String::from(
+                                "q"
+                            )))))
+                        ),
+                        vec![
+                            Command::Assignment(
+                                Assignment::Single(
+                                    Variable::Named(String::from("z")),
+                                    Value::Expr(Expr::Op(
+                                        Box::new(Expr::Value(Box::new(Value::Variable(
+                                            Variable::Named(String::from("z"))
+                                        )))),
+                                        Opcode::Add,
+                                        Box::new(Expr::Number(1))
+                                    ))
+                                ),
+                                Span::default()
+                            ),
+                            Command::Assignment(
+                                Assignment::Single(
+                                    Variable::Named(String::from("q")),
+                                    Value::Expr(Expr::Op(
+                                        Box::new(Expr::Value(Box::new(Value::Variable(
+                                            Variable::Named(String::from("q"))
+                                        )))),
+                                        Opcode::Add,
+                                        Box::new(Expr::Number(3))
+                                    ))
+                                ),
+                                Span::default()
+                            )
+                        ],
+                        Bool::Equal(
+                            Expr::Op(
                                 Box::new(Expr::Value(Box::new(Value::Variable(Variable::Named(
-                                    String::from("z")
+                                    String::from("q")
                                 ))))),
                                 Opcode::Add,
-                                Box::new(Expr::Number(1))
-                            ))
-                        )),
-                        Command::Assignment(Assignment::Single(
-                            Variable::Named(String::from("q")),
-                            Value::Expr(Expr::Op(
                                 Box::new(Expr::Value(Box::new(Value::Variable(Variable::Named(
-                                    String::from("q")
+                                    String::from("r")
+                                )))))
+                            ),
+                            Expr::Op(
+                                Box::new(Expr::Value(Box::new(Value::Variable(Variable::Named(
+                                    String::from("z")
                                 ))))),
-                                Opcode::Add,
-                                Box::new(Expr::Number(3))
-                            ))
-                        ))
-                    ],
-                    Bool::Equal(
-                        Expr::Op(
-                            Box::new(Expr::Value(Box::new(Value::Variable(Variable::Named(
-                                String::from("q")
-                            ))))),
-                            Opcode::Add,
-                            Box::new(Expr::Value(Box::new(Value::Variable(Variable::Named(
-                                String::from("r")
-                            )))))
+                                Opcode::Mul,
+                                Box::new(Expr::Number(2))
+                            )
                         ),
-                        Expr::Op(
-                            Box::new(Expr::Value(Box::new(Value::Variable(Variable::Named(
-                                String::from("z")
-                            ))))),
-                            Opcode::Mul,
-                            Box::new(Expr::Number(2))
-                        )
+                        Expr::Number(0)
                     ),
-                    Expr::Number(0)
-                ))],
+                    Span::default()
+                )],
                 input: vec![],
                 output: Type::Unit,
                 precondition: Bool::And(
@@ -1148,8 +1440,13 @@ fn prove_while_invariant_fail_1() {
                     ))
                 ),
                 postcondition: Bool::True,
-                return_value: Value::Unit
-            }]
+                return_value: Value::Unit,
+                return_span: Span::default(),
+                span: Span::default(),
+                arith: None,
+                limits: Limits::default(),
+            }],
+            source: None,
         },
         vec![]
     ));
@@ -1170,46 +1467,60 @@ fn prove_while_variant1() {
             content: vec![Function {
                 name: String::from("test"),
                 content: vec![
-                    Command::Binding(Binding::Assignment(
-                        Variable::Named(String::from("i")),
-                        Type::I32,
-                        Value::Expr(Expr::Number(10)),
-                        true,
-                    )),
-                    Command::Block(Block::While(
-                        Bool::GreaterThan(
-                            Expr::Value(Box::new(Value::Variable(Variable::Named(String::from(
-                                "i"
-                            ))))),
-                            Expr::Number(0)
-                        ),
-                        vec![Command::Assignment(Assignment::Single(
+                    Command::Binding(
+                        Binding::Assignment(
                             Variable::Named(String::from("i")),
-                            Value::Expr(Expr::Op(
-                                Box::new(Expr::Value(Box::new(Value::Variable(Variable::Named(
+                            Type::I32,
+                            Value::Expr(Expr::Number(10)),
+                            true,
+                        ),
+                        Span::default()
+                    ),
+                    Command::Block(
+                        Block::While(
+                            Bool::GreaterThan(
+                                Expr::Value(Box::new(Value::Variable(Variable::Named(
                                     String::from("i")
-                                ))))),
-                                Opcode::Sub,
-                                Box::new(Expr::Number(1))
-                            ))
-                        )),],
-                        Bool::GreaterEqual(
+                                )))),
+                                Expr::Number(0)
+                            ),
+                            vec![Command::Assignment(
+                                Assignment::Single(
+                                    Variable::Named(String::from("i")),
+                                    Value::Expr(Expr::Op(
+                                        Box::new(Expr::Value(Box::new(Value::Variable(
+                                            Variable::Named(String::from("i"))
+                                        )))),
+                                        Opcode::Sub,
+                                        Box::new(Expr::Number(1))
+                                    ))
+                                ),
+                                Span::default()
+                            ),],
+                            Bool::GreaterEqual(
+                                Expr::Value(Box::new(Value::Variable(Variable::Named(
+                                    String::from("i")
+                                )))),
+                                Expr::Number(0)
+                            ),
                             Expr::Value(Box::new(Value::Variable(Variable::Named(String::from(
                                 "i"
-                            ))))),
-                            Expr::Number(0)
+                            )))))
                         ),
-                        Expr::Value(Box::new(Value::Variable(Variable::Named(String::from(
-                            "i"
-                        )))))
-                    ))
+                        Span::default()
+                    )
                 ],
                 input: vec![],
                 output: Type::Unit,
                 precondition: Bool::True,
                 postcondition: Bool::True,
-                return_value: Value::Unit
-            }]
+                return_value: Value::Unit,
+                return_span: Span::default(),
+                span: Span::default(),
+                arith: None,
+                limits: Limits::default(),
+            }],
+            source: None,
         },
         vec![]
     ));
@@ -1230,41 +1541,55 @@ fn prove_while_variant_fail_1() {
             content: vec![Function {
                 name: String::from("test"),
                 content: vec![
-                    Command::Binding(Binding::Assignment(
-                        Variable::Named(String::from("i")),
-                        Type::I32,
-                        Value::Expr(Expr::Number(10)),
-                        true,
-                    )),
-                    Command::Block(Block::While(
-                        Bool::GreaterThan(
-                            Expr::Value(Box::new(Value::Variable(Variable::Named(String::from(
-                                "i"
-                            ))))),
-                            Expr::Number(0)
-                        ),
-                        vec![Command::Assignment(Assignment::Single(
+                    Command::Binding(
+                        Binding::Assignment(
                             Variable::Named(String::from("i")),
-                            Value::Expr(Expr::Op(
-                                Box::new(Expr::Value(Box::new(Value::Variable(Variable::Named(
+                            Type::I32,
+                            Value::Expr(Expr::Number(10)),
+                            true,
+                        ),
+                        Span::default()
+                    ),
+                    Command::Block(
+                        Block::While(
+                            Bool::GreaterThan(
+                                Expr::Value(Box::new(Value::Variable(Variable::Named(
                                     String::from("i")
-                                ))))),
-                                Opcode::Sub,
-                                Box::new(Expr::Number(1))
-                            ))
-                        )),],
-                        Bool::True,
-                        Expr::Value(Box::new(Value::Variable(Variable::Named(String::from(
-                            "a"
-                        )))))
-                    ))
+                                )))),
+                                Expr::Number(0)
+                            ),
+                            vec![Command::Assignment(
+                                Assignment::Single(
+                                    Variable::Named(String::from("i")),
+                                    Value::Expr(Expr::Op(
+                                        Box::new(Expr::Value(Box::new(Value::Variable(
+                                            Variable::Named(String::from("i"))
+                                        )))),
+                                        Opcode::Sub,
+                                        Box::new(Expr::Number(1))
+                                    ))
+                                ),
+                                Span::default()
+                            ),],
+                            Bool::True,
+                            Expr::Value(Box::new(Value::Variable(Variable::Named(String::from(
+                                "a"
+                            )))))
+                        ),
+                        Span::default()
+                    )
                 ],
                 input: vec![],
                 output: Type::Unit,
                 precondition: Bool::True,
                 postcondition: Bool::True,
-                return_value: Value::Unit
-            }]
+                return_value: Value::Unit,
+                return_span: Span::default(),
+                span: Span::default(),
+                arith: None,
+                limits: Limits::default(),
+            }],
+            source: None,
         },
         vec![]
     ));
@@ -1291,69 +1616,84 @@ fn prove_while_postcondition1() {
             content: vec![Function {
                 name: String::from("test"),
                 content: vec![
-                    Command::Assignment(Assignment::Single(
-                        Variable::Named(String::from("q")),
-                        Value::Expr(Expr::Number(0))
-                    )),
-                    Command::Assignment(Assignment::Single(
-                        Variable::Named(String::from("r")),
-                        Value::Variable(Variable::Named(String::from("x")))
-                    )),
-                    Command::Block(Block::While(
-                        Bool::GreaterEqual(
-                            Expr::Value(Box::new(Value::Variable(Variable::Named(String::from(
-                                "r"
-                            ))))),
-                            Expr::Value(Box::new(Value::Variable(Variable::Named(String::from(
-                                "y"
-                            )))))
+                    Command::Assignment(
+                        Assignment::Single(
+                            Variable::Named(String::from("q")),
+                            Value::Expr(Expr::Number(0))
                         ),
-                        vec![
-                            Command::Assignment(Assignment::Single(
-                                Variable::Named(String::from("r")),
-                                Value::Expr(Expr::Op(
-                                    Box::new(Expr::Value(Box::new(Value::Variable(
-                                        Variable::Named(String::from("r"))
-                                    )))),
-                                    Opcode::Sub,
-                                    Box::new(Expr::Value(Box::new(Value::Variable(
-                                        Variable::Named(String::from("y"))
-                                    )))),
-                                ))
-                            )),
-                            Command::Assignment(Assignment::Single(
-                                Variable::Named(String::from("q")),
-                                Value::Expr(Expr::Op(
-                                    Box::new(Expr::Value(Box::new(Value::Variable(
-                                        Variable::Named(String::from("q"))
-                                    )))),
+                        Span::default()
+                    ),
+                    Command::Assignment(
+                        Assignment::Single(
+                            Variable::Named(String::from("r")),
+                            Value::Variable(Variable::Named(String::from("x")))
+                        ),
+                        Span::default()
+                    ),
+                    Command::Block(
+                        Block::While(
+                            Bool::GreaterEqual(
+                                Expr::Value(Box::new(Value::Variable(Variable::Named(
+                                    String::from("r")
+                                )))),
+                                Expr::Value(Box::new(Value::Variable(Variable::Named(
+                                    String::from("y")
+                                ))))
+                            ),
+                            vec![
+                                Command::Assignment(
+                                    Assignment::Single(
+                                        Variable::Named(String::from("r")),
+                                        Value::Expr(Expr::Op(
+                                            Box::new(Expr::Value(Box::new(Value::Variable(
+                                                Variable::Named(String::from("r"))
+                                            )))),
+                                            Opcode::Sub,
+                                            Box::new(Expr::Value(Box::new(Value::Variable(
+                                                Variable::Named(String::from("y"))
+                                            )))),
+                                        ))
+                                    ),
+                                    Span::default()
+                                ),
+                                Command::Assignment(
+                                    Assignment::Single(
+                                        Variable::Named(String::from("q")),
+                                        Value::Expr(Expr::Op(
+                                            Box::new(Expr::Value(Box::new(Value::Variable(
+                                                Variable::Named(String::from("q"))
+                                            )))),
+                                            Opcode::Add,
+                                            Box::new(Expr::Number(1))
+                                        ))
+                                    ),
+                                    Span::default()
+                                ),
+                            ],
+                            Bool::Equal(
+                                Expr::Op(
+                                    Box::new(Expr::Op(
+                                        Box::new(Expr::Value(Box::new(Value::Variable(
+                                            Variable::Named(String::from("q"))
+                                        )))),
+                                        Opcode::Mul,
+                                        Box::new(Expr::Value(Box::new(Value::Variable(
+                                            Variable::Named(String::from("y"))
+                                        ))))
+                                    )),
                                     Opcode::Add,
-                                    Box::new(Expr::Number(1))
-                                ))
-                            )),
-                        ],
-                        Bool::Equal(
-                            Expr::Op(
-                                Box::new(Expr::Op(
-                                    Box::new(Expr::Value(Box::new(Value::Variable(
-                                        Variable::Named(String::from("q"))
-                                    )))),
-                                    Opcode::Mul,
                                     Box::new(Expr::Value(Box::new(Value::Variable(
-                                        Variable::Named(String::from("y"))
+                                        Variable::Named(String::from("r"))
                                     ))))
-                                )),
-                                Opcode::Add,
-                                Box::new(Expr::Value(Box::new(Value::Variable(Variable::Named(
-                                    String::from("r")
-                                )))))
+                                ),
+                                Expr::Value(Box::new(Value::Variable(Variable::Named(
+                                    String::from("x")
+                                ))))
                             ),
-                            Expr::Value(Box::new(Value::Variable(Variable::Named(String::from(
-                                "x"
-                            )))))
+                            Expr::Number(0)
                         ),
-                        Expr::Number(0)
-                    ))
+                        Span::default()
+                    )
                 ],
                 input: vec![],
                 output: Type::Unit,
@@ -1391,8 +1731,13 @@ fn prove_while_postcondition1() {
                         "x"
                     )))))
                 ),
-                return_value: Value::Unit
-            }]
+                return_value: Value::Unit,
+                return_span: Span::default(),
+                span: Span::default(),
+                arith: None,
+                limits: Limits::default(),
+            }],
+            source: None,
         },
         vec![]
     ));
@@ -1419,69 +1764,84 @@ fn prove_while_postcondition_fail1() {
             content: vec![Function {
                 name: String::from("test"),
                 content: vec![
-                    Command::Assignment(Assignment::Single(
-                        Variable::Named(String::from("q")),
-                        Value::Expr(Expr::Number(0))
-                    )),
-                    Command::Assignment(Assignment::Single(
-                        Variable::Named(String::from("r")),
-                        Value::Variable(Variable::Named(String::from("x")))
-                    )),
-                    Command::Block(Block::While(
-                        Bool::GreaterEqual(
-                            Expr::Value(Box::new(Value::Variable(Variable::Named(String::from(
-                                "r"
-                            ))))),
-                            Expr::Value(Box::new(Value::Variable(Variable::Named(String::from(
-                                "y"
-                            )))))
+                    Command::Assignment(
+                        Assignment::Single(
+                            Variable::Named(String::from("q")),
+                            Value::Expr(Expr::Number(0))
                         ),
-                        vec![
-                            Command::Assignment(Assignment::Single(
-                                Variable::Named(String::from("r")),
-                                Value::Expr(Expr::Op(
-                                    Box::new(Expr::Value(Box::new(Value::Variable(
-                                        Variable::Named(String::from("r"))
-                                    )))),
-                                    Opcode::Sub,
-                                    Box::new(Expr::Value(Box::new(Value::Variable(
-                                        Variable::Named(String::from("y"))
-                                    )))),
-                                ))
-                            )),
-                            Command::Assignment(Assignment::Single(
-                                Variable::Named(String::from("q")),
-                                Value::Expr(Expr::Op(
-                                    Box::new(Expr::Value(Box::new(Value::Variable(
-                                        Variable::Named(String::from("q"))
-                                    )))),
+                        Span::default()
+                    ),
+                    Command::Assignment(
+                        Assignment::Single(
+                            Variable::Named(String::from("r")),
+                            Value::Variable(Variable::Named(String::from("x")))
+                        ),
+                        Span::default()
+                    ),
+                    Command::Block(
+                        Block::While(
+                            Bool::GreaterEqual(
+                                Expr::Value(Box::new(Value::Variable(Variable::Named(
+                                    String::from("r")
+                                )))),
+                                Expr::Value(Box::new(Value::Variable(Variable::Named(
+                                    String::from("y")
+                                ))))
+                            ),
+                            vec![
+                                Command::Assignment(
+                                    Assignment::Single(
+                                        Variable::Named(String::from("r")),
+                                        Value::Expr(Expr::Op(
+                                            Box::new(Expr::Value(Box::new(Value::Variable(
+                                                Variable::Named(String::from("r"))
+                                            )))),
+                                            Opcode::Sub,
+                                            Box::new(Expr::Value(Box::new(Value::Variable(
+                                                Variable::Named(String::from("y"))
+                                            )))),
+                                        ))
+                                    ),
+                                    Span::default()
+                                ),
+                                Command::Assignment(
+                                    Assignment::Single(
+                                        Variable::Named(String::from("q")),
+                                        Value::Expr(Expr::Op(
+                                            Box::new(Expr::Value(Box::new(Value::Variable(
+                                                Variable::Named(String::from("q"))
+                                            )))),
+                                            Opcode::Add,
+                                            Box::new(Expr::Number(1))
+                                        ))
+                                    ),
+                                    Span::default()
+                                ),
+                            ],
+                            Bool::Equal(
+                                Expr::Op(
+                                    Box::new(Expr::Op(
+                                        Box::new(Expr::Value(Box::new(Value::Variable(
+                                            Variable::Named(String::from("q"))
+                                        )))),
+                                        Opcode::Mul,
+                                        Box::new(Expr::Value(Box::new(Value::Variable(
+                                            Variable::Named(String::from("y"))
+                                        ))))
+                                    )),
                                     Opcode::Add,
-                                    Box::new(Expr::Number(1))
-                                ))
-                            )),
-                        ],
-                        Bool::Equal(
-                            Expr::Op(
-                                Box::new(Expr::Op(
-                                    Box::new(Expr::Value(Box::new(Value::Variable(
-                                        Variable::Named(String::from("q"))
-                                    )))),
-                                    Opcode::Mul,
                                     Box::new(Expr::Value(Box::new(Value::Variable(
-                                        Variable::Named(String::from("y"))
+                                        Variable::Named(String::from("r"))
                                     ))))
-                                )),
-                                Opcode::Add,
-                                Box::new(Expr::Value(Box::new(Value::Variable(Variable::Named(
-                                    String::from("r")
-                                )))))
+                                ),
+                                Expr::Value(Box::new(Value::Variable(Variable::Named(
+                                    String::from("x")
+                                ))))
                             ),
-                            Expr::Value(Box::new(Value::Variable(Variable::Named(String::from(
-                                "x"
-                            )))))
+                            Expr::Number(0)
                         ),
-                        Expr::Number(0)
-                    ))
+                        Span::default()
+                    )
                 ],
                 input: vec![],
                 output: Type::Unit,
@@ -1523,8 +1883,13 @@ fn prove_while_postcondition_fail1() {
                         Box::new(Expr::Number(1))
                     )
                 ),
-                return_value: Value::Unit
-            }]
+                return_value: Value::Unit,
+                return_span: Span::default(),
+                span: Span::default(),
+                arith: None,
+                limits: Limits::default(),
+            }],
+            source: None,
         },
         vec![]
     ));
@@ -1536,15 +1901,26 @@ fn prove_array_elem_dummy1() {
         Program {
             content: vec![Function {
                 name: String::from("test"),
-                content: vec![Command::Binding(Binding::Assignment(
-                    Variable::ArrayElem(String::from("x"), Box::new(Value::Expr(Expr::Number(0)))),
-                    Type::I32,
-                    Value::Expr(Expr::Number(1)),
-                    false
-                ))],
+                content: vec![Command::Binding(
+                    Binding::Assignment(
+                        Variable::ArrayElem(
+                            String::from("x"),
+                            Box::new(Value::Expr(Expr::Number(0)))
+                        ),
+                        Type::I32,
+                        Value::Expr(Expr::Number(1)),
+                        false
+                    ),
+                    Span::default()
+                )],
                 input: vec![],
                 output: Type::Unit,
-                precondition: Bool::True,
+                precondition: Bool::GreaterThan(
+                    Expr::Value(Box::new(Value::Variable(Variable::Length(String::from(
+                        "x"
+                    ))))),
+                    Expr::Number(0)
+                ),
                 postcondition: Bool::Equal(
                     Expr::Value(Box::new(Value::Variable(Variable::ArrayElem(
                         String::from("x"),
@@ -1552,8 +1928,13 @@ fn prove_array_elem_dummy1() {
                     )))),
                     Expr::Number(1)
                 ),
-                return_value: Value::Unit
-            }]
+                return_value: Value::Unit,
+                return_span: Span::default(),
+                span: Span::default(),
+                arith: None,
+                limits: Limits::default(),
+            }],
+            source: None,
         },
         vec![]
     ));
@@ -1565,15 +1946,26 @@ fn prove_array_elem_dummy_fail1() {
         Program {
             content: vec![Function {
                 name: String::from("test"),
-                content: vec![Command::Binding(Binding::Assignment(
-                    Variable::ArrayElem(String::from("x"), Box::new(Value::Expr(Expr::Number(0)))),
-                    Type::I32,
-                    Value::Expr(Expr::Number(1)),
-                    false
-                ))],
+                content: vec![Command::Binding(
+                    Binding::Assignment(
+                        Variable::ArrayElem(
+                            String::from("x"),
+                            Box::new(Value::Expr(Expr::Number(0)))
+                        ),
+                        Type::I32,
+                        Value::Expr(Expr::Number(1)),
+                        false
+                    ),
+                    Span::default()
+                )],
                 input: vec![],
                 output: Type::Unit,
-                precondition: Bool::True,
+                precondition: Bool::GreaterThan(
+                    Expr::Value(Box::new(Value::Variable(Variable::Length(String::from(
+                        "x"
+                    ))))),
+                    Expr::Number(0)
+                ),
                 postcondition: Bool::Equal(
                     Expr::Value(Box::new(Value::Variable(Variable::ArrayElem(
                         String::from("x"),
@@ -1581,8 +1973,13 @@ fn prove_array_elem_dummy_fail1() {
                     )))),
                     Expr::Number(2)
                 ),
-                return_value: Value::Unit
-            }]
+                return_value: Value::Unit,
+                return_span: Span::default(),
+                span: Span::default(),
+                arith: None,
+                limits: Limits::default(),
+            }],
+            source: None,
         },
         vec![]
     ));
@@ -1600,19 +1997,33 @@ fn prove_array_elem_var1() {
         Program {
             content: vec![Function {
                 name: String::from("test"),
-                content: vec![Command::Binding(Binding::Assignment(
-                    Variable::ArrayElem(String::from("x"), Box::new(Value::Expr(Expr::Number(0)))),
-                    Type::I32,
-                    Value::Expr(Expr::Number(1)),
-                    false
-                ))],
+                content: vec![Command::Binding(
+                    Binding::Assignment(
+                        Variable::ArrayElem(
+                            String::from("x"),
+                            Box::new(Value::Expr(Expr::Number(0)))
+                        ),
+                        Type::I32,
+                        Value::Expr(Expr::Number(1)),
+                        false
+                    ),
+                    Span::default()
+                )],
                 input: vec![],
                 output: Type::Unit,
-                precondition: Bool::Equal(
-                    Expr::Value(Box::new(Value::Variable(Variable::Named(String::from(
-                        "y"
-                    ))))),
-                    Expr::Number(0)
+                precondition: Bool::And(
+                    Box::new(Bool::Equal(
+                        Expr::Value(Box::new(Value::Variable(Variable::Named(String::from(
+                            "y"
+                        ))))),
+                        Expr::Number(0)
+                    )),
+                    Box::new(Bool::GreaterThan(
+                        Expr::Value(Box::new(Value::Variable(Variable::Length(String::from(
+                            "x"
+                        ))))),
+                        Expr::Number(0)
+                    ))
                 ),
 
                 postcondition: Bool::Equal(
@@ -1622,8 +2033,13 @@ fn prove_array_elem_var1() {
                     )))),
                     Expr::Number(1)
                 ),
-                return_value: Value::Unit
-            }]
+                return_value: Value::Unit,
+                return_span: Span::default(),
+                span: Span::default(),
+                arith: None,
+                limits: Limits::default(),
+            }],
+            source: None,
         },
         vec![]
     ));
@@ -1640,19 +2056,33 @@ fn prove_array_elem_var_fail1() {
         Program {
             content: vec![Function {
                 name: String::from("test"),
-                content: vec![Command::Binding(Binding::Assignment(
-                    Variable::ArrayElem(String::from("x"), Box::new(Value::Expr(Expr::Number(0)))),
-                    Type::I32,
-                    Value::Expr(Expr::Number(2)),
-                    false
-                ))],
+                content: vec![Command::Binding(
+                    Binding::Assignment(
+                        Variable::ArrayElem(
+                            String::from("x"),
+                            Box::new(Value::Expr(Expr::Number(0)))
+                        ),
+                        Type::I32,
+                        Value::Expr(Expr::Number(2)),
+                        false
+                    ),
+                    Span::default()
+                )],
                 input: vec![],
                 output: Type::Unit,
-                precondition: Bool::Equal(
-                    Expr::Value(Box::new(Value::Variable(Variable::Named(String::from(
-                        "y"
-                    ))))),
-                    Expr::Number(0)
+                precondition: Bool::And(
+                    Box::new(Bool::Equal(
+                        Expr::Value(Box::new(Value::Variable(Variable::Named(String::from(
+                            "y"
+                        ))))),
+                        Expr::Number(0)
+                    )),
+                    Box::new(Bool::GreaterThan(
+                        Expr::Value(Box::new(Value::Variable(Variable::Length(String::from(
+                            "x"
+                        ))))),
+                        Expr::Number(0)
+                    ))
                 ),
 
                 postcondition: Bool::Equal(
@@ -1662,8 +2092,13 @@ fn prove_array_elem_var_fail1() {
                     )))),
                     Expr::Number(1)
                 ),
-                return_value: Value::Unit
-            }]
+                return_value: Value::Unit,
+                return_span: Span::default(),
+                span: Span::default(),
+                arith: None,
+                limits: Limits::default(),
+            }],
+            source: None,
         },
         vec![]
     ));
@@ -1675,12 +2110,15 @@ fn prove_array_dummy1() {
         Program {
             content: vec![Function {
                 name: String::from("test"),
-                content: vec![Command::Binding(Binding::Assignment(
-                    Variable::Named(String::from("x")),
-                    Type::Array(Box::new(Type::I32), 1),
-                    Value::Array(vec![Value::Expr(Expr::Number(1))]),
-                    false
-                ))],
+                content: vec![Command::Binding(
+                    Binding::Assignment(
+                        Variable::Named(String::from("x")),
+                        Type::Array(Box::new(Type::I32), 1),
+                        Value::Array(vec![Value::Expr(Expr::Number(1))]),
+                        false
+                    ),
+                    Span::default()
+                )],
                 input: vec![],
                 output: Type::Unit,
                 precondition: Bool::True,
@@ -1691,8 +2129,13 @@ fn prove_array_dummy1() {
                     )))),
                     Expr::Number(1)
                 ),
-                return_value: Value::Unit
-            }]
+                return_value: Value::Unit,
+                return_span: Span::default(),
+                span: Span::default(),
+                arith: None,
+                limits: Limits::default(),
+            }],
+            source: None,
         },
         vec![]
     ));
@@ -1704,12 +2147,15 @@ fn prove_array_dummy_fail1() {
         Program {
             content: vec![Function {
                 name: String::from("test"),
-                content: vec![Command::Binding(Binding::Assignment(
-                    Variable::Named(String::from("x")),
-                    Type::Array(Box::new(Type::I32), 1),
-                    Value::Array(vec![Value::Expr(Expr::Number(2))]),
-                    false
-                ))],
+                content: vec![Command::Binding(
+                    Binding::Assignment(
+                        Variable::Named(String::from("x")),
+                        Type::Array(Box::new(Type::I32), 1),
+                        Value::Array(vec![Value::Expr(Expr::Number(2))]),
+                        false
+                    ),
+                    Span::default()
+                )],
                 input: vec![],
                 output: Type::Unit,
                 precondition: Bool::True,
@@ -1720,8 +2166,13 @@ fn prove_array_dummy_fail1() {
                     )))),
                     Expr::Number(1)
                 ),
-                return_value: Value::Unit
-            }]
+                return_value: Value::Unit,
+                return_span: Span::default(),
+                span: Span::default(),
+                arith: None,
+                limits: Limits::default(),
+            }],
+            source: None,
         },
         vec![]
     ));
@@ -1734,18 +2185,24 @@ fn prove_array_dummy_variable_index1() {
             content: vec![Function {
                 name: String::from("test"),
                 content: vec![
-                    Command::Binding(Binding::Assignment(
-                        Variable::Named(String::from("y")),
-                        Type::I32,
-                        Value::Expr(Expr::Number(0)),
-                        false
-                    )),
-                    Command::Binding(Binding::Assignment(
-                        Variable::Named(String::from("x")),
-                        Type::Array(Box::new(Type::I32), 1),
-                        Value::Array(vec![Value::Expr(Expr::Number(1))]),
-                        false
-                    ))
+                    Command::Binding(
+                        Binding::Assignment(
+                            Variable::Named(String::from("y")),
+                            Type::I32,
+                            Value::Expr(Expr::Number(0)),
+                            false
+                        ),
+                        Span::default()
+                    ),
+                    Command::Binding(
+                        Binding::Assignment(
+                            Variable::Named(String::from("x")),
+                            Type::Array(Box::new(Type::I32), 1),
+                            Value::Array(vec![Value::Expr(Expr::Number(1))]),
+                            false
+                        ),
+                        Span::default()
+                    )
                 ],
                 input: vec![],
                 output: Type::Unit,
@@ -1757,8 +2214,13 @@ fn prove_array_dummy_variable_index1() {
                     )))),
                     Expr::Number(1)
                 ),
-                return_value: Value::Unit
-            }]
+                return_value: Value::Unit,
+                return_span: Span::default(),
+                span: Span::default(),
+                arith: None,
+                limits: Limits::default(),
+            }],
+            source: None,
         },
         vec![]
     ));
@@ -1771,18 +2233,24 @@ fn prove_array_dummy_variable_index_fail1() {
             content: vec![Function {
                 name: String::from("test"),
                 content: vec![
-                    Command::Binding(Binding::Assignment(
-                        Variable::Named(String::from("y")),
-                        Type::I32,
-                        Value::Expr(Expr::Number(1)),
-                        false
-                    )),
-                    Command::Binding(Binding::Assignment(
-                        Variable::Named(String::from("x")),
-                        Type::Array(Box::new(Type::I32), 1),
-                        Value::Array(vec![Value::Expr(Expr::Number(1))]),
-                        false
-                    ))
+                    Command::Binding(
+                        Binding::Assignment(
+                            Variable::Named(String::from("y")),
+                            Type::I32,
+                            Value::Expr(Expr::Number(1)),
+                            false
+                        ),
+                        Span::default()
+                    ),
+                    Command::Binding(
+                        Binding::Assignment(
+                            Variable::Named(String::from("x")),
+                            Type::Array(Box::new(Type::I32), 1),
+                            Value::Array(vec![Value::Expr(Expr::Number(1))]),
+                            false
+                        ),
+                        Span::default()
+                    )
                 ],
                 input: vec![],
                 output: Type::Unit,
@@ -1794,9 +2262,427 @@ fn prove_array_dummy_variable_index_fail1() {
                     )))),
                     Expr::Number(1)
                 ),
-                return_value: Value::Unit
-            }]
+                return_value: Value::Unit,
+                return_span: Span::default(),
+                span: Span::default(),
+                arith: None,
+                limits: Limits::default(),
+            }],
+            source: None,
         },
         vec![]
     ));
 }
+
+#[test]
+fn prove_for_range1() {
+    /*
+    //%precondition n >= 0
+    //%postcondition s == n
+    let mut s: i32 = 0;
+    //%invariant s == i
+    for i in 0..n {
+        s = s + 1;
+    }
+    */
+    assert!(prove(
+        Program {
+            content: vec![Function {
+                name: String::from("test"),
+                content: vec![
+                    Command::Binding(
+                        Binding::Assignment(
+                            Variable::Named(String::from("s")),
+                            Type::I32,
+                            Value::Expr(Expr::Number(0)),
+                            true
+                        ),
+                        Span::default()
+                    ),
+                    Command::Block(
+                        Block::ForRange(
+                            Variable::Named(String::from("i")),
+                            Value::Expr(Expr::Number(0)),
+                            Value::Variable(Variable::Named(String::from("n"))),
+                            vec![Command::Assignment(
+                                Assignment::Single(
+                                    Variable::Named(String::from("s")),
+                                    Value::Expr(Expr::Op(
+                                        Box::new(Expr::Value(Box::new(Value::Variable(
+                                            Variable::Named(String::from("s"))
+                                        )))),
+                                        Opcode::Add,
+                                        Box::new(Expr::Number(1))
+                                    ))
+                                ),
+                                Span::default()
+                            )],
+                            Bool::Equal(
+                                Expr::Value(Box::new(Value::Variable(Variable::Named(
+                                    String::from("s")
+                                )))),
+                                Expr::Value(Box::new(Value::Variable(Variable::Named(
+                                    String::from("i")
+                                ))))
+                            )
+                        ),
+                        Span::default()
+                    )
+                ],
+                input: vec![],
+                output: Type::Unit,
+                precondition: Bool::GreaterEqual(
+                    Expr::Value(Box::new(Value::Variable(Variable::Named(String::from(
+                        "n"
+                    ))))),
+                    Expr::Number(0)
+                ),
+                postcondition: Bool::Equal(
+                    Expr::Value(Box::new(Value::Variable(Variable::Named(String::from(
+                        "s"
+                    ))))),
+                    Expr::Value(Box::new(Value::Variable(Variable::Named(String::from(
+                        "n"
+                    )))))
+                ),
+                return_value: Value::Unit,
+                return_span: Span::default(),
+                span: Span::default(),
+                arith: None,
+                limits: Limits::default(),
+            }],
+            source: None,
+        },
+        vec![]
+    ));
+}
+
+#[test]
+fn prove_for_range_fail1() {
+    /*
+    //%precondition true
+    //%postcondition s == n
+    let mut s: i32 = 0;
+    //%invariant s == i
+    for i in 0..n {
+        s = s + 1;
+    }
+    */
+    assert!(!prove(
+        Program {
+            content: vec![Function {
+                name: String::from("test"),
+                content: vec![
+                    Command::Binding(
+                        Binding::Assignment(
+                            Variable::Named(String::from("s")),
+                            Type::I32,
+                            Value::Expr(Expr::Number(0)),
+                            true
+                        ),
+                        Span::default()
+                    ),
+                    Command::Block(
+                        Block::ForRange(
+                            Variable::Named(String::from("i")),
+                            Value::Expr(Expr::Number(0)),
+                            Value::Variable(Variable::Named(String::from("n"))),
+                            vec![Command::Assignment(
+                                Assignment::Single(
+                                    Variable::Named(String::from("s")),
+                                    Value::Expr(Expr::Op(
+                                        Box::new(Expr::Value(Box::new(Value::Variable(
+                                            Variable::Named(String::from("s"))
+                                        )))),
+                                        Opcode::Add,
+                                        Box::new(Expr::Number(1))
+                                    ))
+                                ),
+                                Span::default()
+                            )],
+                            Bool::Equal(
+                                Expr::Value(Box::new(Value::Variable(Variable::Named(
+                                    String::from("s")
+                                )))),
+                                Expr::Value(Box::new(Value::Variable(Variable::Named(
+                                    String::from("i")
+                                ))))
+                            )
+                        ),
+                        Span::default()
+                    )
+                ],
+                input: vec![],
+                output: Type::Unit,
+                precondition: Bool::True,
+                postcondition: Bool::Equal(
+                    Expr::Value(Box::new(Value::Variable(Variable::Named(String::from(
+                        "s"
+                    ))))),
+                    Expr::Value(Box::new(Value::Variable(Variable::Named(String::from(
+                        "n"
+                    )))))
+                ),
+                return_value: Value::Unit,
+                return_span: Span::default(),
+                span: Span::default(),
+                arith: None,
+                limits: Limits::default(),
+            }],
+            source: None,
+        },
+        vec![]
+    ));
+}
+
+#[test]
+fn prove_for_range_empty1() {
+    /*
+    //%invariant true
+    for i in 5..2 {
+        //%assert false
+    }
+    */
+    assert!(prove(
+        Program {
+            content: vec![Function {
+                name: String::from("test"),
+                content: vec![Command::Block(
+                    Block::ForRange(
+                        Variable::Named(String::from("i")),
+                        Value::Expr(Expr::Number(5)),
+                        Value::Expr(Expr::Number(2)),
+                        vec![Command::ProveControl(
+                            ProveControl::Assert(Bool::False),
+                            Span::default()
+                        )],
+                        Bool::True
+                    ),
+                    Span::default()
+                )],
+                input: vec![],
+                output: Type::Unit,
+                precondition: Bool::True,
+                postcondition: Bool::True,
+                return_value: Value::Unit,
+                return_span: Span::default(),
+                span: Span::default(),
+                arith: None,
+                limits: Limits::default(),
+            }],
+            source: None,
+        },
+        vec![]
+    ));
+}
+
+/// fn inc(x: i32) -> i32 with contract: x >= 0 => return_value == x + 1
+fn function_inc() -> Function {
+    Function {
+        name: String::from("inc"),
+        content: vec![],
+        input: vec![Binding::Declaration(
+            Variable::Named(String::from("x")),
+            Type::I32,
+            false,
+        )],
+        output: Type::I32,
+        precondition: Bool::GreaterEqual(
+            Expr::Value(Box::new(Value::Variable(Variable::Named(String::from(
+                "x",
+            ))))),
+            Expr::Number(0),
+        ),
+        postcondition: Bool::Equal(
+            Expr::Value(Box::new(Value::Variable(Variable::Named(String::from(
+                "return_value",
+            ))))),
+            Expr::Op(
+                Box::new(Expr::Value(Box::new(Value::Variable(Variable::Named(
+                    String::from("x"),
+                ))))),
+                Opcode::Add,
+                Box::new(Expr::Number(1)),
+            ),
+        ),
+        return_value: Value::Expr(Expr::Op(
+            Box::new(Expr::Value(Box::new(Value::Variable(Variable::Named(
+                String::from("x"),
+            ))))),
+            Opcode::Add,
+            Box::new(Expr::Number(1)),
+        )),
+        return_span: Span::default(),
+        span: Span::default(),
+        arith: None,
+        limits: Limits::default(),
+    }
+}
+
+#[test]
+fn prove_function_call1() {
+    /*
+    //%precondition y >= 0 && y <= 1000
+    //%postcondition return_value == y + 2
+    fn test(y: i32) -> i32 {
+        let a: i32 = inc(y);
+        inc(a)
+    }
+    */
+    assert!(prove(
+        Program {
+            content: vec![
+                function_inc(),
+                Function {
+                    name: String::from("test"),
+                    content: vec![Command::Binding(
+                        Binding::Assignment(
+                            Variable::Named(String::from("a")),
+                            Type::I32,
+                            Value::FunctionCall(
+                                String::from("inc"),
+                                vec![Value::Variable(Variable::Named(String::from("y")))]
+                            ),
+                            false
+                        ),
+                        Span::default()
+                    )],
+                    input: vec![Binding::Declaration(
+                        Variable::Named(String::from("y")),
+                        Type::I32,
+                        false
+                    )],
+                    output: Type::I32,
+                    precondition: Bool::And(
+                        Box::new(Bool::GreaterEqual(
+                            Expr::Value(Box::new(Value::Variable(Variable::Named(String::from(
+                                "y"
+                            ))))),
+                            Expr::Number(0)
+                        )),
+                        Box::new(Bool::LowerEqual(
+                            Expr::Value(Box::new(Value::Variable(Variable::Named(String::from(
+                                "y"
+                            ))))),
+                            Expr::Number(1000)
+                        ))
+                    ),
+                    postcondition: Bool::Equal(
+                        Expr::Value(Box::new(Value::Variable(Variable::Named(String::from(
+                            "return_value"
+                        ))))),
+                        Expr::Op(
+                            Box::new(Expr::Value(Box::new(Value::Variable(Variable::Named(
+                                String::from("y")
+                            ))))),
+                            Opcode::Add,
+                            Box::new(Expr::Number(2))
+                        )
+                    ),
+                    return_value: Value::FunctionCall(
+                        String::from("inc"),
+                        vec![Value::Variable(Variable::Named(String::from("a")))]
+                    ),
+                    return_span: Span::default(),
+                    span: Span::default(),
+                    arith: None,
+                    limits: Limits::default(),
+                }
+            ],
+            source: None,
+        },
+        vec![String::from("test")]
+    ));
+}
+
+#[test]
+fn prove_function_call_precondition_fail1() {
+    /*
+    //%precondition true
+    //%postcondition true
+    fn test(y: i32) -> i32 {
+        inc(y)
+    }
+    */
+    assert!(!prove(
+        Program {
+            content: vec![
+                function_inc(),
+                Function {
+                    name: String::from("test"),
+                    content: vec![],
+                    input: vec![Binding::Declaration(
+                        Variable::Named(String::from("y")),
+                        Type::I32,
+                        false
+                    )],
+                    output: Type::I32,
+                    precondition: Bool::True,
+                    postcondition: Bool::True,
+                    return_value: Value::FunctionCall(
+                        String::from("inc"),
+                        vec![Value::Variable(Variable::Named(String::from("y")))]
+                    ),
+                    return_span: Span::default(),
+                    span: Span::default(),
+                    arith: None,
+                    limits: Limits::default(),
+                }
+            ],
+            source: None,
+        },
+        vec![String::from("test")]
+    ));
+}
+
+#[test]
+fn prove_function_call_postcondition_fail1() {
+    /*
+    //%precondition y >= 0
+    //%postcondition return_value == y
+    fn test(y: i32) -> i32 {
+        inc(y)
+    }
+    */
+    assert!(!prove(
+        Program {
+            content: vec![
+                function_inc(),
+                Function {
+                    name: String::from("test"),
+                    content: vec![],
+                    input: vec![Binding::Declaration(
+                        Variable::Named(String::from("y")),
+                        Type::I32,
+                        false
+                    )],
+                    output: Type::I32,
+                    precondition: Bool::GreaterEqual(
+                        Expr::Value(Box::new(Value::Variable(Variable::Named(String::from(
+                            "y"
+                        ))))),
+                        Expr::Number(0)
+                    ),
+                    postcondition: Bool::Equal(
+                        Expr::Value(Box::new(Value::Variable(Variable::Named(String::from(
+                            "return_value"
+                        ))))),
+                        Expr::Value(Box::new(Value::Variable(Variable::Named(String::from(
+                            "y"
+                        )))))
+                    ),
+                    return_value: Value::FunctionCall(
+                        String::from("inc"),
+                        vec![Value::Variable(Variable::Named(String::from("y")))]
+                    ),
+                    return_span: Span::default(),
+                    span: Span::default(),
+                    arith: None,
+                    limits: Limits::default(),
+                }
+            ],
+            source: None,
+        },
+        vec![String::from("test")]
+    ));
+}
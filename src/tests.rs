@@ -0,0 +1,1450 @@
+use crate::prover::ModelValue;
+use crate::*;
+
+static SWAP: &str = "//%precondition true
+//%postcondition x == y'old && y == x'old
+fn swap(mut x: i32, mut y: i32) {
+    let t: i32 = x;
+    x = y;
+    y = t;
+}
+";
+
+#[test]
+fn parse1() {
+    let program = parse("swap.rs", SWAP).unwrap();
+    assert_eq!(program.content.len(), 1);
+    assert_eq!(program.content[0].name, "swap");
+}
+
+#[test]
+fn parse_not_whole1() {
+    let source = String::from(SWAP) + "fn main() { let a = ; }\n";
+    match parse_with("swap.rs", &source, Frontend::Nom) {
+        Err(ParseError::NotWhole(e)) => {
+            assert_eq!(e.span.left, "fn main() { let a = ; }\n".len())
+        }
+        x => panic!("Unexpected parse result: {:?}", x),
+    }
+}
+
+#[test]
+fn validate1() {
+    let program = parse("swap.rs", SWAP).unwrap();
+    assert!(validate(&program).is_empty());
+
+    let program = parse(
+        "shadow.rs",
+        "fn a() {\n    let x: i32 = 1;\n    let x: i32 = 2;\n}\n",
+    )
+    .unwrap();
+    let errors = validate(&program);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].message, "variable redeclared: x");
+}
+
+#[test]
+fn validate_sources1() {
+    let shadow = parse(
+        "shadow.rs",
+        "fn a() {\n    let x: i32 = 1;\n    let x: i32 = 2;\n}\n",
+    )
+    .unwrap();
+    // Parsing another file doesn't change where the errors of the first one point to
+    parse("swap.rs", SWAP).unwrap();
+
+    let errors = validate(&shadow);
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].to_string().contains("--> shadow.rs:3:5"));
+}
+
+#[test]
+fn validate_types1() {
+    let program = parse(
+        "mismatch.rs",
+        "fn a(x: i32) {\n    let mut b: bool = true;\n    b = x;\n}\n",
+    )
+    .unwrap();
+    let errors = validate(&program);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(
+        errors[0].message,
+        "mismatched types: expected bool, found i32"
+    );
+    assert_eq!(errors[0].span.left, "b = x;\n}\n".len());
+}
+
+#[test]
+fn validate_types2() {
+    // The annotations are checked too, instead of reaching the backends
+    let source = "//%precondition true
+//%postcondition return_value == 1
+fn a(b: bool) -> bool {
+    //%assert b == 1
+    b
+}
+";
+    let program = parse("annotation.rs", source).unwrap();
+    let errors = validate(&program);
+    let spans: Vec<usize> = errors.iter().map(|x| x.span.left).collect();
+    let at = |code: &str| source.len() - source.find(code).unwrap();
+    assert_eq!(spans, vec![at("fn a"), at("//%assert")]);
+}
+
+#[test]
+fn verify1() {
+    let program = parse("swap.rs", SWAP).unwrap();
+    let report = verify(&program, &[]);
+    assert!(report.proven());
+    assert_eq!(report.functions.len(), 1);
+    assert_eq!(report.functions[0].status(), Status::Proven);
+}
+
+#[test]
+fn verify_fail1() {
+    let source = SWAP.replace("y = t;", "y = t + 1;");
+    let program = parse("swap.rs", &source).unwrap();
+    let report = verify(&program, &[]);
+    assert!(!report.proven());
+
+    let failed: Vec<&Obligation> = report.functions[0]
+        .obligations
+        .iter()
+        .filter(|x| x.status == Status::Failed)
+        .collect();
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0].goal, Goal::Postcondition);
+    assert!(failed[0].counterexample.is_some());
+}
+
+static TWO_FAILURES: &str = "//%precondition x > 0
+//%postcondition true
+fn a(mut x: i32) {
+    //%assert x > 1
+    x = x + 1;
+    //%assert x > 3
+}
+
+//%precondition true
+//%postcondition x > 0
+fn b(x: i32) {
+}
+";
+
+#[test]
+fn verify_first_failure1() {
+    let program = parse("two.rs", TWO_FAILURES).unwrap();
+    let report = verify(&program, &[]);
+    assert!(!report.proven());
+    assert_eq!(report.functions.len(), 1);
+    assert_eq!(report.functions[0].obligations.len(), 1);
+}
+
+/// What a report says, without the times
+fn outcome(report: &Report) -> Vec<(&str, Goal, Status, &str, usize)> {
+    report
+        .functions
+        .iter()
+        .flat_map(|f| {
+            f.obligations.iter().map(move |x| {
+                (
+                    f.name.as_str(),
+                    x.goal,
+                    x.status,
+                    x.condition.as_str(),
+                    x.span.left,
+                )
+            })
+        })
+        .collect()
+}
+
+#[test]
+fn verify_jobs1() {
+    let source = String::from(SWAP) + TWO_FAILURES + &SWAP.replace("swap", "swap2");
+    let program = parse("jobs.rs", &source).unwrap();
+    for keep_going in [false, true].iter() {
+        let options = |jobs| Options {
+            keep_going: *keep_going,
+            jobs,
+            ..Default::default()
+        };
+        let expected = verify_with(&program, &[], options(1));
+        for jobs in 2..6 {
+            let report = verify_with(&program, &[], options(jobs));
+            assert_eq!(outcome(&report), outcome(&expected));
+        }
+    }
+}
+
+#[test]
+fn verify_keep_going1() {
+    let program = parse("two.rs", TWO_FAILURES).unwrap();
+    let report = verify_with(
+        &program,
+        &[],
+        Options {
+            keep_going: true,
+            ..Default::default()
+        },
+    );
+    assert!(!report.proven());
+    assert_eq!(report.functions.len(), 2);
+
+    let statuses: Vec<Status> = report.functions[0]
+        .obligations
+        .iter()
+        .map(|x| x.status)
+        .collect();
+    assert_eq!(
+        statuses,
+        vec![Status::Failed, Status::Failed, Status::Proven]
+    );
+    assert_eq!(report.functions[1].status(), Status::Failed);
+}
+
+#[test]
+fn verify_keep_going_loop1() {
+    // The invariant is broken, but what follows the loop is still checked
+    let source = "//%precondition true
+//%postcondition i == 9
+fn a() {
+    let mut i: i32 = 0;
+    //%invariant i >= 0 && i <= 10 && i != 5
+    //%variant 10 - i
+    while i < 10 {
+        i = i + 1;
+    }
+}
+";
+    let program = parse("loop.rs", source).unwrap();
+    let report = verify_with(
+        &program,
+        &[],
+        Options {
+            keep_going: true,
+            ..Default::default()
+        },
+    );
+
+    let failed: Vec<Goal> = report.functions[0]
+        .obligations
+        .iter()
+        .filter(|x| x.status == Status::Failed)
+        .map(|x| x.goal)
+        .collect();
+    assert_eq!(failed, vec![Goal::LoopInvariant, Goal::LoopExit]);
+}
+
+fn verify_source(source: &str) -> Report {
+    let program = parse("types.rs", source).unwrap();
+    verify(&program, &[])
+}
+
+#[test]
+fn verify_int_types1() {
+    let report = verify_source(
+        "//%precondition true
+//%postcondition a >= 0 && a <= 255 && b >= -128 && b <= 127 && c >= 0 && d >= 0
+fn types(a: u8, b: i8, c: u32, d: usize) {
+}
+",
+    );
+    assert!(report.proven());
+
+    // i32 is not limited like that
+    let report = verify_source(
+        "//%precondition true
+//%postcondition a >= 0
+fn types(a: i32) {
+}
+",
+    );
+    assert!(!report.proven());
+}
+
+#[test]
+fn verify_int_types_counterexample1() {
+    let report = verify_source(
+        "//%precondition true
+//%postcondition a <= 5
+fn types(a: u32) {
+}
+",
+    );
+
+    let obligation = &report.functions[0].obligations[0];
+    let counterexample = obligation.counterexample.as_ref().unwrap();
+    match counterexample.values.get("a") {
+        Some(prover::ModelValue::Int(a)) => assert!(*a > 5),
+        x => panic!("Unexpected value: {:?}", x),
+    }
+}
+
+#[test]
+fn verify_int_types_loop1() {
+    let report = verify_source(
+        "//%precondition n > 0
+//%postcondition return_value == n
+fn count(n: usize) -> usize {
+    let mut c: usize = 0;
+    //%invariant c == i
+    for i in 0..n {
+        c = c + 1;
+    }
+    (c)
+}
+",
+    );
+    assert!(report.proven());
+}
+
+fn verify_strict(source: &str) -> Report {
+    let program = parse("strict.rs", source).unwrap();
+    let options = Options {
+        strict_overflow: true,
+        ..Default::default()
+    };
+    verify_with(&program, &[], options)
+}
+
+static DIVISION: &str = "//%precondition true
+//%postcondition return_value == x / y
+fn div(x: i32, y: i32) -> i32 {
+    let z: i32 = x / y;
+    (z)
+}
+";
+
+#[test]
+fn verify_division_by_zero1() {
+    // Not only a warning, even without the strict overflows
+    let report = verify_source(DIVISION);
+    assert!(!report.proven());
+
+    let obligation = &report.functions[0].obligations[0];
+    assert_eq!(obligation.goal, Goal::DivisionByZero);
+    assert_eq!(obligation.status, Status::Failed);
+    assert_eq!(obligation.condition, "(/ x y)");
+    let y = obligation.counterexample.as_ref().unwrap().values.get("y");
+    assert_eq!(y, Some(&prover::ModelValue::Int(0)));
+}
+
+#[test]
+fn verify_division_trunc1() {
+    // Rounded toward zero as in Rust, not the Euclidean division of SMT-LIB
+    let source = "//%precondition true
+//%postcondition return_value
+fn trunc() -> bool {
+    let a: i32 = -7;
+    let b: i32 = 2;
+    let c: i32 = 0 - b;
+    let d: i32 = 0 - a;
+    a / b == -3 && a % b == -1 && d / c == -3 && d % c == 1 && a / c == 3 && a % c == -1
+}
+";
+    assert!(verify_source(source).proven());
+    assert!(verify_source(&source.replace("fn trunc", "//%arith bv\nfn trunc")).proven());
+    assert!(!verify_source(&source.replace("a % b == -1", "a % b == 1")).proven());
+}
+
+#[test]
+fn verify_division_trunc2() {
+    let source = "//%precondition y != 0 && x > -1000 && x < 1000 && y > -1000 && y < 1000
+//%postcondition return_value.0 * y + return_value.1 == x && (x < 0 || return_value.1 >= 0) && (x > 0 || return_value.1 <= 0)
+fn div(x: i32, y: i32) -> (i32, i32) {
+    (x / y, x % y)
+}
+";
+    assert!(verify_source(source).proven());
+}
+
+#[test]
+fn verify_division_by_zero2() {
+    // The division is only done when the divisor is not zero
+    let report = verify_strict(
+        "//%precondition true
+//%postcondition true
+fn rem(x: i32, y: i32) -> i32 {
+    let mut z: i32 = 0;
+    if y != 0 {
+        z = x % y;
+    }
+    (z)
+}
+",
+    );
+    assert!(report.proven());
+
+    let report = verify_strict(&DIVISION.replace("//%precondition true", "//%precondition y > 0"));
+    assert!(report.proven());
+}
+
+#[test]
+fn verify_division_by_zero3() {
+    // Checked even when the result isn't used
+    let source = "//%precondition true
+//%postcondition true
+fn unused(n: i32) {
+    let y: i32 = 10 / n;
+}
+";
+    for arith in [Arith::Int, Arith::Bv].iter() {
+        let program = parse("unused.rs", source).unwrap();
+        let options = Options {
+            arith: *arith,
+            ..Default::default()
+        };
+        let report = verify_with(&program, &[], options);
+        assert!(!report.proven());
+        assert_eq!(
+            report.functions[0].obligations[0].goal,
+            Goal::DivisionByZero
+        );
+    }
+}
+
+#[test]
+fn verify_overflow1() {
+    let source = "//%precondition x >= 250
+//%postcondition return_value == x + 10
+fn add(x: u8) -> u8 {
+    let y: u8 = x + 10;
+    (y)
+}
+";
+    let program = parse("overflow.rs", source).unwrap();
+    let options = Options {
+        strict_overflow: true,
+        arith: Arith::Bv,
+        ..Default::default()
+    };
+    let report = verify_with(&program, &[], options.clone());
+    assert!(!report.proven());
+
+    let obligation = &report.functions[0].obligations[0];
+    assert_eq!(obligation.goal, Goal::Overflow);
+    assert_eq!(obligation.condition, "(+ x 10)");
+
+    let program = parse("overflow.rs", &source.replace("x >= 250", "x <= 245")).unwrap();
+    assert!(verify_with(&program, &[], options).proven());
+}
+
+#[test]
+fn verify_overflow2() {
+    // The unused results overflow too, and the literals keep the types of the variables they are assigned to
+    let unused = "//%precondition true
+//%postcondition true
+fn unused(x: u8) {
+    let y: u8 = x + 1;
+}
+";
+    let literal = "//%precondition true
+//%postcondition true
+fn literal() {
+    let r: u8 = 255;
+    let s: u8 = r + 1;
+}
+";
+    for arith in [Arith::Int, Arith::Bv].iter() {
+        let options = Options {
+            strict_overflow: true,
+            arith: *arith,
+            ..Default::default()
+        };
+        let program = parse("unused.rs", unused).unwrap();
+        assert!(!verify_with(&program, &[], options.clone()).proven());
+
+        let program = parse("literal.rs", literal).unwrap();
+        let report = verify_with(&program, &[], options.clone());
+        assert!(!report.proven());
+        let obligation = &report.functions[0].obligations[0];
+        assert_eq!(obligation.goal, Goal::Overflow);
+        assert_eq!(obligation.condition, "(+ 255u8 1)");
+
+        let program = parse("literal.rs", &literal.replace("255", "254")).unwrap();
+        assert!(verify_with(&program, &[], options).proven());
+    }
+}
+
+#[test]
+fn verify_literal_range1() {
+    // A literal that doesn't fit its type is rejected by the validation, without it the proof doesn't go through anyway
+    let source = "//%precondition true
+//%postcondition return_value == 7
+fn wrong() -> u8 {
+    let r: u8 = 300;
+    r
+}
+";
+    for literal in ["300", "-1"].iter() {
+        let source = source.replace("300", literal);
+        let program = parse("literal.rs", &source).unwrap();
+        assert_eq!(validate(&program)[0].message, "literal out of range for u8");
+        for arith in [Arith::Int, Arith::Bv].iter() {
+            let options = Options {
+                strict_overflow: true,
+                arith: *arith,
+                ..Default::default()
+            };
+            assert!(!verify_with(&program, &[], options).proven());
+        }
+    }
+}
+
+#[test]
+fn verify_large_number1() {
+    let source = "//%precondition x < 10
+//%postcondition return_value == x + 4294967296 && return_value < 18446744073709551615
+fn big(x: u64) -> u64 {
+    (x + 4294967296)
+}
+";
+    for frontend in [Frontend::Syn, Frontend::Nom].iter() {
+        let program = parse_with("big.rs", source, *frontend).unwrap();
+        for arith in [Arith::Int, Arith::Bv].iter() {
+            let options = Options {
+                arith: *arith,
+                ..Default::default()
+            };
+            assert!(verify_with(&program, &[], options).proven());
+        }
+    }
+}
+
+#[test]
+fn verify_overflow_int1() {
+    let source = "//%precondition y > 0
+//%postcondition return_value == x + y
+fn add(x: i32, y: i32) -> i32 {
+    let z: i32 = x + y;
+    (z)
+}
+";
+    let program = parse("overflow.rs", source).unwrap();
+    let options = Options {
+        strict_overflow: true,
+        arith: Arith::Int,
+        ..Default::default()
+    };
+    let report = verify_with(&program, &[], options.clone());
+    assert!(!report.proven());
+    let failed: Vec<Goal> = report.functions[0]
+        .obligations
+        .iter()
+        .filter(|x| x.status == Status::Failed)
+        .map(|x| x.goal)
+        .collect();
+    assert_eq!(failed, vec![Goal::Overflow]);
+
+    // Unbounded ints don't overflow without --strict-overflow
+    let relaxed = Options {
+        strict_overflow: false,
+        ..options.clone()
+    };
+    assert!(verify_with(&program, &[], relaxed).proven());
+
+    let source = source.replace("y > 0", "y > 0 && x < 2147483647 - y");
+    let program = parse("overflow.rs", &source).unwrap();
+    assert!(verify_with(&program, &[], options.clone()).proven());
+
+    // A division by zero isn't an overflow as well
+    let source = DIVISION.replace("x / y", "10 / y");
+    let program = parse("overflow.rs", &source).unwrap();
+    let report = verify_with(&program, &[], options);
+    let failed: Vec<Goal> = report.functions[0]
+        .obligations
+        .iter()
+        .filter(|x| x.status == Status::Failed)
+        .map(|x| x.goal)
+        .collect();
+    assert_eq!(failed, vec![Goal::DivisionByZero]);
+}
+
+static WRAPPING: &str = "//%precondition x == 255
+//%postcondition return_value == 0
+fn wrap(x: u8) -> u8 {
+    let y: u8 = x + 1;
+    (y)
+}
+";
+
+#[test]
+fn verify_arith1() {
+    let program = parse("wrap.rs", WRAPPING).unwrap();
+    let with = |arith| {
+        let options = Options {
+            arith,
+            ..Default::default()
+        };
+        verify_with(&program, &[], options).proven()
+    };
+    assert!(!with(Arith::Int));
+    assert!(with(Arith::Bv));
+}
+
+#[test]
+fn verify_arith_function1() {
+    // The function's choice wins over the default one
+    let source = WRAPPING.replace("fn wrap", "//%arith bv\nfn wrap");
+    let program = parse("wrap.rs", &source).unwrap();
+    let options = Options {
+        arith: Arith::Int,
+        ..Default::default()
+    };
+    assert!(verify_with(&program, &[], options).proven());
+}
+
+static REMAINDER: &str = "//%precondition x >= 0 && y > 0
+//%postcondition return_value.0 * y + return_value.1 == x && return_value.1 < y
+fn remainder(x: i32, y: i32) -> (i32, i32) {
+    let mut quo: i32 = 0;
+    let mut rem: i32 = x;
+
+    //%invariant quo * y + rem == x && y > 0 && rem >= 0
+    //%variant rem
+    while rem >= y {
+        rem = rem - y;
+        quo = quo + 1;
+    }
+
+    (quo, rem)
+}
+";
+
+#[test]
+fn verify_tuple_return1() {
+    assert!(verify_source(REMAINDER).proven());
+
+    let report = verify_source(&REMAINDER.replace("return_value.1 < y", "return_value.1 < 1"));
+    assert!(!report.proven());
+    // The code after the loop is proven together with the loop exit
+    let failed = report.functions[0]
+        .obligations
+        .iter()
+        .find(|x| x.status == Status::Failed)
+        .unwrap();
+    assert_eq!(failed.goal, Goal::LoopExit);
+}
+
+#[test]
+fn verify_tuple_elem1() {
+    // Both elements are read before any of them is assigned
+    let source = "//%precondition true
+//%postcondition return_value.0 == t'old.1 && return_value.1 == t'old.0 + 1
+fn flip(t: (i32, i32)) -> (i32, i32) {
+    let (a, b): (i32, i32) = t;
+    let mut u: (i32, i32) = (a, b);
+    (u.0, u.1) = (u.1, u.0);
+    u.1 = u.1 + 1;
+    (u)
+}
+";
+    assert!(verify_source(source).proven());
+    assert!(!verify_source(&source.replace("t'old.0 + 1", "t'old.1 + 1")).proven());
+}
+
+#[test]
+fn verify_tuple_call1() {
+    let source = String::from(REMAINDER)
+        + "
+//%precondition a >= 0
+//%postcondition return_value == a
+fn caller(a: i32) -> i32 {
+    let r: (i32, i32) = remainder(a, 3);
+    (r.0 * 3 + r.1)
+}
+";
+    assert!(verify_source(&source).proven());
+}
+
+static INC: &str = "//%precondition *x < 100
+//%postcondition *x == x'old + 1
+fn inc(x: &mut i32) {
+    *x = *x + 1;
+}
+";
+
+#[test]
+fn verify_reference1() {
+    assert!(verify_source(INC).proven());
+    assert!(!verify_source(&INC.replace("x'old + 1", "x'old")).proven());
+}
+
+#[test]
+fn verify_reference_local1() {
+    // The borrowed variable is changed through the reference
+    let source = "//%precondition a < 50
+//%postcondition return_value == a + 1
+fn local(a: i32) -> i32 {
+    let mut b: i32 = a;
+    let r: &mut i32 = &mut b;
+    *r = *r + 1;
+    let s: &i32 = &b;
+    (*s)
+}
+";
+    assert!(verify_source(source).proven());
+    assert!(!verify_source(&source.replace("a + 1", "a")).proven());
+}
+
+#[test]
+fn verify_reference_call1() {
+    let source = String::from(INC)
+        + "
+//%precondition true
+//%postcondition return_value == *x + *y
+fn sum(x: &i32, y: &i32) -> i32 {
+    (*x + *y)
+}
+
+//%precondition a < 10
+//%postcondition return_value == 2 * a'old + 1 && a == a'old + 1
+fn caller(mut a: i32) -> i32 {
+    let c: i32 = a + 0;
+    inc(&mut a);
+    let s: i32 = sum(&a, &c);
+    (s)
+}
+";
+    assert!(verify_source(&source).proven());
+
+    // The borrowed variable doesn't keep its value
+    let report = verify_source(&source.replace("a == a'old + 1", "a == a'old"));
+    assert!(!report.proven());
+}
+
+static SWAP_ELEMENTS: &str = "//%precondition i < x.len() && j < x.len()
+//%postcondition x[i] == x'old[j] && x[j] == x'old[i] && (forall k !(k >= 0 && k < x.len() && k != i && k != j) || x[k] == x'old[k])
+fn swap_elements(x: &mut [i32], i: usize, j: usize) {
+    let t: i32 = x[i];
+    x[i] = x[j];
+    x[j] = t;
+}
+";
+
+#[test]
+fn verify_array_old1() {
+    assert!(verify_source(SWAP_ELEMENTS).proven());
+}
+
+#[test]
+fn verify_array_old2() {
+    let report = verify_source(&SWAP_ELEMENTS.replace("x[j] == x'old[i]", "x[j] == x'old[j]"));
+    assert!(!report.proven());
+
+    // The 'old array is shown at the same indices as the array
+    let c = report.functions[0]
+        .obligations
+        .iter()
+        .find(|x| x.goal == Goal::Postcondition)
+        .and_then(|x| x.counterexample.as_ref())
+        .unwrap();
+    match (c.values.get("x"), c.values.get("x'old")) {
+        (Some(ModelValue::Array(x)), Some(ModelValue::Array(old))) => {
+            assert!(x.keys().all(|i| old.contains_key(i)))
+        }
+        x => panic!("Unexpected counterexample: {:?}", x),
+    }
+}
+
+static ZERO: &str = "//%precondition n <= x.len()
+//%postcondition forall k !(k >= 0 && k < n) || x[k] == 0
+fn zero(x: &mut [i32], n: usize) {
+    let mut i: usize = 0;
+    //%invariant i <= n && n <= x.len() && (forall k !(k >= 0 && k < i) || x[k] == 0)
+    //%variant n - i
+    while i < n {
+        x[i] = 0;
+        i = i + 1;
+    }
+}
+";
+
+#[test]
+fn verify_bounds1() {
+    assert!(verify_source(ZERO).proven());
+}
+
+#[test]
+fn verify_bounds2() {
+    // Off by one, the last write is past the end
+    let report = verify_source(&ZERO.replace("while i < n", "while i <= n"));
+    let failed: Vec<(Goal, String)> = report.functions[0]
+        .obligations
+        .iter()
+        .filter(|x| x.status == Status::Failed && x.goal == Goal::Bounds)
+        .map(|x| (x.goal, x.condition.clone()))
+        .collect();
+    assert_eq!(failed, vec![(Goal::Bounds, String::from("x[i]"))]);
+}
+
+#[test]
+fn verify_bounds3() {
+    let source = "//%precondition true
+//%postcondition return_value == 3
+fn last() -> i32 {
+    let a: [i32; 3] = [1, 2, 3];
+    let x: i32 = a[a.len() - 1];
+    (x)
+}
+";
+    assert!(verify_source(source).proven());
+    assert!(!verify_source(&source.replace("a[a.len() - 1]", "a[a.len()]")).proven());
+}
+
+#[test]
+fn verify_bounds4() {
+    // The length of a slice is a usize like any other
+    let source = "//%precondition true
+//%postcondition x.len() >= 0
+fn len(x: &[i32]) {
+}
+";
+    assert!(verify_source(source).proven());
+}
+
+#[test]
+fn verify_check_span1() {
+    // Reported at the code that's checked, not at the function
+    let at = |source: &str, code: &str| source.len() - source.find(code).unwrap();
+    let failed = |source: &str, strict_overflow| {
+        let program = parse("span.rs", source).unwrap();
+        let options = Options {
+            strict_overflow,
+            keep_going: true,
+            ..Default::default()
+        };
+        let report = verify_with(&program, &[], options);
+        report.functions[0]
+            .obligations
+            .iter()
+            .filter(|x| x.status == Status::Failed)
+            .map(|x| (x.goal, x.span.left))
+            .collect::<Vec<_>>()
+    };
+
+    let source = ZERO.replace("while i < n", "while i <= n");
+    assert!(failed(&source, false).contains(&(Goal::Bounds, at(&source, "x[i] = 0;"))));
+
+    assert_eq!(
+        failed(DIVISION, false),
+        vec![(Goal::DivisionByZero, at(DIVISION, "let z: i32 = x / y;"))]
+    );
+
+    let source = "//%precondition true
+//%postcondition true
+fn add(x: i32, y: i32) -> i32 {
+    let z: i32 = x;
+    (z + y)
+}
+";
+    assert_eq!(
+        failed(source, true),
+        vec![
+            (Goal::Overflow, at(source, "(z + y)")),
+            (Goal::Underflow, at(source, "(z + y)"))
+        ]
+    );
+}
+
+#[test]
+fn verify_bounds_counterexample1() {
+    // The variables of the failed access are shown, the names made up by the prover aren't
+    let source = "//%precondition true
+//%postcondition return_value == x + 1
+fn inc(x: i32) -> i32 {
+    x + 1
+}
+
+//%precondition true
+//%postcondition true
+fn get(a: &[i32], n: usize) -> i32 {
+    let x: i32 = 0;
+    let z: i32 = inc(x);
+    let y: i32 = a[n];
+    y
+}
+";
+    let report = verify_source(source);
+    let obligation = report.functions[1]
+        .obligations
+        .iter()
+        .find(|x| x.status == Status::Failed)
+        .unwrap();
+    assert_eq!(obligation.goal, Goal::Bounds);
+    let counterexample = obligation.counterexample.as_ref().unwrap();
+    let names: Vec<&str> = counterexample.values.keys().map(|x| x.as_str()).collect();
+    assert_eq!(names, vec!["a", "a'old", "a.len()", "n", "n'old"]);
+}
+
+#[test]
+fn verify_bool_array1() {
+    let source = "//%precondition i < x.len()
+//%postcondition x[i] && (forall k !(k >= 0 && k < x.len() && k != i) || x[k] == x'old[k])
+fn set(x: &mut [bool], i: usize) {
+    x[i] = true;
+}
+";
+    assert!(verify_source(source).proven());
+    assert!(!verify_source(&source.replace("x[i] = true", "x[i] = false")).proven());
+}
+
+static BUMP: &str = "//%precondition i < x.len()
+//%postcondition x[i].0 == x'old[i].0 + 1 && x[i].1 != x'old[i].1
+fn bump(x: &mut [(i64, bool)], i: usize) {
+    x[i].0 = x[i].0 + 1;
+    x[i].1 = !x[i].1;
+}
+";
+
+#[test]
+fn verify_tuple_array1() {
+    assert!(verify_source(BUMP).proven());
+    assert!(!verify_source(&BUMP.replace("!x[i].1", "x[i].1")).proven());
+}
+
+#[test]
+fn verify_tuple_array2() {
+    let source = "//%precondition true
+//%postcondition return_value == 2
+fn second() -> i32 {
+    let a: [(i32, bool); 2] = [(1, true), (2, false)];
+    let mut r: i32 = 0;
+    if !a[1].1 {
+        r = a[1].0;
+    }
+    (r)
+}
+";
+    assert!(verify_source(source).proven());
+}
+
+#[test]
+fn verify_inferred1() {
+    let source = "//%precondition x < 100
+//%postcondition return_value == x + 1 && c == c'old
+fn next(x: u8, c: bool) -> u8 {
+    let mut y;
+    let b = c;
+    if b {
+        y = x + 1;
+    } else {
+        y = x;
+        y = y + 1;
+    }
+    (y)
+}
+";
+    assert!(verify_source(source).proven());
+    assert!(!verify_source(&source.replace("y = y + 1;", "")).proven());
+}
+
+#[test]
+fn verify_loop1() {
+    let source = "//%precondition n >= 0 && n < 1000
+//%postcondition return_value == n
+fn count(n: i32) -> i32 {
+    let mut i: i32 = 0;
+    //%invariant i >= 0 && i <= n
+    //%variant n - i
+    loop {
+        if i == n {
+            break;
+        }
+        i = i + 1;
+    }
+    i
+}
+";
+    assert!(verify_source(source).proven());
+    // Without the break the loop never ends
+    assert!(!verify_source(&source.replace("break;", "i = i;")).proven());
+}
+
+#[test]
+fn verify_loop_break1() {
+    // The wrong postcondition is reported as what has to hold after the loop, not the invariant
+    let source = "//%precondition n >= 0 && n < 1000
+//%postcondition return_value == n + 1
+fn count(n: i32) -> i32 {
+    let mut i: i32 = 0;
+    //%invariant i >= 0 && i <= n
+    //%variant n - i
+    loop {
+        if i == n {
+            break;
+        }
+        i = i + 1;
+    }
+    i
+}
+";
+    let report = verify_source(source);
+    let failed: Vec<(Goal, &str)> = report.functions[0]
+        .obligations
+        .iter()
+        .filter(|x| x.status == Status::Failed)
+        .map(|x| (x.goal, x.condition.as_str()))
+        .collect();
+    assert_eq!(failed, vec![(Goal::LoopExit, "i == (+ n 1)")]);
+}
+
+#[test]
+fn verify_attributes1() {
+    let source = "use rustp_contracts::{ensures, requires};
+
+#[requires(n >= 0 && n < 1000)]
+#[ensures(return_value == n)]
+fn count(n: i32) -> i32 {
+    let mut i: i32 = 0;
+    #[invariant(i >= 0 && i <= n)]
+    #[variant(n - i)]
+    loop {
+        if i == n {
+            break;
+        }
+        i = i + 1;
+    }
+    i
+}
+";
+    assert!(verify_source(source).proven());
+    assert!(!verify_source(&source.replace("return_value == n", "return_value < n")).proven());
+}
+
+#[test]
+fn verify_continue1() {
+    let source = "//%precondition n >= 0 && n < 1000
+//%postcondition return_value >= 0 && return_value <= n
+fn evens(n: i32) -> i32 {
+    let mut c: i32 = 0;
+    //%invariant c >= 0 && c <= i
+    for i in 0..n {
+        if i % 2 == 1 {
+            continue;
+        }
+        c = c + 1;
+    }
+    c
+}
+";
+    assert!(verify_source(source).proven());
+    assert!(!verify_source(&source.replace("continue;", "c = c + 1;")).proven());
+}
+
+// Quantified invariants are a lot slower to prove with bit-vectors
+static FIND: &str = "//%precondition true
+//%postcondition (return_value == x.len() && (forall k !(k >= 0 && k < x.len()) || x[k] != v)) || (return_value < x.len() && x[return_value] == v)
+//%arith int
+fn find(x: &[i32], v: i32) -> usize {
+    let mut i: usize = 0;
+    //%invariant i <= x.len() && (forall k !(k >= 0 && k < i) || x[k] != v)
+    //%variant x.len() - i
+    while i < x.len() {
+        if x[i] == v {
+            break;
+        }
+        i = i + 1;
+    }
+    i
+}
+";
+
+#[test]
+fn verify_break1() {
+    assert!(verify_source(FIND).proven());
+    assert!(!verify_source(&FIND.replace("x[i] == v {", "x[i] != v {")).proven());
+}
+
+#[test]
+fn verify_return_in_loop1() {
+    let source = FIND
+        .replace("break;", "return i;")
+        .replace("    i\n}", "    x.len()\n}");
+    assert!(verify_source(&source).proven());
+    assert!(!verify_source(&source.replace("return i;", "return 0;")).proven());
+}
+
+static GUARD: &str = "//%precondition n >= 0 && n < 1000
+//%postcondition return_value >= 1
+fn clamp(n: i32) -> i32 {
+    if n == 0 {
+        return 1;
+    }
+    let mut r: i32 = n;
+    if r > 5 {
+        return 5;
+    } else {
+        r = r + 1;
+    }
+    r
+}
+";
+
+#[test]
+fn verify_return1() {
+    assert!(verify_source(GUARD).proven());
+
+    // Reported at the return that breaks the postcondition
+    let source = GUARD.replace("return 5;", "return 0;");
+    let report = verify_source(&source);
+    assert!(!report.proven());
+    let failed: Vec<&Obligation> = report.functions[0]
+        .obligations
+        .iter()
+        .filter(|x| x.status == Status::Failed)
+        .collect();
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0].goal, Goal::Postcondition);
+    assert_eq!(
+        failed[0].span.left,
+        source.len() - source.find("return 0;").unwrap()
+    );
+}
+
+#[test]
+fn verify_return2() {
+    // Nothing after the return is run
+    let source = "//%precondition true
+//%postcondition x == x'old
+fn nothing(mut x: i32) {
+    if x > 0 {
+        return;
+    }
+    x = x;
+}
+";
+    assert!(verify_source(source).proven());
+    assert!(!verify_source(&source.replace("return;", "x = x + 1;\n        return;")).proven());
+    assert!(!verify_source(&source.replace("x = x;", "x = 1;")).proven());
+}
+
+#[test]
+fn verify_return3() {
+    // No value at the end of the function when every path returns before it
+    let source = "//%precondition true
+//%postcondition return_value >= 0
+fn pos(x: i32) -> i32 {
+    if x > 0 {
+        return x;
+    } else {
+        return 0;
+    }
+}
+";
+    assert!(verify_source(source).proven());
+    assert!(!verify_source(&source.replace("return 0;", "return -1;")).proven());
+
+    let source = "//%precondition true
+//%postcondition return_value > 0
+fn pos(x: i32) -> i32 {
+    //%invariant true
+    loop {
+        if x > 0 {
+            return x;
+        }
+    }
+}
+";
+    assert!(verify_source(source).proven());
+    assert!(!verify_source(&source.replace("return x;", "return 0;")).proven());
+}
+
+static FACTOR: &str = "//%precondition x > 1 && y > 1 && x < 65536 && y < 65536
+//%postcondition x * y != 2147483647
+//%arith bv
+fn factor(x: u32, y: u32) {
+}
+";
+
+#[test]
+fn verify_rlimit1() {
+    let source = "//%precondition x > 0 && x < 100
+//%postcondition return_value > 1
+//%rlimit 1
+fn inc(x: i32) -> i32 {
+    let y: i32 = x + 1;
+    y
+}
+";
+    let report = verify_source(source);
+    assert!(!report.proven());
+    assert_eq!(report.status(), Status::Unknown);
+    let obligation = &report.functions[0].obligations[0];
+    assert_eq!(obligation.status, Status::Unknown);
+    assert_eq!(obligation.reason.as_deref(), Some("rlimit"));
+    assert!(obligation.counterexample.is_none());
+
+    // The function's limit wins over the options
+    let program = parse("inc.rs", &source.replace("rlimit 1", "rlimit 1000000")).unwrap();
+    let options = Options {
+        limits: Limits {
+            rlimit: Some(1),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    assert!(verify_with(&program, &[], options).proven());
+}
+
+#[test]
+fn verify_timeout1() {
+    let program = parse("factor.rs", FACTOR).unwrap();
+    let options = Options {
+        limits: Limits {
+            timeout: Some(1),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let report = verify_with(&program, &[], options);
+    assert_eq!(report.status(), Status::Unknown);
+    let obligation = &report.functions[0].obligations[0];
+    assert_eq!(obligation.reason.as_deref(), Some("timeout"));
+}
+
+#[test]
+fn verify_timeout2() {
+    // Z3 doesn't stop at its own timeout for the nonlinear ints, it's stopped anyway
+    let source = "//%precondition x > 0 && y > 0 && z > 0
+//%postcondition x * x * x + y * y * y != z * z * z
+fn cubic(x: i64, y: i64, z: i64) {
+}
+";
+    let program = parse("cubic.rs", source).unwrap();
+    let options = Options {
+        limits: Limits {
+            timeout: Some(200),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let start = std::time::Instant::now();
+    let report = verify_with(&program, &[], options);
+    assert!(start.elapsed() < std::time::Duration::from_secs(5));
+    assert_eq!(report.status(), Status::Unknown);
+    let obligation = &report.functions[0].obligations[0];
+    assert_eq!(obligation.reason.as_deref(), Some("timeout"));
+}
+
+#[test]
+fn verify_emit_smt1() {
+    let program = parse("div.rs", DIVISION).unwrap();
+    let emit = |jobs| {
+        let dir = std::env::temp_dir().join(format!("rustp_emit_smt1_{}", jobs));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let options = Options {
+            keep_going: true,
+            jobs,
+            emit_smt: Some(dir.clone()),
+            ..Default::default()
+        };
+        assert!(!verify_with(&program, &[], options).proven());
+
+        let mut files: Vec<(String, String)> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|x| x.unwrap().path())
+            .map(|x| {
+                let name = x.file_name().unwrap().to_str().unwrap().to_string();
+                (name, std::fs::read_to_string(x).unwrap())
+            })
+            .collect();
+        files.sort();
+        std::fs::remove_dir_all(&dir).unwrap();
+        files
+    };
+
+    let files = emit(1);
+    let names: Vec<&str> = files.iter().map(|x| x.0.as_str()).collect();
+    assert!(names.contains(&"div.1.division_by_zero.smt2"));
+    assert!(names.iter().any(|x| x.ends_with(".postcondition.smt2")));
+    for (_, query) in files.iter() {
+        assert!(query.contains("(declare-fun y ()"));
+        assert!(query.starts_with("; ") && query.ends_with("(check-sat)\n"));
+    }
+    assert_eq!(emit(3), files);
+}
+
+static BIG: &str = "//%precondition x > 0
+//%postcondition (return_value && x > 100) || (!return_value && x <= 100)
+fn big(x: i32) -> bool {
+    x > 100
+}
+";
+
+#[test]
+fn verify_short_circuit_call1() {
+    // The call on the right is only made when the left side doesn't decide
+    let source = String::from(BIG)
+        + "
+//%precondition x > -1000 && x < 1000
+//%postcondition (return_value && (x > 100 || x < -100)) || (!return_value && x >= -100 && x <= 100)
+fn far(x: i32) -> bool {
+    let a: bool = x > 0 && big(x);
+    let b: bool = x >= 0 || big(0 - x);
+    let mut r: bool = b && a;
+    if x > 0 {
+        r = a;
+    } else if x < 0 && big(0 - x) {
+        r = true;
+    }
+    (r)
+}
+";
+    assert!(verify_source(&source).proven());
+    assert!(!verify_source(&source.replace("x > 0 && big(x)", "x > -1 && big(x)")).proven());
+}
+
+#[test]
+fn validate_call_in_loop1() {
+    let source = String::from(BIG)
+        + "
+fn a(mut x: i32) {
+    //%invariant x > 0
+    while big(x) {
+        x = x - 1;
+    }
+}
+";
+    let program = parse("loop.rs", &source).unwrap();
+    let errors = validate(&program);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(
+        errors[0].message,
+        "unsupported construct: function call in a loop condition"
+    );
+}
+
+static FIRST: &str = "
+//%precondition true
+//%postcondition true
+fn first(x: &[i32; 2]) -> i32 {
+    x[0]
+}
+";
+
+#[test]
+fn validate_references1() {
+    let unsupported = [
+        (
+            "fn a(mut x: [i32; 2]) {\n    let r = &mut x[0];\n    *r = 1;\n}\n",
+            "mutable borrow of something other than a variable",
+        ),
+        (
+            "fn a(r: &mut i32) {\n    inc(&mut *r);\n}\n",
+            "mutable borrow of something other than a variable",
+        ),
+        (
+            "fn a(mut b: i32, mut c: i32) {\n    let mut r: &mut i32 = &mut b;\n    r = &mut c;\n    *r = 1;\n}\n",
+            "borrow assigned to the reference r",
+        ),
+        (
+            "fn a(x: [i32; 2]) -> i32 {\n    let y: i32 = first(&x);\n    y\n}\n",
+            "call to first with the parameter x of type &[i32; 2]",
+        ),
+    ];
+    for (code, message) in unsupported.iter() {
+        let source = String::from(INC) + FIRST + code;
+        let program = parse("references.rs", &source).unwrap();
+        let errors = validate(&program);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].message,
+            format!("unsupported construct: {}", message)
+        );
+    }
+
+    // An element can be borrowed by a call, it's assigned what the callee left behind
+    let source = String::from(INC)
+        + "//%precondition x[0] < 50
+//%postcondition x[0] == x'old[0] + 1
+fn a(x: &mut [i32; 2]) {
+    inc(&mut x[0]);
+}
+";
+    let program = parse("references.rs", &source).unwrap();
+    assert!(validate(&program).is_empty());
+    assert!(verify(&program, &[]).proven());
+}
+
+#[test]
+fn verify_call_types1() {
+    // The result of the call has the callee's type, not the default i32
+    let source = "//%precondition true
+//%postcondition return_value == 255
+fn max() -> u8 {
+    255
+}
+
+//%precondition true
+//%postcondition return_value == 0
+fn next() -> u8 {
+    let a: u8 = max() + 1;
+    (a)
+}
+";
+    let program = parse("call.rs", source).unwrap();
+    let options = Options {
+        strict_overflow: true,
+        arith: Arith::Bv,
+        ..Default::default()
+    };
+    let report = verify_with(&program, &[], options);
+    assert!(!report.proven());
+    let failed: Vec<Goal> = report.functions[1]
+        .obligations
+        .iter()
+        .filter(|x| x.status == Status::Failed)
+        .map(|x| x.goal)
+        .collect();
+    assert_eq!(failed, vec![Goal::Overflow]);
+}
+
+#[test]
+fn verify_call_precondition1() {
+    // The failing precondition is reported once, not again as the postcondition or the variant
+    let source = "//%precondition x >= 1 && x < 2000
+//%postcondition return_value == x + 1
+fn inc(x: i32) -> i32 {
+    (x + 1)
+}
+
+//%precondition n >= 0 && n < 10
+//%postcondition return_value == n
+fn count(n: i32) -> i32 {
+    let mut a: i32 = 0;
+    //%invariant a == i
+    for i in 0..n {
+        a = inc(a);
+    }
+    a
+}
+
+//%precondition true
+//%postcondition return_value == -1
+fn once() -> i32 {
+    inc(-2)
+}
+";
+    let program = parse("call.rs", source).unwrap();
+    let report = verify_with(
+        &program,
+        &[],
+        Options {
+            keep_going: true,
+            ..Default::default()
+        },
+    );
+    assert!(!report.proven());
+    for f in &report.functions[1..] {
+        let failed: Vec<Goal> = f
+            .obligations
+            .iter()
+            .filter(|x| x.status == Status::Failed)
+            .map(|x| x.goal)
+            .collect();
+        assert_eq!(failed, vec![Goal::CallPrecondition], "{}", f.name);
+    }
+}
@@ -0,0 +1,606 @@
+use crate::ast::*;
+use crate::diagnostic::{self, Diagnostic};
+use std::collections::HashMap;
+
+#[cfg(test)]
+mod tests;
+
+/// Check the types in the program, the mismatches are reported as diagnostics
+pub fn check(input: Program) -> bool {
+    let (_, errors) = typed(input);
+    let ok = errors.is_empty();
+    for i in errors {
+        diagnostic::error(&i.message, i.span);
+    }
+
+    ok
+}
+
+/// The program with all the types resolved, `let` types inferred
+/// and the values turned into ints or bools according to the types they are used as
+pub fn infer(input: Program) -> Program {
+    typed(input).0
+}
+
+fn typed(input: Program) -> (Program, Vec<Diagnostic>) {
+    let mut functions = HashMap::new();
+    for func in input.content.iter() {
+        let inputs = func
+            .input
+            .iter()
+            .map(|x| match x {
+                Binding::Declaration(_, t, _) => t.clone(),
+                _ => Type::Infer,
+            })
+            .collect();
+        functions.insert(func.name.clone(), (inputs, func.output.clone()));
+    }
+
+    let mut errors = Vec::new();
+    let mut content = Vec::new();
+    let source = input.source;
+    for func in input.content {
+        let mut typer = Typer {
+            types: HashMap::new(),
+            functions: &functions,
+            span: func.span,
+            output: func.output.clone(),
+            inferred: HashMap::new(),
+            quantified: Vec::new(),
+            annotation: false,
+            errors: Vec::new(),
+        };
+        content.push(typer.function(func));
+        errors.append(&mut typer.errors);
+    }
+
+    (Program { content, source }, errors)
+}
+
+/// Types of the variables of a single function
+struct Typer<'a> {
+    types: HashMap<String, Type>,
+    /// name -> (input types, output type)
+    functions: &'a HashMap<String, (Vec<Type>, Type)>,
+    /// Place of the command being checked
+    span: Span,
+    /// Type the function returns
+    output: Type,
+    /// Declarations without a type, along with where they are
+    inferred: HashMap<String, Span>,
+    /// Variables of the quantifiers the condition being checked is in, they are ints of any type
+    quantified: Vec<String>,
+    /// Checking an annotation, its ints are unbounded so any of them can be used together
+    annotation: bool,
+    errors: Vec<Diagnostic>,
+}
+
+/// References are used just like the values they point to
+fn base(t: &Type) -> &Type {
+    match t {
+        Type::Reference(a) | Type::ReferenceMutable(a) => base(a),
+        a => a,
+    }
+}
+
+/// Whether the value of type `found` can be used where `expected` is needed
+/// `Type::Infer` stands for the int literals and fits any type
+fn fits(expected: &Type, found: &Type) -> bool {
+    match (base(expected), base(found)) {
+        (Type::Infer, _) | (_, Type::Infer) => true,
+        (Type::Tuple(a), Type::Tuple(b)) => {
+            a.len() == b.len() && a.iter().zip(b.iter()).all(|(a, b)| fits(a, b))
+        }
+        (Type::Array(a, n), Type::Array(b, m)) => n == m && fits(a, b),
+        (Type::ArraySlice(a), Type::Array(b, _)) | (Type::ArraySlice(a), Type::ArraySlice(b)) => {
+            fits(a, b)
+        }
+        (a, b) => a == b,
+    }
+}
+
+/// Type given to the unannotated variables, int literals are i32 like in Rust
+fn concrete(t: Type) -> Type {
+    match t {
+        Type::Infer => Type::I32,
+        Type::Tuple(a) => Type::Tuple(a.into_iter().map(concrete).collect()),
+        Type::Array(a, n) => Type::Array(Box::new(concrete(*a)), n),
+        Type::Reference(a) => Type::Reference(Box::new(concrete(*a))),
+        Type::ReferenceMutable(a) => Type::ReferenceMutable(Box::new(concrete(*a))),
+        a => a,
+    }
+}
+
+/// Turn the value into the form the backends expect for the type,
+/// e.g. the parser can't tell if `x = y;` assigns an int or a bool
+fn coerce(val: Value, t: &Type) -> Value {
+    match (val, base(t)) {
+        (Value::Bool(Bool::Value(v)), t) if t.is_int() => match *v {
+            Value::Expr(e) => Value::Expr(e),
+            v => Value::Expr(Expr::Value(Box::new(v))),
+        },
+        (Value::Expr(Expr::Value(v)), Type::Bool) => match *v {
+            Value::Bool(b) => Value::Bool(b),
+            v => Value::Bool(Bool::Value(Box::new(v))),
+        },
+        (Value::Tuple(vals), Type::Tuple(types)) => Value::Tuple(
+            vals.into_iter()
+                .zip(types.iter())
+                .map(|(v, t)| coerce(v, t))
+                .collect(),
+        ),
+        (Value::Array(vals), Type::Array(t, _)) | (Value::Array(vals), Type::ArraySlice(t)) => {
+            Value::Array(vals.into_iter().map(|v| coerce(v, t)).collect())
+        }
+        (Value::Reference(v), t) => Value::Reference(Box::new(coerce(*v, t))),
+        (Value::ReferenceMutable(v), t) => Value::ReferenceMutable(Box::new(coerce(*v, t))),
+        (v, _) => v,
+    }
+}
+
+/// Whether the code never gets past its end, i.e. it returns on every path or stays in a `loop`
+fn diverges(code: &[Command]) -> bool {
+    code.iter().any(|x| match x {
+        Command::Return(_, _) => true,
+        Command::Block(Block::If(_, comms, el), _) => {
+            !el.is_empty() && diverges(el) && comms.iter().all(|x| diverges(x))
+        }
+        Command::Block(Block::While(Bool::True, comms, _, _), _) => !breaks(comms),
+        _ => false,
+    })
+}
+
+/// Whether the code leaves the loop it's in, the breaks of the nested loops don't count
+pub fn breaks(code: &[Command]) -> bool {
+    code.iter().any(|x| match x {
+        Command::Break(_) => true,
+        Command::Block(Block::If(_, comms, el), _) => breaks(el) || comms.iter().any(|x| breaks(x)),
+        _ => false,
+    })
+}
+
+impl Typer<'_> {
+    fn error(&mut self, message: String) {
+        self.errors.push(Diagnostic {
+            message,
+            span: self.span,
+            source: None,
+        });
+    }
+
+    fn expect(&mut self, expected: &Type, found: &Type) {
+        let ints = self.annotation && base(expected).is_int() && base(found).is_int();
+        if !(ints || fits(expected, found)) {
+            self.error(format!(
+                "mismatched types: expected {}, found {}",
+                expected, found
+            ));
+        }
+    }
+
+    /// Like Rust, reject the int literals that don't fit the type they are used as
+    fn literals(&mut self, t: &Type, val: &Value) {
+        match (val, base(t)) {
+            (Value::Expr(Expr::Number(n)), t) => match t.int_range() {
+                Some((min, max)) if *n < min || *n > max => {
+                    self.error(format!("literal out of range for {}", t))
+                }
+                _ => {}
+            },
+            (Value::Tuple(vals), Type::Tuple(types)) => {
+                for (v, t) in vals.iter().zip(types.iter()) {
+                    self.literals(t, v);
+                }
+            }
+            (Value::Array(vals), Type::Array(t, _)) | (Value::Array(vals), Type::ArraySlice(t)) => {
+                for v in vals {
+                    self.literals(t, v);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn function(&mut self, f: Function) -> Function {
+        for i in f.input.iter() {
+            if let Binding::Declaration(Variable::Named(x), t, _) = i {
+                self.types.insert(x.clone(), t.clone());
+            }
+        }
+
+        // The conditions only see the inputs, their 'old values and the value returned
+        self.annotation(&f.precondition);
+        self.types
+            .insert(String::from("return_value"), f.output.clone());
+        self.annotation(&f.postcondition);
+        self.types.remove("return_value");
+
+        let content = self.commands(f.content);
+        let content = self.resolve(content);
+
+        self.span = f.span;
+        // There's no value at the end when the function never gets there
+        if !diverges(&content) {
+            let found = self.value_type(&f.return_value);
+            self.expect(&f.output, &found);
+            self.literals(&f.output, &f.return_value);
+        }
+        let return_value = coerce(f.return_value, &f.output);
+
+        Function {
+            content,
+            return_value,
+            ..f
+        }
+    }
+
+    fn commands(&mut self, code: Vec<Command>) -> Vec<Command> {
+        code.into_iter().map(|x| self.command(x)).collect()
+    }
+
+    fn command(&mut self, comm: Command) -> Command {
+        let span = comm.span();
+        if span.is_known() {
+            self.span = span;
+        }
+        match comm {
+            Command::Binding(b, _) => Command::Binding(self.binding(b), span),
+            Command::Assignment(a, _) => Command::Assignment(self.assignment(a), span),
+            Command::Block(Block::If(conds, comms, el), _) => {
+                for c in conds.iter() {
+                    self.condition(c);
+                }
+                let comms = comms.into_iter().map(|x| self.commands(x)).collect();
+                Command::Block(Block::If(conds, comms, self.commands(el)), span)
+            }
+            Command::Block(Block::ForRange(iter, first, last, comms, inv), _) => {
+                let t = self.value_type(&first);
+                let u = self.value_type(&last);
+                self.int(&t);
+                self.int(&u);
+                self.expect(&t, &u);
+                let t = match t {
+                    Type::Infer => u,
+                    t => t,
+                };
+                if let Variable::Named(x) = &iter {
+                    self.types.insert(x.clone(), concrete(t));
+                }
+                self.annotation(&inv);
+                let comms = self.commands(comms);
+                Command::Block(Block::ForRange(iter, first, last, comms, inv), span)
+            }
+            Command::Return(val, _) => {
+                let found = self.value_type(&val);
+                let output = self.output.clone();
+                self.expect(&output, &found);
+                self.literals(&output, &val);
+                Command::Return(coerce(val, &output), span)
+            }
+            Command::Block(Block::While(cond, comms, inv, var), _) => {
+                self.condition(&cond);
+                self.annotation(&inv);
+                self.annotation = true;
+                let t = self.expr_type(&var);
+                self.int(&t);
+                self.annotation = false;
+                let comms = self.commands(comms);
+                Command::Block(Block::While(cond, comms, inv, var), span)
+            }
+            Command::ProveControl(ProveControl::Assert(b), _) => {
+                self.annotation(&b);
+                Command::ProveControl(ProveControl::Assert(b), span)
+            }
+            x => x,
+        }
+    }
+
+    fn binding(&mut self, b: Binding) -> Binding {
+        match b {
+            Binding::Declaration(Variable::Named(x), Type::Infer, m) => {
+                self.inferred.insert(x.clone(), self.span);
+                self.types.insert(x.clone(), Type::Infer);
+                Binding::Declaration(Variable::Named(x), Type::Infer, m)
+            }
+            Binding::Declaration(v, t, m) => {
+                if let Variable::Named(x) = &v {
+                    self.types.insert(x.clone(), t.clone());
+                }
+                Binding::Declaration(v, t, m)
+            }
+            Binding::Assignment(v, t, val, m) => {
+                let found = self.value_type(&val);
+                let t = match t {
+                    Type::Infer => concrete(found),
+                    t => {
+                        self.expect(&t, &found);
+                        t
+                    }
+                };
+                if let Variable::Named(x) = &v {
+                    self.types.insert(x.clone(), t.clone());
+                }
+                self.literals(&t, &val);
+                let val = coerce(val, &t);
+                Binding::Assignment(v, t, val, m)
+            }
+            Binding::Tuple(comms) => Binding::Tuple(self.commands(comms)),
+        }
+    }
+
+    fn assignment(&mut self, a: Assignment) -> Assignment {
+        match a {
+            Assignment::Tuple(vec) => {
+                Assignment::Tuple(vec.into_iter().map(|x| self.assignment(x)).collect())
+            }
+            Assignment::Single(var, val) => {
+                let found = self.value_type(&val);
+                let t = match &var {
+                    Variable::Empty => found.clone(),
+                    // The first assignment decides the type of a declaration without one
+                    Variable::Named(x) if self.types.get(x) == Some(&Type::Infer) => {
+                        let t = concrete(found.clone());
+                        self.types.insert(x.clone(), t.clone());
+                        t
+                    }
+                    v => self.value_type(&Value::Variable(v.clone())),
+                };
+                self.expect(&t, &found);
+                self.literals(&t, &val);
+                Assignment::Single(var, coerce(val, &t))
+            }
+        }
+    }
+
+    /// Fill in the types of the declarations given by the later assignments
+    fn resolve(&mut self, code: Vec<Command>) -> Vec<Command> {
+        code.into_iter()
+            .map(|comm| match comm {
+                Command::Binding(
+                    Binding::Declaration(Variable::Named(x), Type::Infer, m),
+                    span,
+                ) => {
+                    let t = self.types.get(&x).cloned().unwrap_or(Type::Infer);
+                    if t == Type::Infer {
+                        if let Some(span) = self.inferred.get(&x) {
+                            self.errors.push(Diagnostic {
+                                message: format!("cannot infer the type of {}", x),
+                                span: *span,
+                                source: None,
+                            });
+                        }
+                    }
+                    Command::Binding(Binding::Declaration(Variable::Named(x), t, m), span)
+                }
+                Command::Binding(Binding::Tuple(comms), span) => {
+                    Command::Binding(Binding::Tuple(self.resolve(comms)), span)
+                }
+                Command::Block(Block::If(conds, comms, el), span) => {
+                    let comms = comms.into_iter().map(|x| self.resolve(x)).collect();
+                    Command::Block(Block::If(conds, comms, self.resolve(el)), span)
+                }
+                Command::Block(Block::ForRange(iter, first, last, comms, inv), span) => {
+                    let comms = self.resolve(comms);
+                    Command::Block(Block::ForRange(iter, first, last, comms, inv), span)
+                }
+                Command::Block(Block::While(cond, comms, inv, var), span) => {
+                    let comms = self.resolve(comms);
+                    Command::Block(Block::While(cond, comms, inv, var), span)
+                }
+                x => x,
+            })
+            .collect()
+    }
+
+    fn int(&mut self, t: &Type) {
+        let t = base(t);
+        if *t != Type::Infer && !t.is_int() {
+            self.error(format!(
+                "mismatched types: expected an integer, found {}",
+                t
+            ));
+        }
+    }
+
+    fn condition(&mut self, b: &Bool) {
+        let t = self.bool_type(b);
+        self.expect(&Type::Bool, &t);
+    }
+
+    fn annotation(&mut self, b: &Bool) {
+        self.annotation = true;
+        self.condition(b);
+        self.annotation = false;
+    }
+
+    fn bool_type(&mut self, b: &Bool) -> Type {
+        match b {
+            Bool::And(a, b) | Bool::Or(a, b) => {
+                self.condition(a);
+                self.condition(b);
+                Type::Bool
+            }
+            Bool::Not(a) | Bool::Located(a, _) => {
+                self.condition(a);
+                Type::Bool
+            }
+            // Just a wrapped value, it can as well be an int
+            Bool::Value(v) => self.value_type(v),
+            Bool::Equal(a, b) => {
+                let t = self.expr_type(a);
+                let u = self.expr_type(b);
+                if self.int_expr(a) {
+                    self.int(&u);
+                }
+                if self.int_expr(b) {
+                    self.int(&t);
+                }
+                self.expect(&t, &u);
+                Type::Bool
+            }
+            Bool::GreaterEqual(a, b)
+            | Bool::LowerEqual(a, b)
+            | Bool::GreaterThan(a, b)
+            | Bool::LowerThan(a, b) => {
+                let t = self.expr_type(a);
+                let u = self.expr_type(b);
+                self.int(&t);
+                self.int(&u);
+                self.expect(&t, &u);
+                Type::Bool
+            }
+            Bool::ValueEqual(a, b) => {
+                let t = self.value_type(a);
+                let u = self.value_type(b);
+                self.expect(&t, &u);
+                Type::Bool
+            }
+            Bool::ForAll(v, b) | Bool::Exists(v, b) => {
+                if let Variable::Named(x) = v {
+                    self.quantified.push(x.clone());
+                }
+                self.condition(b);
+                if let Variable::Named(_) = v {
+                    self.quantified.pop();
+                }
+                Type::Bool
+            }
+            Bool::True | Bool::False => Type::Bool,
+        }
+    }
+
+    /// Whether the expression can only be an int, e.g. a literal or a quantified variable,
+    /// their `Type::Infer` would fit a bool too
+    fn int_expr(&self, e: &Expr) -> bool {
+        match e {
+            Expr::Number(_) | Expr::Op(_, _, _) => true,
+            Expr::Value(v) => {
+                matches!(&**v, Value::Variable(Variable::Named(x)) if self.quantified.contains(x))
+            }
+        }
+    }
+
+    /// Type of the variable or of the one its 'old value is taken of
+    fn declared(&self, name: &str) -> Option<&Type> {
+        if self.quantified.iter().any(|x| x == name) {
+            return Some(&Type::Infer);
+        }
+        self.types
+            .get(name)
+            .or_else(|| self.types.get(&name.replacen("'old", "", 1)))
+    }
+
+    fn expr_type(&mut self, e: &Expr) -> Type {
+        match e {
+            Expr::Number(_) => Type::Infer,
+            Expr::Value(v) => self.value_type(v),
+            Expr::Op(a, _, b) => {
+                let t = self.expr_type(a);
+                let u = self.expr_type(b);
+                self.int(&t);
+                self.int(&u);
+                self.expect(&t, &u);
+                // The result is an int even if an operand isn't, that one is reported already
+                match (base(&t), base(&u)) {
+                    (t, _) if t.is_int() => t.clone(),
+                    (_, u) if u.is_int() => u.clone(),
+                    _ => Type::Infer,
+                }
+            }
+        }
+    }
+
+    /// Type of the array's elements, `x.0` stands for the first elements of an array of tuples
+    fn array_elem_type(&self, name: &str) -> Type {
+        let (name, component) = match name.split_once('.') {
+            Some((a, i)) => (a, i.parse::<usize>().ok()),
+            None => (name, None),
+        };
+        let elem = self
+            .declared(name)
+            .and_then(|x| x.array_elem())
+            .cloned()
+            .unwrap_or(Type::Infer);
+        match (elem, component) {
+            (Type::Tuple(types), Some(i)) => types.get(i).cloned().unwrap_or(Type::Infer),
+            (elem, _) => elem,
+        }
+    }
+
+    fn variable_type(&mut self, v: &Variable) -> Type {
+        match v {
+            Variable::Named(x) => self.declared(x).cloned().unwrap_or(Type::Infer),
+            Variable::Empty => Type::Infer,
+            Variable::Length(_) => Type::Usize,
+            Variable::ArrayElem(name, ind) => {
+                let t = self.value_type(ind);
+                self.int(&t);
+                self.array_elem_type(name)
+            }
+            Variable::TupleElem(name, ind) => {
+                let types = match self.declared(name).map(base) {
+                    Some(Type::Tuple(types)) => types.clone(),
+                    _ => return Type::Infer,
+                };
+                match ind.as_ref() {
+                    Value::Expr(Expr::Number(i)) => {
+                        types.get(*i as usize).cloned().unwrap_or(Type::Infer)
+                    }
+                    _ => Type::Infer,
+                }
+            }
+        }
+    }
+
+    /// `Type::Infer` if the type can't be told, e.g. for the int literals
+    fn value_type(&mut self, v: &Value) -> Type {
+        match v {
+            Value::Expr(e) => self.expr_type(e),
+            Value::Bool(b) => self.bool_type(b),
+            Value::Variable(x) => self.variable_type(x),
+            Value::Tuple(vals) => Type::Tuple(vals.iter().map(|x| self.value_type(x)).collect()),
+            Value::Array(vals) => {
+                let mut elem = Type::Infer;
+                for i in vals {
+                    let t = self.value_type(i);
+                    self.expect(&elem, &t);
+                    if elem == Type::Infer {
+                        elem = t;
+                    }
+                }
+                Type::Array(Box::new(elem), vals.len() as i32)
+            }
+            Value::FunctionCall(name, args) => {
+                let (inputs, output) = match self.functions.get(name) {
+                    Some(a) => a.clone(),
+                    None => return Type::Infer,
+                };
+                if inputs.len() != args.len() {
+                    self.error(format!(
+                        "function {} takes {} arguments, {} given",
+                        name,
+                        inputs.len(),
+                        args.len()
+                    ));
+                }
+                for (t, arg) in inputs.iter().zip(args.iter()) {
+                    let found = self.value_type(arg);
+                    self.expect(t, &found);
+                    self.literals(t, arg);
+                }
+                output
+            }
+            Value::Dereference(a) => base(&self.value_type(a)).clone(),
+            Value::Reference(a) => Type::Reference(Box::new(self.value_type(a))),
+            Value::ReferenceMutable(a) => Type::ReferenceMutable(Box::new(self.value_type(a))),
+            Value::Unit => Type::Unit,
+            Value::Ternary(c, a, b) => {
+                self.condition(c);
+                let t = self.value_type(a);
+                let u = self.value_type(b);
+                self.expect(&t, &u);
+                t
+            }
+        }
+    }
+}
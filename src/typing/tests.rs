@@ -0,0 +1,285 @@
+use crate::parser;
+use crate::typing::*;
+
+fn program(source: &str) -> Program {
+    let (rest, program) = parser::program(source).unwrap();
+    assert_eq!(rest, "");
+    program
+}
+
+fn errors(source: &str) -> Vec<String> {
+    typed(program(source))
+        .1
+        .into_iter()
+        .map(|x| x.message)
+        .collect()
+}
+
+fn named(x: &str) -> Box<Value> {
+    Box::new(Value::Variable(Variable::Named(String::from(x))))
+}
+
+#[test]
+fn fits1() {
+    assert!(fits(&Type::U8, &Type::Infer));
+    assert!(fits(&Type::I32, &Type::Reference(Box::new(Type::I32))));
+    assert!(fits(
+        &Type::ArraySlice(Box::new(Type::Bool)),
+        &Type::Array(Box::new(Type::Bool), 3)
+    ));
+    assert!(!fits(&Type::I32, &Type::I64));
+    assert!(!fits(&Type::Bool, &Type::Usize));
+    assert!(!fits(
+        &Type::Tuple(vec![Type::I32, Type::Bool]),
+        &Type::Tuple(vec![Type::I32, Type::I32])
+    ));
+}
+
+#[test]
+fn concrete1() {
+    assert_eq!(concrete(Type::Infer), Type::I32);
+    assert_eq!(
+        concrete(Type::Tuple(vec![Type::Infer, Type::Bool])),
+        Type::Tuple(vec![Type::I32, Type::Bool])
+    );
+}
+
+#[test]
+fn coerce1() {
+    assert_eq!(
+        coerce(Value::Bool(Bool::Value(named("x"))), &Type::U8),
+        Value::Expr(Expr::Value(named("x")))
+    );
+    assert_eq!(
+        coerce(Value::Expr(Expr::Value(named("x"))), &Type::Bool),
+        Value::Bool(Bool::Value(named("x")))
+    );
+    assert_eq!(
+        coerce(
+            Value::Tuple(vec![
+                Value::Bool(Bool::Value(named("x"))),
+                Value::Expr(Expr::Value(named("y")))
+            ]),
+            &Type::Tuple(vec![Type::I32, Type::Bool])
+        ),
+        Value::Tuple(vec![
+            Value::Expr(Expr::Value(named("x"))),
+            Value::Bool(Bool::Value(named("y")))
+        ])
+    );
+}
+
+#[test]
+fn infer1() {
+    let p = infer(program(
+        "fn a(x: u8, c: bool) {\n    let y = x + 1;\n    let b = c;\n    let t = (1, b);\n}\n",
+    ));
+    let types: Vec<Type> = p.content[0]
+        .content
+        .iter()
+        .filter_map(|x| match x {
+            Command::Binding(Binding::Assignment(_, t, _, _), _) => Some(t.clone()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        types,
+        vec![
+            Type::U8,
+            Type::Bool,
+            Type::Tuple(vec![Type::I32, Type::Bool])
+        ]
+    );
+}
+
+#[test]
+fn infer_declaration1() {
+    let p = infer(program(
+        "fn a(x: i64) {\n    let mut y;\n    if x > 0 {\n        y = x;\n    } else {\n        y = 0;\n    }\n}\n",
+    ));
+    assert!(p.content[0]
+        .content
+        .iter()
+        .any(|x| x.same_code(&Command::Binding(
+            Binding::Declaration(Variable::Named(String::from("y")), Type::I64, true),
+            Span::default()
+        ))));
+}
+
+#[test]
+fn infer_assignment1() {
+    // Parsed as a bool first, it's an int because of the variable it's assigned to
+    let p = infer(program(
+        "fn a(x: i32) {\n    let mut y: i32 = 0;\n    y = x;\n}\n",
+    ));
+    assert!(p.content[0]
+        .content
+        .iter()
+        .any(|x| x.same_code(&Command::Assignment(
+            Assignment::Single(
+                Variable::Named(String::from("y")),
+                Value::Expr(Expr::Value(named("x")))
+            ),
+            Span::default()
+        ))));
+}
+
+#[test]
+fn mismatch1() {
+    assert_eq!(
+        errors("fn a(x: i32) {\n    let b: bool = x;\n}\n"),
+        vec!["mismatched types: expected bool, found i32"]
+    );
+    assert_eq!(
+        errors("fn a(x: i32, y: u8) {\n    let z: i32 = x + y;\n}\n"),
+        vec!["mismatched types: expected i32, found u8"]
+    );
+    assert_eq!(
+        errors("fn a(x: i32) {\n    let mut y: i32 = 0;\n    if x {\n        y = 1;\n    }\n}\n"),
+        vec!["mismatched types: expected bool, found i32"]
+    );
+}
+
+#[test]
+fn mismatch_call1() {
+    assert_eq!(
+        errors("fn f(x: u8) -> u8 {\n    x\n}\nfn a(b: bool) {\n    let y: u8 = f(b);\n}\n"),
+        vec!["mismatched types: expected u8, found bool"]
+    );
+    assert_eq!(
+        errors("fn f(x: u8) -> u8 {\n    x\n}\nfn a() {\n    let y: u8 = f(1, 2);\n}\n"),
+        vec!["function f takes 1 arguments, 2 given"]
+    );
+}
+
+#[test]
+fn mismatch_return1() {
+    assert_eq!(
+        errors("fn a(x: i32) -> bool {\n    x\n}\n"),
+        vec!["mismatched types: expected bool, found i32"]
+    );
+    assert_eq!(
+        errors("fn a(x: i32) -> bool {\n    if x > 0 {\n        return x;\n    }\n    true\n}\n"),
+        vec!["mismatched types: expected bool, found i32"]
+    );
+}
+
+#[test]
+fn mismatch_return2() {
+    assert!(errors("fn a(x: i32) -> i32 {\n    return x;\n}\n").is_empty());
+    assert_eq!(
+        errors("fn a(x: i32) -> i32 {\n    let y: i32 = x;\n}\n"),
+        vec!["mismatched types: expected i32, found ()"]
+    );
+}
+
+#[test]
+fn mismatch_annotation1() {
+    let int = "mismatched types: expected an integer, found bool";
+    assert_eq!(
+        errors("//%precondition true\n//%postcondition return_value == 1\nfn a() -> bool {\n    true\n}\n"),
+        vec![int]
+    );
+    assert_eq!(
+        errors("//%precondition true\n//%postcondition return_value.0 == 1\nfn a() -> (bool, i32) {\n    (true, 1)\n}\n"),
+        vec![int]
+    );
+    assert_eq!(
+        errors("//%precondition true\n//%postcondition true\nfn a(b: bool) {\n    //%assert b == 1\n    let x: i32 = 0;\n}\n"),
+        vec![int]
+    );
+    assert_eq!(
+        errors("//%precondition x\n//%postcondition true\nfn a(x: i32) {\n}\n"),
+        vec!["mismatched types: expected bool, found i32"]
+    );
+    assert_eq!(
+        errors("//%precondition b + 1 > 0\n//%postcondition true\nfn a(b: bool) {\n}\n"),
+        vec![int]
+    );
+    assert_eq!(
+        errors("//%precondition forall k b == k\n//%postcondition true\nfn a(b: bool) {\n}\n"),
+        vec![int]
+    );
+}
+
+#[test]
+fn mismatch_annotation2() {
+    // The loops' invariants are conditions and their variants are ints
+    assert_eq!(
+        errors("fn a(n: i32) {\n    let mut i: i32 = 0;\n    //%invariant i\n    //%variant n - i\n    while i < n {\n        i = i + 1;\n    }\n}\n"),
+        vec!["mismatched types: expected bool, found i32"]
+    );
+    assert_eq!(
+        errors("fn a(b: bool) {\n    let mut i: i32 = 0;\n    //%invariant i >= 0\n    //%variant b\n    while i < 5 {\n        i = i + 1;\n    }\n}\n"),
+        vec!["mismatched types: expected an integer, found bool"]
+    );
+    assert_eq!(
+        errors("fn a(n: i32) {\n    //%invariant n\n    for i in 0..n {\n    }\n}\n"),
+        vec!["mismatched types: expected bool, found i32"]
+    );
+}
+
+#[test]
+fn annotation_ints1() {
+    // The ints of the annotations are unbounded, the quantified ones and the 'old values have types too
+    assert!(errors(
+        "//%precondition n <= x.len() && forall k !(k >= 0 && k < n) || x[k] == k\n//%postcondition x[0] == x'old[0] && n == n'old\nfn a(x: &mut [i32], n: usize) {\n    let mut i: usize = 0;\n    let mut j: i32 = 0;\n    //%assert i == j\n    let y: i32 = 0;\n}\n"
+    )
+    .is_empty());
+}
+
+#[test]
+fn literal_range1() {
+    assert_eq!(
+        errors(
+            "fn a() {\n    let x: u8 = 256;\n    let y: i8 = -129;\n    let z = 2147483648;\n}\n"
+        ),
+        vec![
+            "literal out of range for u8",
+            "literal out of range for i8",
+            "literal out of range for i32"
+        ]
+    );
+    assert_eq!(
+        errors("fn f(x: u8) -> u8 {\n    x\n}\nfn a() -> u16 {\n    let t: (u8, bool) = (255, true);\n    let mut a: [i8; 2] = [-128, 127];\n    a[0] = 128;\n    let y: u8 = f(-1);\n    65536\n}\n"),
+        vec![
+            "literal out of range for i8",
+            "literal out of range for u8",
+            "literal out of range for u16"
+        ]
+    );
+}
+
+#[test]
+fn diverges1() {
+    assert!(errors(
+        "fn a(x: i32) -> i32 {\n    if x > 0 {\n        return x;\n    } else {\n        return 0;\n    }\n}\n"
+    )
+    .is_empty());
+    assert_eq!(
+        errors("fn a(x: i32) -> i32 {\n    if x > 0 {\n        return x;\n    }\n}\n"),
+        vec!["mismatched types: expected i32, found ()"]
+    );
+}
+
+#[test]
+fn diverges2() {
+    assert!(errors(
+        "fn a(x: i32) -> i32 {\n    //%invariant true\n    loop {\n        if x > 0 {\n            return x;\n        }\n    }\n}\n"
+    )
+    .is_empty());
+    assert_eq!(
+        errors(
+            "fn a(x: i32) -> i32 {\n    //%invariant true\n    loop {\n        if x > 0 {\n            break;\n        }\n    }\n}\n"
+        ),
+        vec!["mismatched types: expected i32, found ()"]
+    );
+}
+
+#[test]
+fn not_inferred1() {
+    assert_eq!(
+        errors("fn a() {\n    let y;\n}\n"),
+        vec!["cannot infer the type of y"]
+    );
+}
@@ -1,11 +1,277 @@
 use crate::ast::*;
+use crate::diagnostic;
 
 mod tests;
 
 static FORBIDDEN_DECS: [&'static str; 1] = ["return_value"];
 
 pub fn validate(input: Program) -> bool {
-    no_shadowing(input.clone()) && no_forbidden_decs(input.clone()) && no_undefined(input)
+    no_shadowing(input.clone())
+        && no_forbidden_decs(input.clone())
+        && no_undefined(input.clone())
+        && no_unsupported_calls(input.clone())
+        && no_unsupported_references(input)
+}
+
+/// Check for function calls where the prover can't put the callee's contract
+/// (loop conditions and annotations, they are evaluated more than once or not at all)
+fn no_unsupported_calls(input: Program) -> bool {
+    for func in input.content {
+        for b in [func.precondition, func.postcondition].iter() {
+            if !no_call_check(
+                get_namedecs(b.clone()),
+                "function call in an annotation",
+                func.span,
+            ) {
+                return false;
+            }
+        }
+        if !no_unsupported_calls_logic(func.content) {
+            return false;
+        }
+    }
+
+    true
+}
+
+fn no_call_check(decs: Vec<Namedec>, what: &str, span: Span) -> bool {
+    let called = decs.into_iter().any(|x| matches!(x, Namedec::Name(..)));
+    if called {
+        diagnostic::error(&format!("unsupported construct: {}", what), span);
+    }
+    !called
+}
+
+fn no_unsupported_calls_logic(content: Vec<Command>) -> bool {
+    for comm in content {
+        let span = comm.span();
+        match comm {
+            Command::ProveControl(ProveControl::Assert(a), _)
+                if !no_call_check(
+                    get_namedecs(a.clone()),
+                    "function call in an annotation",
+                    span,
+                ) =>
+            {
+                return false;
+            }
+            Command::Block(Block::If(_, blocks, el), _) => {
+                let mut temp = blocks;
+                temp.push(el);
+
+                for block in temp {
+                    if !no_unsupported_calls_logic(block) {
+                        return false;
+                    }
+                }
+            }
+            Command::Block(Block::ForRange(_, _, _, vec, a), _) => {
+                if !no_call_check(get_namedecs(a), "function call in an annotation", span) {
+                    return false;
+                }
+
+                if !no_unsupported_calls_logic(vec) {
+                    return false;
+                }
+            }
+            Command::Block(Block::While(cond, vec, a, var), _) => {
+                if !no_call_check(
+                    get_namedecs(cond),
+                    "function call in a loop condition",
+                    span,
+                ) {
+                    return false;
+                }
+                let mut decs = Vec::new();
+                _get_namedecs_expr(var, &mut decs);
+                if !no_call_check(get_namedecs(a), "function call in an annotation", span)
+                    || !no_call_check(decs, "function call in an annotation", span)
+                {
+                    return false;
+                }
+
+                if !no_unsupported_calls_logic(vec) {
+                    return false;
+                }
+            }
+
+            _ => {}
+        }
+    }
+
+    true
+}
+
+/// Check for the borrows the prover can't follow, the references are replaced with the borrowed variables
+/// (so only whole variables can be borrowed into them and they can't be pointed anywhere else later)
+fn no_unsupported_references(input: Program) -> bool {
+    for func in input.content.iter() {
+        let mut references = Vec::new();
+        if !no_unsupported_references_logic(func.content.clone(), &input.content, &mut references)
+            || !no_unsupported_borrows(func.return_value.clone(), &input.content, func.span)
+        {
+            return false;
+        }
+    }
+
+    true
+}
+
+fn no_unsupported_references_logic(
+    content: Vec<Command>,
+    functions: &[Function],
+    references: &mut Vec<String>,
+) -> bool {
+    for comm in content {
+        let span = comm.span();
+        let mut values = Vec::new();
+        match comm {
+            Command::Binding(Binding::Assignment(var, t, val, _), _) => {
+                let aliased = match &val {
+                    Value::ReferenceMutable(v) => {
+                        if !matches!(**v, Value::Variable(Variable::Named(_))) {
+                            diagnostic::error(
+                                "unsupported construct: mutable borrow of something other than a variable",
+                                span,
+                            );
+                            return false;
+                        }
+                        true
+                    }
+                    Value::Reference(v) => matches!(**v, Value::Variable(Variable::Named(_))),
+                    // Reborrow of a reference, the type can still be left to infer here
+                    Value::Variable(Variable::Named(_)) => matches!(
+                        t,
+                        Type::Reference(_) | Type::ReferenceMutable(_) | Type::Infer
+                    ),
+                    _ => false,
+                };
+                if let (true, Variable::Named(x)) = (aliased, var) {
+                    references.push(x);
+                }
+                values.push(val);
+            }
+            Command::Binding(Binding::Tuple(vec), _)
+                if !no_unsupported_references_logic(vec.clone(), functions, references) =>
+            {
+                return false;
+            }
+            Command::Assignment(a, _) => {
+                let mut temp = vec![a];
+                while let Some(a) = temp.pop() {
+                    match a {
+                        Assignment::Single(Variable::Named(x), v)
+                            if references.contains(&x)
+                                && matches!(
+                                    v,
+                                    Value::Reference(_) | Value::ReferenceMutable(_)
+                                ) =>
+                        {
+                            diagnostic::error(
+                                &format!(
+                                    "unsupported construct: borrow assigned to the reference {}",
+                                    x
+                                ),
+                                span,
+                            );
+                            return false;
+                        }
+                        Assignment::Single(_, v) => values.push(v),
+                        Assignment::Tuple(vec) => temp.extend(vec),
+                    }
+                }
+            }
+            Command::Block(Block::If(conds, blocks, el), _) => {
+                values.extend(conds.into_iter().map(Value::Bool));
+                let mut temp = blocks;
+                temp.push(el);
+
+                for block in temp {
+                    if !no_unsupported_references_logic(block, functions, &mut references.clone()) {
+                        return false;
+                    }
+                }
+            }
+            Command::Block(Block::ForRange(_, first, last, vec, _), _) => {
+                values.push(first);
+                values.push(last);
+                if !no_unsupported_references_logic(vec, functions, &mut references.clone()) {
+                    return false;
+                }
+            }
+            Command::Block(Block::While(_, vec, _, _), _)
+                if !no_unsupported_references_logic(
+                    vec.clone(),
+                    functions,
+                    &mut references.clone(),
+                ) =>
+            {
+                return false;
+            }
+            Command::Return(val, _) => values.push(val),
+            _ => {}
+        }
+
+        for val in values {
+            if !no_unsupported_borrows(val, functions, span) {
+                return false;
+            }
+        }
+    }
+
+    true
+}
+
+/// The parameters of the called functions have to be the ones their contracts can be put in place of,
+/// and only variables can be mutably borrowed into them
+fn no_unsupported_borrows(val: Value, functions: &[Function], span: Span) -> bool {
+    let supported = |t: &Type| *t == Type::Bool || t.is_int() || matches!(t, Type::Tuple(_));
+    let mut decs = Vec::new();
+    _get_namedecs_val(val, &mut decs);
+    for dec in decs {
+        let (name, args) = match dec {
+            Namedec::Name(name, args) => (name, args),
+            Namedec::Variable(_) => continue,
+        };
+        let callee = match functions.iter().find(|x| x.name == name) {
+            Some(x) => x,
+            None => continue,
+        };
+        for (param, arg) in callee.input.iter().zip(args) {
+            let (x, t) = match param {
+                Binding::Declaration(Variable::Named(x), t, _) => (x, t),
+                _ => continue,
+            };
+            let ok = match t {
+                Type::Reference(t) => supported(t),
+                Type::ReferenceMutable(t) => {
+                    if let Value::ReferenceMutable(v) = arg {
+                        if !matches!(*v, Value::Variable(_)) {
+                            diagnostic::error(
+                                "unsupported construct: mutable borrow of something other than a variable",
+                                span,
+                            );
+                            return false;
+                        }
+                    }
+                    supported(t)
+                }
+                t => supported(t),
+            };
+            if !ok {
+                diagnostic::error(
+                    &format!(
+                        "unsupported construct: call to {} with the parameter {} of type {}",
+                        name, x, t
+                    ),
+                    span,
+                );
+                return false;
+            }
+        }
+    }
+
+    true
 }
 
 /// Check for using undefined variables in ProveCommands
@@ -42,7 +308,8 @@ fn no_undefined(input: Program) -> bool {
 
 enum Namedec {
     Variable(Variable),
-    Name(String),
+    /// Called function along with the arguments
+    Name(String, Vec<Value>),
 }
 
 /// Unpack the bool and get all the variables that are used in it
@@ -72,7 +339,7 @@ fn _get_namedecs_bool(z: Bool, mut decs: &mut Vec<Namedec>) {
             _get_namedecs_bool(*a, &mut decs);
             _get_namedecs_bool(*b, &mut decs);
         }
-        Bool::Not(a) => {
+        Bool::Not(a) | Bool::Located(a, _) => {
             _get_namedecs_bool(*a, &mut decs);
         }
         Bool::Value(a) => {
@@ -127,7 +394,7 @@ fn _get_namedecs_val(z: Value, mut decs: &mut Vec<Namedec>) {
             }
         }
         Value::FunctionCall(name, a) => {
-            decs.push(Namedec::Name(name));
+            decs.push(Namedec::Name(name, a.clone()));
             for i in a {
                 _get_namedecs_val(i, &mut decs);
             }
@@ -175,6 +442,7 @@ fn no_undefined_check(
     definitions: &mut Vec<String>,
     functions: &Vec<String>,
     val: Namedec,
+    span: Span,
 ) -> bool {
     match val {
         Namedec::Variable(t) => {
@@ -182,16 +450,17 @@ fn no_undefined_check(
                 Variable::Named(a) => a,
                 Variable::ArrayElem(a, _) => a,
                 Variable::TupleElem(a, _) => a,
+                Variable::Length(a) => a,
                 _ => return true,
             };
             if !definitions.iter().any(|i| *i == name) {
-                println!("Undefined variable used: {}", name);
+                diagnostic::error(&format!("undefined variable used: {}", name), span);
                 return false;
             }
         }
-        Namedec::Name(name) => {
+        Namedec::Name(name, _) => {
             if !functions.iter().any(|i| *i == name) {
-                println!("Undefined function used: {}", name);
+                diagnostic::error(&format!("undefined function used: {}", name), span);
                 return false;
             }
         }
@@ -206,39 +475,40 @@ fn no_undefined_logic(
     functions: &Vec<String>,
 ) -> bool {
     for comm in content {
+        let span = comm.span();
         match comm {
-            Command::ProveControl(ProveControl::Assert(a)) => {
+            Command::ProveControl(ProveControl::Assert(a), _) => {
                 match a.clone() {
                     Bool::ForAll(v, _) => def_push(&mut definitions, v),
                     Bool::Exists(v, _) => def_push(&mut definitions, v),
                     _ => {}
                 };
                 for i in get_namedecs(a) {
-                    if !no_undefined_check(&mut definitions, &functions, i) {
+                    if !no_undefined_check(&mut definitions, &functions, i, span) {
                         return false;
                     }
                 }
             }
-            Command::Binding(Binding::Declaration(name, _, _)) => {
+            Command::Binding(Binding::Declaration(name, _, _), _) => {
                 def_push(&mut definitions, name);
             }
-            Command::Binding(Binding::Assignment(name, _, _, _)) => {
+            Command::Binding(Binding::Assignment(name, _, _, _), _) => {
                 def_push(&mut definitions, name);
             }
-            Command::Binding(Binding::Tuple(vec)) => {
+            Command::Binding(Binding::Tuple(vec), _) => {
                 for dec in vec {
                     match dec {
-                        Command::Binding(Binding::Declaration(name, _, _)) => {
+                        Command::Binding(Binding::Declaration(name, _, _), _) => {
                             def_push(&mut definitions, name);
                         }
-                        Command::Binding(Binding::Assignment(name, _, _, _)) => {
+                        Command::Binding(Binding::Assignment(name, _, _, _), _) => {
                             def_push(&mut definitions, name);
                         }
                         _ => {}
                     }
                 }
             }
-            Command::Block(Block::If(_, blocks, el)) => {
+            Command::Block(Block::If(_, blocks, el), _) => {
                 let mut temp = blocks;
                 temp.push(el);
 
@@ -249,20 +519,27 @@ fn no_undefined_logic(
                     }
                 }
             }
-            Command::Block(Block::ForRange(_, _, _, vec, a)) => {
+            Command::Block(Block::ForRange(iter, _, _, vec, a), _) => {
                 let mut temp = definitions.clone();
+                // The iterator is only visible inside of the loop
+                def_push(&mut temp, iter);
 
-                if !no_undefined_logic(vec, &mut temp, &functions) {
-                    return false;
-                }
-
+                match a.clone() {
+                    Bool::ForAll(v, _) => def_push(&mut temp, v),
+                    Bool::Exists(v, _) => def_push(&mut temp, v),
+                    _ => {}
+                };
                 for i in get_namedecs(a) {
-                    if !no_undefined_check(&mut definitions, &functions, i) {
+                    if !no_undefined_check(&mut temp, functions, i, span) {
                         return false;
                     }
                 }
+
+                if !no_undefined_logic(vec, &mut temp, &functions) {
+                    return false;
+                }
             }
-            Command::Block(Block::While(_, vec, _a, _var)) => {
+            Command::Block(Block::While(_, vec, _a, _var), _) => {
                 let mut temp = definitions.clone();
 
                 if !no_undefined_logic(vec, &mut temp, &functions) {
@@ -299,18 +576,18 @@ fn no_forbidden_decs(input: Program) -> bool {
     true
 }
 
-fn no_forbidden_decs_check(val: Variable) -> bool {
+fn no_forbidden_decs_check(val: Variable, span: Span) -> bool {
     match val {
         Variable::Named(a) => {
             if !FORBIDDEN_DECS.contains(&a.as_str()) {
                 if !a.ends_with("'old") {
                     true
                 } else {
-                    println!("'old variable used in binding: {}", a);
+                    diagnostic::error(&format!("'old variable used in binding: {}", a), span);
                     false
                 }
             } else {
-                println!("Keyword variable used in binding: {}", a);
+                diagnostic::error(&format!("keyword variable used in binding: {}", a), span);
                 false
             }
         }
@@ -320,27 +597,28 @@ fn no_forbidden_decs_check(val: Variable) -> bool {
 
 fn no_forbidden_decs_logic(content: Vec<Command>) -> bool {
     for comm in content {
+        let span = comm.span();
         match comm {
-            Command::Binding(Binding::Declaration(name, _, _)) => {
-                if !no_forbidden_decs_check(name) {
+            Command::Binding(Binding::Declaration(name, _, _), _) => {
+                if !no_forbidden_decs_check(name, span) {
                     return false;
                 }
             }
-            Command::Binding(Binding::Assignment(name, _, _, _)) => {
-                if !no_forbidden_decs_check(name) {
+            Command::Binding(Binding::Assignment(name, _, _, _), _) => {
+                if !no_forbidden_decs_check(name, span) {
                     return false;
                 }
             }
-            Command::Binding(Binding::Tuple(vec)) => {
+            Command::Binding(Binding::Tuple(vec), _) => {
                 for dec in vec {
                     match dec {
-                        Command::Binding(Binding::Declaration(name, _, _)) => {
-                            if !no_forbidden_decs_check(name) {
+                        Command::Binding(Binding::Declaration(name, _, _), _) => {
+                            if !no_forbidden_decs_check(name, span) {
                                 return false;
                             }
                         }
-                        Command::Binding(Binding::Assignment(name, _, _, _)) => {
-                            if !no_forbidden_decs_check(name) {
+                        Command::Binding(Binding::Assignment(name, _, _, _), _) => {
+                            if !no_forbidden_decs_check(name, span) {
                                 return false;
                             }
                         }
@@ -348,7 +626,7 @@ fn no_forbidden_decs_logic(content: Vec<Command>) -> bool {
                     }
                 }
             }
-            Command::Block(Block::If(_, blocks, el)) => {
+            Command::Block(Block::If(_, blocks, el), _) => {
                 let mut temp = blocks;
                 temp.push(el);
 
@@ -358,8 +636,8 @@ fn no_forbidden_decs_logic(content: Vec<Command>) -> bool {
                     }
                 }
             }
-            Command::Block(Block::ForRange(iter, _, _, vec, _)) => {
-                if !no_forbidden_decs_check(iter) {
+            Command::Block(Block::ForRange(iter, _, _, vec, _), _) => {
+                if !no_forbidden_decs_check(iter, span) {
                     return false;
                 }
 
@@ -367,7 +645,7 @@ fn no_forbidden_decs_logic(content: Vec<Command>) -> bool {
                     return false;
                 }
             }
-            Command::Block(Block::While(_, vec, _, _)) => {
+            Command::Block(Block::While(_, vec, _, _), _) => {
                 if !no_forbidden_decs_logic(vec) {
                     return false;
                 }
@@ -403,7 +681,7 @@ fn no_shadowing(input: Program) -> bool {
     true
 }
 
-fn no_shadowing_check(definitions: &mut Vec<String>, val: Variable) -> bool {
+fn no_shadowing_check(definitions: &mut Vec<String>, val: Variable, span: Span) -> bool {
     let name = match val {
         Variable::Named(a) => a,
         _ => return true,
@@ -411,7 +689,7 @@ fn no_shadowing_check(definitions: &mut Vec<String>, val: Variable) -> bool {
     log::trace!("{:?}", definitions);
 
     if definitions.iter().any(|i| *i == name) {
-        println!("Variable redeclared: {}", name);
+        diagnostic::error(&format!("variable redeclared: {}", name), span);
         return false;
     } else {
         definitions.push(name)
@@ -424,27 +702,28 @@ fn no_shadowing_logic(content: Vec<Command>, mut definitions: &mut Vec<String>)
         log::trace!("{}", x);
     }
     for comm in content {
+        let span = comm.span();
         match comm {
-            Command::Binding(Binding::Declaration(name, _, _)) => {
-                if !no_shadowing_check(&mut definitions, name) {
+            Command::Binding(Binding::Declaration(name, _, _), _) => {
+                if !no_shadowing_check(&mut definitions, name, span) {
                     return false;
                 }
             }
-            Command::Binding(Binding::Assignment(name, _, _, _)) => {
-                if !no_shadowing_check(&mut definitions, name) {
+            Command::Binding(Binding::Assignment(name, _, _, _), _) => {
+                if !no_shadowing_check(&mut definitions, name, span) {
                     return false;
                 }
             }
-            Command::Binding(Binding::Tuple(vec)) => {
+            Command::Binding(Binding::Tuple(vec), _) => {
                 for dec in vec {
                     match dec {
-                        Command::Binding(Binding::Declaration(name, _, _)) => {
-                            if !no_shadowing_check(&mut definitions, name) {
+                        Command::Binding(Binding::Declaration(name, _, _), _) => {
+                            if !no_shadowing_check(&mut definitions, name, span) {
                                 return false;
                             }
                         }
-                        Command::Binding(Binding::Assignment(name, _, _, _)) => {
-                            if !no_shadowing_check(&mut definitions, name) {
+                        Command::Binding(Binding::Assignment(name, _, _, _), _) => {
+                            if !no_shadowing_check(&mut definitions, name, span) {
                                 return false;
                             }
                         }
@@ -452,7 +731,7 @@ fn no_shadowing_logic(content: Vec<Command>, mut definitions: &mut Vec<String>)
                     }
                 }
             }
-            Command::Block(Block::If(_, blocks, el)) => {
+            Command::Block(Block::If(_, blocks, el), _) => {
                 let mut temp = blocks;
                 temp.push(el);
 
@@ -463,9 +742,9 @@ fn no_shadowing_logic(content: Vec<Command>, mut definitions: &mut Vec<String>)
                     }
                 }
             }
-            Command::Block(Block::ForRange(iter, _, _, vec, _)) => {
+            Command::Block(Block::ForRange(iter, _, _, vec, _), _) => {
                 let mut temp = definitions.clone();
-                if !no_shadowing_check(&mut temp, iter) {
+                if !no_shadowing_check(&mut temp, iter, span) {
                     return false;
                 }
 
@@ -473,7 +752,7 @@ fn no_shadowing_logic(content: Vec<Command>, mut definitions: &mut Vec<String>)
                     return false;
                 }
             }
-            Command::Block(Block::While(_, vec, _, _)) => {
+            Command::Block(Block::While(_, vec, _, _), _) => {
                 let mut temp = definitions.clone();
 
                 if !no_shadowing_logic(vec, &mut temp) {
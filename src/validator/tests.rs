@@ -4,21 +4,26 @@ mod test {
 
     #[test]
     fn no_forbidden_decs_check1() {
-        assert!(!no_forbidden_decs_check(Variable::Named(String::from(
-            "return_value"
-        ))));
+        assert!(!no_forbidden_decs_check(
+            Variable::Named(String::from("return_value")),
+            Span::default()
+        ));
     }
 
     #[test]
     fn no_forbidden_decs_check2() {
-        assert!(no_forbidden_decs_check(Variable::Named(String::from("a"))));
+        assert!(no_forbidden_decs_check(
+            Variable::Named(String::from("a")),
+            Span::default()
+        ));
     }
 
     #[test]
     fn no_forbidden_decs_check3() {
-        assert!(!no_forbidden_decs_check(Variable::Named(String::from(
-            "a'old"
-        ))));
+        assert!(!no_forbidden_decs_check(
+            Variable::Named(String::from("a'old")),
+            Span::default()
+        ));
     }
 
     #[test]
@@ -28,7 +33,8 @@ mod test {
 
         assert!(!no_shadowing_check(
             &mut defs,
-            Variable::Named(String::from("a"))
+            Variable::Named(String::from("a")),
+            Span::default()
         ));
     }
 
@@ -39,7 +45,8 @@ mod test {
 
         assert!(no_shadowing_check(
             &mut defs,
-            Variable::Named(String::from("b"))
+            Variable::Named(String::from("b")),
+            Span::default()
         ));
     }
 
@@ -50,7 +57,8 @@ mod test {
 
         assert!(no_shadowing_check(
             &mut defs,
-            Variable::Named(String::from("b"))
+            Variable::Named(String::from("b")),
+            Span::default()
         ));
 
         let mut temp = Vec::new();
@@ -66,20 +74,26 @@ mod test {
 
         let mut coms = Vec::new();
         let mut for1 = Vec::new();
-        for1.push(Command::Binding(Binding::Assignment(
-            Variable::Named(String::from("a")),
-            Type::I32,
-            Value::Unit,
-            false,
-        )));
-
-        coms.push(Command::Block(Block::ForRange(
-            Variable::Named(String::from("i")),
-            Value::Unit,
-            Value::Unit,
-            for1,
-            Bool::True,
-        )));
+        for1.push(Command::Binding(
+            Binding::Assignment(
+                Variable::Named(String::from("a")),
+                Type::I32,
+                Value::Unit,
+                false,
+            ),
+            Span::default(),
+        ));
+
+        coms.push(Command::Block(
+            Block::ForRange(
+                Variable::Named(String::from("i")),
+                Value::Unit,
+                Value::Unit,
+                for1,
+                Bool::True,
+            ),
+            Span::default(),
+        ));
 
         assert!(!no_shadowing_logic(coms, &mut defs));
     }
@@ -91,20 +105,26 @@ mod test {
 
         let mut coms = Vec::new();
         let mut for1 = Vec::new();
-        for1.push(Command::Binding(Binding::Assignment(
-            Variable::Named(String::from("b")),
-            Type::I32,
-            Value::Unit,
-            false,
-        )));
-
-        coms.push(Command::Block(Block::ForRange(
-            Variable::Named(String::from("i")),
-            Value::Unit,
-            Value::Unit,
-            for1,
-            Bool::True,
-        )));
+        for1.push(Command::Binding(
+            Binding::Assignment(
+                Variable::Named(String::from("b")),
+                Type::I32,
+                Value::Unit,
+                false,
+            ),
+            Span::default(),
+        ));
+
+        coms.push(Command::Block(
+            Block::ForRange(
+                Variable::Named(String::from("i")),
+                Value::Unit,
+                Value::Unit,
+                for1,
+                Bool::True,
+            ),
+            Span::default(),
+        ));
 
         assert!(no_shadowing_logic(coms, &mut defs));
     }
@@ -116,20 +136,26 @@ mod test {
 
         let mut coms = Vec::new();
         let mut for1 = Vec::new();
-        for1.push(Command::Binding(Binding::Assignment(
-            Variable::Named(String::from("b")),
-            Type::I32,
-            Value::Unit,
-            false,
-        )));
+        for1.push(Command::Binding(
+            Binding::Assignment(
+                Variable::Named(String::from("b")),
+                Type::I32,
+                Value::Unit,
+                false,
+            ),
+            Span::default(),
+        ));
 
-        coms.push(Command::Block(Block::ForRange(
-            Variable::Named(String::from("a")),
-            Value::Unit,
-            Value::Unit,
-            for1,
-            Bool::True,
-        )));
+        coms.push(Command::Block(
+            Block::ForRange(
+                Variable::Named(String::from("a")),
+                Value::Unit,
+                Value::Unit,
+                for1,
+                Bool::True,
+            ),
+            Span::default(),
+        ));
 
         assert!(!no_shadowing_logic(coms, &mut defs));
     }
@@ -142,30 +168,39 @@ mod test {
         let mut coms = Vec::new();
 
         let mut for2 = Vec::new();
-        for2.push(Command::Binding(Binding::Assignment(
-            Variable::Named(String::from("c")),
-            Type::I32,
-            Value::Unit,
-            false,
-        )));
+        for2.push(Command::Binding(
+            Binding::Assignment(
+                Variable::Named(String::from("c")),
+                Type::I32,
+                Value::Unit,
+                false,
+            ),
+            Span::default(),
+        ));
 
         let mut for1 = Vec::new();
 
-        for1.push(Command::Block(Block::ForRange(
-            Variable::Named(String::from("d")),
-            Value::Unit,
-            Value::Unit,
-            for2,
-            Bool::True,
-        )));
-
-        coms.push(Command::Block(Block::ForRange(
-            Variable::Named(String::from("c")),
-            Value::Unit,
-            Value::Unit,
-            for1,
-            Bool::True,
-        )));
+        for1.push(Command::Block(
+            Block::ForRange(
+                Variable::Named(String::from("d")),
+                Value::Unit,
+                Value::Unit,
+                for2,
+                Bool::True,
+            ),
+            Span::default(),
+        ));
+
+        coms.push(Command::Block(
+            Block::ForRange(
+                Variable::Named(String::from("c")),
+                Value::Unit,
+                Value::Unit,
+                for1,
+                Bool::True,
+            ),
+            Span::default(),
+        ));
 
         assert!(!no_shadowing_logic(coms, &mut defs));
     }
@@ -178,17 +213,23 @@ mod test {
         let mut coms = Vec::new();
 
         let mut if1 = Vec::new();
-        if1.push(Command::Binding(Binding::Assignment(
-            Variable::Named(String::from("a")),
-            Type::I32,
-            Value::Unit,
-            false,
-        )));
+        if1.push(Command::Binding(
+            Binding::Assignment(
+                Variable::Named(String::from("a")),
+                Type::I32,
+                Value::Unit,
+                false,
+            ),
+            Span::default(),
+        ));
 
         let mut ifg = Vec::new();
         ifg.push(if1);
 
-        coms.push(Command::Block(Block::If(Vec::new(), ifg, Vec::new())));
+        coms.push(Command::Block(
+            Block::If(Vec::new(), ifg, Vec::new()),
+            Span::default(),
+        ));
 
         assert!(!no_shadowing_logic(coms, &mut defs));
     }
@@ -201,17 +242,23 @@ mod test {
         let mut coms = Vec::new();
 
         let mut if1 = Vec::new();
-        if1.push(Command::Binding(Binding::Assignment(
-            Variable::Named(String::from("b")),
-            Type::I32,
-            Value::Unit,
-            false,
-        )));
+        if1.push(Command::Binding(
+            Binding::Assignment(
+                Variable::Named(String::from("b")),
+                Type::I32,
+                Value::Unit,
+                false,
+            ),
+            Span::default(),
+        ));
 
         let mut ifg = Vec::new();
         ifg.push(if1);
 
-        coms.push(Command::Block(Block::If(Vec::new(), ifg, Vec::new())));
+        coms.push(Command::Block(
+            Block::If(Vec::new(), ifg, Vec::new()),
+            Span::default(),
+        ));
 
         assert!(no_shadowing_logic(coms, &mut defs));
     }
@@ -224,25 +271,34 @@ mod test {
         let mut coms = Vec::new();
 
         let mut if1 = Vec::new();
-        if1.push(Command::Binding(Binding::Assignment(
-            Variable::Named(String::from("b")),
-            Type::I32,
-            Value::Unit,
-            false,
-        )));
+        if1.push(Command::Binding(
+            Binding::Assignment(
+                Variable::Named(String::from("b")),
+                Type::I32,
+                Value::Unit,
+                false,
+            ),
+            Span::default(),
+        ));
 
         let mut el = Vec::new();
-        el.push(Command::Binding(Binding::Assignment(
-            Variable::Named(String::from("a")),
-            Type::I32,
-            Value::Unit,
-            false,
-        )));
+        el.push(Command::Binding(
+            Binding::Assignment(
+                Variable::Named(String::from("a")),
+                Type::I32,
+                Value::Unit,
+                false,
+            ),
+            Span::default(),
+        ));
 
         let mut ifg = Vec::new();
         ifg.push(if1);
 
-        coms.push(Command::Block(Block::If(Vec::new(), ifg, el)));
+        coms.push(Command::Block(
+            Block::If(Vec::new(), ifg, el),
+            Span::default(),
+        ));
 
         assert!(!no_shadowing_logic(coms, &mut defs));
     }